@@ -1,22 +1,34 @@
 // src/app/manager.rs
-use crate::cert::verification::CertificateVerifier;
+use crate::cert::ocsp;
+use crate::cert::profiles::TemplateContext;
+use crate::cert::revocation::{
+    self, EntryStatus, RevocationDb, RevocationReason, RevocationTarget,
+};
+use crate::cert::verification::{CertificateVerifier, ExpectedFile, HostVerification};
 use crate::cert::{
-    CertificateConfig, CertificateOperations, CertificateType, ClusterEndpoints,
-    ControllerCertGenerator, ControllerManagerGenerator, NodeCertGenerator,
-    ServiceAccountGenerator,
+    etcd::ETCD_DIR, verify_chain, CertificateOperations, ClusterEndpoints, ControllerCertGenerator,
+    ControllerManagerGenerator, EtcdCertGenerator, NodeCertGenerator, ServiceAccountGenerator,
+    WorkerNode,
 };
 use crate::config::{ClusterConfig, ConfigEditor};
-use crate::discovery::{CertificateDiscovery, CertificateInfo, NodeTrustInfo};
+use crate::discovery::probe::{self, Component, EndpointProbe, ProbeTarget};
+use crate::discovery::{kubeadm, CertificateDiscovery, CertificateInfo, NodeTrustInfo};
 use crate::kubeconfig::{EncryptionConfigGenerator, KubeConfigGenerator};
 use crate::metrics::MetricsCollector;
+use crate::remote::{
+    self, BackupManifest, CheckStatus, DistributionJob, DistributionProgress, HostPreflight,
+    HostState, IntegrityCheck, PreflightGate, PreflightNode, Transfer, TransferState, Transport,
+};
+use crate::state::{self, CertificateRecord, StateStore};
 use crate::types::{
-    ActiveSection, AppMode, CertTracker, ConfirmationCallback, ConfirmationDialog, ScrollDirection,
+    ActiveSection, AppMode, CertTracker, CertificateStatus, ConfirmationCallback, ConfirmationDialog,
+    HostDistribution, RestorePicker, RevocationPicker, RevokeStep, ScrollDirection,
 };
 use crate::ui;
 use crate::utils::logging::Logger;
 use crate::web::WebServerState;
 
-use chrono::Local;
+use chrono::{Local, Utc};
 use crossterm::event::KeyCode;
 use glob::glob;
 use ratatui::{
@@ -24,9 +36,8 @@ use ratatui::{
     text::{Line, Span},
 };
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
-use std::process::Command;
 use std::sync::{Arc, RwLock};
 use std::{fs, io, path::PathBuf};
 use utoipa::ToSchema;
@@ -59,6 +70,17 @@ pub struct CertManager {
     pub kubeconfig_generator: Option<KubeConfigGenerator>,
     pub encryption_generator: Option<EncryptionConfigGenerator>,
     pub trust_store: Option<HashMap<String, NodeTrustInfo>>,
+    /// Per-host report of the last remote verification.
+    pub verification: Vec<HostVerification>,
+    /// Endpoints of the last TLS probe.
+    pub probes: Vec<EndpointProbe>,
+    /// Per-node report of the last preflight run.
+    pub preflight: Vec<HostPreflight>,
+    pub revocation_picker: Option<RevocationPicker>,
+    pub restore_picker: Option<RestorePicker>,
+    distribution: Option<DistributionJob>,
+    /// Dialog opened once the running distribution placed every file.
+    distribution_followup: Option<ConfirmationDialog>,
 }
 
 #[derive(Clone)]
@@ -116,6 +138,11 @@ pub struct CertStatus {
     pub status: String,
     #[schema(example = "2024-01-01T00:00:00Z")]
     pub last_updated: Option<String>,
+    /// SHA-256 found on the node by the last post-copy check or audit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum_matches: Option<bool>,
 }
 
 impl Default for CertManager {
@@ -144,7 +171,12 @@ impl CertManager {
             "Exit".to_string(),
             "Distribute Pending Certificates".to_string(),
             "Save Certificate Status".to_string(),
+            "Revoke Certificate".to_string(),
+            "Generate etcd Certs".to_string(),
+            "Generate Front Proxy Certs".to_string(),
             "Automate all".to_string(),
+            "Restore Backup".to_string(),
+            "Audit Distributed Files".to_string(),
         ];
 
         Self {
@@ -172,6 +204,13 @@ impl CertManager {
             kubeconfig_generator: None,
             encryption_generator: None,
             trust_store: None,
+            verification: Vec::new(),
+            probes: Vec::new(),
+            preflight: Vec::new(),
+            revocation_picker: None,
+            restore_picker: None,
+            distribution: None,
+            distribution_followup: None,
         }
     }
 
@@ -200,7 +239,15 @@ impl CertManager {
                 "Distribute Pending Certificates".to_string(),
                 "Save Certificate Status".to_string(),
                 "Import Existing Certificates".to_string(), // Discover
+                "Revoke Certificate".to_string(),
+                "Generate etcd Certs".to_string(),
+                "Generate Front Proxy Certs".to_string(),
                 "Automate all".to_string(),
+                "Restore Backup".to_string(),
+                "Audit Distributed Files".to_string(),
+                "Import kubeadm PKI".to_string(),
+                "Probe TLS Endpoints".to_string(),
+                "Run Preflight Checks".to_string(),
             ],
             mode: AppMode::Normal,
             debug,
@@ -220,6 +267,13 @@ impl CertManager {
             kubeconfig_generator: None,
             encryption_generator: None,
             trust_store: None,
+            verification: Vec::new(),
+            probes: Vec::new(),
+            preflight: Vec::new(),
+            revocation_picker: None,
+            restore_picker: None,
+            distribution: None,
+            distribution_followup: None,
         };
         manager.init_cert_ops();
         manager
@@ -245,6 +299,7 @@ impl CertManager {
             self.config.control_plane.clone(),
             PathBuf::from("kubeconfig"),
             PathBuf::from("certs/kubernetes-ca/ca-chain.crt"),
+            PathBuf::from("certs"),
         ));
 
         self.encryption_generator = Some(EncryptionConfigGenerator::new(PathBuf::from(
@@ -281,14 +336,14 @@ impl CertManager {
 
         // Clone the values we need upfront
         let control_plane = self.config.control_plane.clone();
-        let worker_nodes = self.config.worker_nodes.clone();
 
         // Generate admin kubeconfig
         {
             let generator = self.kubeconfig_generator.as_ref().unwrap();
             generator.generate_kubeconfig("admin", "default-admin")?;
         }
-        self.track_kubeconfig("admin", &control_plane);
+        self.cert_tracker
+            .add_certificate("kubeconfig-admin", "kubeconfig/admin.conf", Vec::new());
 
         // Generate controller-manager kubeconfig
         {
@@ -305,16 +360,20 @@ impl CertManager {
         }
         self.track_kubeconfig("scheduler", &control_plane);
 
-        // Generate node kubeconfigs
-        for (i, node) in worker_nodes.iter().enumerate() {
-            let node_name = format!("node-{}", i + 1);
-            let credential_name = format!("system:node:worker-node-{}", i + 1);
-            {
-                let generator = self.kubeconfig_generator.as_ref().unwrap();
-                generator.generate_kubeconfig(&node_name, &credential_name)?;
-            }
-            self.track_kubeconfig(&node_name, node);
+        // Generate kube-proxy kubeconfig for every node
+        {
+            let generator = self.kubeconfig_generator.as_ref().unwrap();
+            generator.generate_kubeconfig("kube-proxy", "system:kube-proxy")?;
         }
+        let all_hosts = self.get_all_hosts();
+        self.cert_tracker.add_certificate(
+            "kubeconfig-kube-proxy",
+            "kubeconfig/kube-proxy.conf",
+            all_hosts,
+        );
+
+        // Generate kubelet kubeconfigs
+        self.generate_worker_kubeconfigs()?;
 
         self.log("Kubeconfig generation completed successfully");
         Ok(())
@@ -346,12 +405,21 @@ impl CertManager {
     fn create_certificate_operations(&self) -> io::Result<CertificateOperations> {
         Ok(CertificateOperations::new(
             Box::new(OperationsLogger::new(self.log_sender.clone(), self.debug)),
-            self.config.remote_dir.clone(),
-            self.config.remote_user.clone(),
-            self.config.ssh_key_path.clone(),
+            self.config.remote_layout(),
+            self.transport(),
+            self.config.key_algorithms.clone(),
+            self.config.revocation.ocsp_url.clone(),
+            self.config.profiles.clone(),
+            self.config.permissions.clone(),
         ))
     }
 
+    pub fn transport(&self) -> Arc<dyn Transport> {
+        self.config
+            .transport
+            .open(&self.config.ssh_options())
+    }
+
     pub fn open_web_ui(&mut self) {
         // Create a smaller scope for the web_state read lock
         let url = {
@@ -377,9 +445,12 @@ impl CertManager {
     pub fn init_cert_ops(&mut self) {
         self.cert_ops = Some(CertificateOperations::new(
             Box::new(OperationsLogger::new(self.log_sender.clone(), self.debug)),
-            self.config.remote_dir.clone(),
-            self.config.remote_user.clone(),
-            self.config.ssh_key_path.clone(),
+            self.config.remote_layout(),
+            self.transport(),
+            self.config.key_algorithms.clone(),
+            self.config.revocation.ocsp_url.clone(),
+            self.config.profiles.clone(),
+            self.config.permissions.clone(),
         ));
     }
 
@@ -405,16 +476,22 @@ impl CertManager {
         // 4. Generate Control Plane certificates
         self.generate_control_plane_certs()?;
 
-        // 5. Generate Worker Node certificates
+        // 5. Generate etcd certificates
+        self.generate_etcd_certs()?;
+
+        // 6. Generate front-proxy certificates
+        self.generate_front_proxy_certs()?;
+
+        // 7. Generate Worker Node certificates
         self.generate_worker_node_certs()?;
 
-        // 6. Generate Service Account Keys
+        // 8. Generate Service Account Keys
         self.generate_service_account_keys()?;
 
-        // 7. Generate Kubeconfigs
+        // 9. Generate Kubeconfigs
         self.generate_all_kubeconfigs()?;
 
-        // 8. Generate Encryption Config
+        // 10. Generate Encryption Config
         self.generate_encryption_config()?;
 
         // Distribute everything at once
@@ -448,6 +525,17 @@ impl CertManager {
         generator.generate_api_server_cert()?;
         generator.generate_controller_manager_cert()?;
         generator.generate_scheduler_cert()?;
+        generator.generate_admin_cert()?;
+        generator.generate_kube_proxy_cert()?;
+
+        // The admin credential never leaves this machine; kube-proxy runs on every node
+        self.cert_tracker
+            .add_certificate("admin", "certs/admin/admin.crt", Vec::new());
+        self.cert_tracker.add_certificate(
+            "kube-proxy",
+            "certs/kube-proxy/kube-proxy.crt",
+            self.get_all_hosts(),
+        );
 
         self.generate_kubelet_client_cert()?;
         self.generate_service_account_keys()?;
@@ -456,6 +544,112 @@ impl CertManager {
         Ok(())
     }
 
+    /// Generates the etcd PKI and queues it for distribution to the etcd
+    /// members, e.g. under `<remote_dir>/etcd/` with the kubeadm layout.
+    pub fn generate_etcd_certs(&mut self) -> io::Result<()> {
+        self.set_current_operation("Generating etcd certificates");
+        let control_plane = self.config.control_plane.clone();
+        let settings = self.config.etcd.clone();
+        let members = settings.members_or_default(&control_plane);
+
+        let cert_ops = self.get_cert_ops();
+        let mut generator = EtcdCertGenerator::new(settings, cert_ops);
+        generator.generate_all(&members)?;
+
+        let member_hosts: Vec<String> = members.iter().map(|m| m.address().to_string()).collect();
+
+        // kube-apiserver on the control plane also needs the etcd trust anchor
+        let mut ca_hosts = member_hosts.clone();
+        if !ca_hosts.contains(&control_plane) {
+            ca_hosts.push(control_plane.clone());
+        }
+        self.cert_tracker
+            .add_certificate("etcd-ca", &format!("{}/ca.crt", ETCD_DIR), ca_hosts);
+
+        for member in &members {
+            for kind in ["server", "peer"] {
+                let cert_type = format!("etcd-{}-{}", kind, member.name);
+                let base = format!("{}/{}/{}", ETCD_DIR, member.name, kind);
+                self.cert_tracker.add_certificate(
+                    &cert_type,
+                    &format!("{}.crt", base),
+                    vec![member.address().to_string()],
+                );
+                self.cert_tracker.add_certificate(
+                    &format!("{}-key", cert_type),
+                    &format!("{}.key", base),
+                    vec![member.address().to_string()],
+                );
+            }
+        }
+
+        let clients = [
+            (
+                "etcd-healthcheck-client",
+                format!("{}/healthcheck-client/healthcheck-client", ETCD_DIR),
+                member_hosts,
+            ),
+            (
+                "apiserver-etcd-client",
+                "certs/apiserver-etcd-client/apiserver-etcd-client".to_string(),
+                vec![control_plane],
+            ),
+        ];
+        for (cert_type, base, hosts) in clients {
+            self.cert_tracker
+                .add_certificate(cert_type, &format!("{}.crt", base), hosts.clone());
+            self.cert_tracker.add_certificate(
+                &format!("{}-key", cert_type),
+                &format!("{}.key", base),
+                hosts,
+            );
+        }
+
+        self.log(&format!(
+            "etcd certificates generated for {} member(s)",
+            members.len()
+        ));
+        Ok(())
+    }
+
+    /// Generates the self-signed front-proxy CA and the client certificate
+    /// kube-apiserver presents to aggregated API servers.
+    pub fn generate_front_proxy_certs(&mut self) -> io::Result<()> {
+        self.set_current_operation("Generating front-proxy certificates");
+        let control_plane = self.config.control_plane.clone();
+
+        let cert_ops = self.get_cert_ops();
+        cert_ops.generate_from_profile(
+            "front-proxy-ca",
+            &TemplateContext::new(),
+            &[&control_plane],
+        )?;
+        cert_ops.generate_from_profile(
+            "front-proxy-client",
+            &TemplateContext::new(),
+            &[&control_plane],
+        )?;
+
+        self.cert_tracker.add_certificate(
+            "front-proxy-ca",
+            "certs/front-proxy-ca/ca.crt",
+            vec![control_plane.clone()],
+        );
+        self.cert_tracker.add_certificate(
+            "front-proxy-client",
+            "certs/front-proxy-client/front-proxy-client.crt",
+            vec![control_plane.clone()],
+        );
+        self.cert_tracker.add_certificate(
+            "front-proxy-client-key",
+            "certs/front-proxy-client/front-proxy-client.key",
+            vec![control_plane],
+        );
+
+        self.log("Front-proxy certificates generated successfully");
+        Ok(())
+    }
+
     pub fn generate_root_ca(&mut self) -> io::Result<()> {
         self.set_current_operation("Generating Root CA");
         let control_plane = self.config.control_plane.clone();
@@ -517,29 +711,9 @@ impl CertManager {
         self.set_current_operation("Generating Kubernetes CA");
         let control_plane = self.config.control_plane.clone();
 
-        let config = CertificateConfig {
-            cert_type: CertificateType::KubernetesCA,
-            common_name: "kubernetes-ca".to_string(),
-            organization: Some("Kubernetes".to_string()),
-            validity_days: 3650,
-            key_size: 2048,
-            output_dir: PathBuf::from("certs/kubernetes-ca"),
-            alt_names: vec![],
-            key_usage: vec![
-                "critical".to_string(),
-                "keyCertSign".to_string(),
-                "cRLSign".to_string(),
-            ],
-            extended_key_usage: vec![],
-            country: Some("US".to_string()),
-            state: Some("Columbia".to_string()),
-            locality: Some("Columbia".to_string()),
-        };
-
-        self.get_cert_ops().generate_cert(
-            "root-ca",
-            "certs/root-ca",
-            &config,
+        self.get_cert_ops().generate_from_profile(
+            "kubernetes-ca",
+            &TemplateContext::new().with_control_plane(&control_plane),
             &[&control_plane],
         )?;
 
@@ -565,29 +739,9 @@ impl CertManager {
         self.set_current_operation("Generating Kubelet Client Certificate");
         let control_plane = self.config.control_plane.clone();
 
-        let config = CertificateConfig {
-            cert_type: CertificateType::KubeletClient,
-            common_name: "kube-apiserver-kubelet-client".to_string(),
-            organization: Some("system:masters".to_string()),
-            validity_days: 375,
-            key_size: 2048,
-            output_dir: PathBuf::from("certs/kube-apiserver-kubelet-client"),
-            alt_names: vec![],
-            key_usage: vec![
-                "critical".to_string(),
-                "digitalSignature".to_string(),
-                "keyEncipherment".to_string(),
-            ],
-            extended_key_usage: vec!["clientAuth".to_string()],
-            country: Some("US".to_string()),
-            state: Some("Columbia".to_string()),
-            locality: Some("Columbia".to_string()),
-        };
-
-        self.get_cert_ops().generate_cert(
+        self.get_cert_ops().generate_from_profile(
             "kube-apiserver-kubelet-client",
-            "certs/kubernetes-ca",
-            &config,
+            &TemplateContext::new().with_control_plane(&control_plane),
             &[&control_plane],
         )?;
 
@@ -733,9 +887,7 @@ impl CertManager {
 
         let mut verifier = CertificateVerifier::new(
             Box::new(OperationsLogger::new(self.log_sender.clone(), self.debug)),
-            self.config.remote_user.clone(),
-            self.config.remote_dir.clone(),
-            self.config.ssh_key_path.clone(),
+            self.transport(),
         );
 
         // Clone the certificates to avoid borrowing issues
@@ -757,14 +909,16 @@ impl CertManager {
                 continue;
             }
 
+            // Private keys are checked against their certificates elsewhere
+            if cert.path.ends_with(".key") {
+                continue;
+            }
+
             // Determine CA chain based on certificate type
-            let ca_chain = match cert.cert_type.as_str() {
-                "root-ca" | "kubernetes-ca" => None, // CA certs don't need verification against another CA
-                _ => Some("certs/kubernetes-ca/ca-chain.crt"), // Default CA chain
-            };
+            let ca_chain = self.ca_chain_for(&cert.cert_type);
 
             // Verify the certificate
-            match verifier.verify_certificate(&cert.path, ca_chain) {
+            match verifier.verify_certificate(&cert.path, ca_chain.as_deref()) {
                 Ok(_) => {
                     self.cert_tracker.mark_verified(&cert.cert_type, true);
                     self.log(&format!("{} verified successfully", cert.cert_type));
@@ -792,10 +946,20 @@ impl CertManager {
             }
         }
 
-        // Verify remote certificates for all hosts
-        let all_hosts = self.get_all_hosts();
-        match verifier.verify_remote_certificates(&all_hosts) {
-            Ok(_) => self.log("Remote certificate verification completed successfully"),
+        // Verify what the hosts hold, at the paths of the remote layout
+        let expected = self.expected_files();
+        let addresses = self.host_addresses(&expected);
+        match verifier.verify_remote_certificates(&expected, &addresses) {
+            Ok(reports) => {
+                self.record_verification(&reports);
+                let failed = reports.iter().filter(|host| !host.valid).count();
+                self.log(&format!(
+                    "Remote verification: {} of {} host(s) valid",
+                    reports.len() - failed,
+                    reports.len()
+                ));
+                self.verification = reports;
+            }
             Err(e) => self.log(&format!("Remote certificate verification failed: {}", e)),
         }
 
@@ -803,25 +967,178 @@ impl CertManager {
         Ok(())
     }
 
+    /// Entries of the trust panel: trust store nodes, then verified hosts,
+    /// probed endpoints and preflight reports.
+    pub fn trust_info_len(&self) -> usize {
+        self.trust_store.as_ref().map_or(0, |store| store.len())
+            + self.verification.len()
+            + self.probes.len()
+            + self.preflight.len()
+    }
+
+    /// Local CA chain a certificate of `cert_type` is verified against; CA
+    /// certificates have none.
+    fn ca_chain_for(&self, cert_type: &str) -> Option<String> {
+        match cert_type {
+            "root-ca" | "kubernetes-ca" | "etcd-ca" | "front-proxy-ca" => None,
+            t if t.starts_with("etcd-") || t == "apiserver-etcd-client" => {
+                Some(format!("{}/ca-chain.crt", self.config.etcd.ca_dir()))
+            }
+            "front-proxy-client" => Some("certs/front-proxy-ca/ca.crt".to_string()),
+            _ => Some("certs/kubernetes-ca/ca-chain.crt".to_string()), // Default CA chain
+        }
+    }
+
+    /// Every distributed certificate, as installed on each of its hosts by
+    /// the remote layout. Keys, kubeconfigs and other files are left out.
+    fn installed_certificates(&self) -> Vec<Transfer> {
+        let Some(cert_ops) = self.cert_ops.as_ref() else {
+            return Vec::new();
+        };
+        let distributed: Vec<&CertificateStatus> = self
+            .cert_tracker
+            .certificates
+            .iter()
+            .filter(|cert| cert.distributed.is_some() && cert.path.ends_with(".crt"))
+            .collect();
+        host_files(cert_ops, &distributed)
+    }
+
+    /// Every distributed file, as installed on each of its hosts by the
+    /// remote layout. Certificates carry their CA chain and the key
+    /// installed next to them on the same host.
+    fn expected_files(&self) -> Vec<ExpectedFile> {
+        let Some(cert_ops) = self.cert_ops.as_ref() else {
+            return Vec::new();
+        };
+        let distributed: Vec<&CertificateStatus> = self
+            .cert_tracker
+            .certificates
+            .iter()
+            .filter(|cert| cert.distributed.is_some())
+            .collect();
+        let files = host_files(cert_ops, &distributed);
+
+        files
+            .iter()
+            .map(|file| {
+                let certificate = file.source.strip_suffix(".crt");
+                let key = certificate.and_then(|stem| {
+                    let key_source = format!("{}.key", stem);
+                    files
+                        .iter()
+                        .find(|key| key.host == file.host && key.source == key_source)
+                        .cloned()
+                });
+                ExpectedFile {
+                    file: file.clone(),
+                    ca_chain: certificate.and_then(|_| self.ca_chain_for(&file.cert_type)),
+                    key,
+                }
+            })
+            .collect()
+    }
+
+    /// Names serving certificates on each host must cover: the address it
+    /// is reached at and, for workers, the hostname their kubelet serves
+    /// under.
+    fn host_addresses(&self, files: &[ExpectedFile]) -> BTreeMap<String, Vec<String>> {
+        let mut addresses = BTreeMap::new();
+        for expected in files {
+            let host = &expected.file.host;
+            addresses.entry(host.clone()).or_insert_with(|| {
+                let mut names = vec![host.clone()];
+                if self.config.worker_nodes.contains(host) {
+                    names.extend(self.config.node_hostnames.get(host).cloned());
+                }
+                names
+            });
+        }
+        addresses
+    }
+
+    /// Carries a verification report over to the tracker and trust store:
+    /// checksums found on the hosts, certificates failing a check, and
+    /// whether each host's files conform to the permission policy.
+    fn record_verification(&mut self, reports: &[HostVerification]) {
+        for host in reports {
+            for file in &host.files {
+                if let Some(check) = &file.checksum {
+                    self.cert_tracker
+                        .record_checksum(&file.cert_type, &host.host, check);
+                }
+                if !file.valid() {
+                    self.cert_tracker.mark_verified(&file.cert_type, false);
+                }
+            }
+        }
+
+        if let Some(store) = self.trust_store.as_mut() {
+            for host in reports {
+                if let Some(trust_info) = store.get_mut(&host.host) {
+                    if let Some(valid) = host.permissions_valid() {
+                        trust_info.permissions_valid = valid;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Loads the tracker from the state database. A database that tracks
+    /// nothing yet first imports certificate_status.json, if there is one.
     pub fn load_certificate_status(&mut self) -> io::Result<()> {
-        let status_path = PathBuf::from("certificate_status.json");
-        if status_path.exists() {
-            let status_str = fs::read_to_string(status_path)?;
-            self.cert_tracker = serde_json::from_str(&status_str)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-            self.log("Loaded certificate status from file");
+        let mut store = StateStore::open(Path::new(state::DEFAULT_PATH))?;
+        let imported = store.import_legacy(Path::new(state::LEGACY_STATUS_PATH), |tracker| {
+            self.tracked_certificates(tracker)
+        })?;
+        if let Some(tracker) = imported {
+            self.log(&format!(
+                "Imported {} certificates from {} into {}",
+                tracker.certificates.len(),
+                state::LEGACY_STATUS_PATH,
+                state::DEFAULT_PATH
+            ));
+        }
+
+        let tracker = store.load_tracker()?;
+        if !tracker.certificates.is_empty() {
+            self.log("Loaded certificate status from the state database");
         }
+        self.cert_tracker = tracker;
         Ok(())
     }
 
-    pub fn save_certificate_status(&self) -> io::Result<()> {
-        let status_path = PathBuf::from("certificate_status.json");
-        let status_str = serde_json::to_string_pretty(&self.cert_tracker)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        fs::write(status_path, status_str)?;
+    /// Saves the tracker to the state database, along with the history
+    /// gathered since the last save: certificates issued or adopted, host
+    /// events and the last verification.
+    pub fn save_certificate_status(&mut self) -> io::Result<()> {
+        let mut store = StateStore::open(Path::new(state::DEFAULT_PATH))?;
+        let mut certificates = self
+            .cert_ops
+            .as_ref()
+            .map(|cert_ops| cert_ops.issued().to_vec())
+            .unwrap_or_default();
+        certificates.extend(self.tracked_certificates(&self.cert_tracker));
+        store.save(&self.cert_tracker, &certificates, &self.verification)?;
+
+        self.cert_tracker.events.clear();
+        if let Some(cert_ops) = self.cert_ops.as_mut() {
+            cert_ops.clear_issued();
+        }
         Ok(())
     }
 
+    /// The tracked files that are certificates, e.g. adopted by an import
+    /// rather than issued here.
+    fn tracked_certificates(&self, tracker: &CertTracker) -> Vec<CertificateRecord> {
+        let layout = self.config.remote_layout();
+        tracker
+            .certificates
+            .iter()
+            .filter_map(|cert| CertificateRecord::read(&layout.source(&cert.path), None, None).ok())
+            .collect()
+    }
+
     pub fn get_certificate_status_info(&self) -> Vec<Line> {
         if self.cert_tracker.certificates.is_empty() {
             return vec![Line::from(vec![Span::styled(
@@ -890,102 +1207,13 @@ impl CertManager {
             .collect()
     }
 
-    fn generate_kubeconfig(&mut self, config_name: &str, credential_name: &str) -> io::Result<()> {
-        self.log(&format!("Generating kubeconfig for {}", config_name));
-
-        // Create directory if it doesn't exist
-        fs::create_dir_all("kubeconfig")?;
-
-        let kubeconfig_path = format!("kubeconfig/{}.conf", config_name);
-
-        // Get API server endpoint
-        let api_server = format!("https://{}:6443", self.config.control_plane);
-
-        // Create kubeconfig using kubectl
-        let output = Command::new("kubectl")
-            .args(&[
-                "config",
-                "set-cluster",
-                "kubernetes",
-                "--certificate-authority=kubernetes/ca.crt",
-                &format!("--server={}", api_server),
-                &format!("--kubeconfig={}", kubeconfig_path),
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                String::from_utf8_lossy(&output.stderr),
-            ));
-        }
-
-        // Set credentials
-        let output = Command::new("kubectl")
-            .args(&[
-                "config",
-                "set-credentials",
-                credential_name,
-                &format!("--client-certificate={}/{}.crt", config_name, config_name),
-                &format!("--client-key={}/{}.key", config_name, config_name),
-                &format!("--kubeconfig={}", kubeconfig_path),
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                String::from_utf8_lossy(&output.stderr),
-            ));
-        }
-
-        // Set context
-        let output = Command::new("kubectl")
-            .args(&[
-                "config",
-                "set-context",
-                "default",
-                "--cluster=kubernetes",
-                &format!("--user={}", credential_name),
-                &format!("--kubeconfig={}", kubeconfig_path),
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                String::from_utf8_lossy(&output.stderr),
-            ));
-        }
-
-        // Use context
-        let output = Command::new("kubectl")
-            .args(&[
-                "config",
-                "use-context",
-                "default",
-                &format!("--kubeconfig={}", kubeconfig_path),
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                String::from_utf8_lossy(&output.stderr),
-            ));
-        }
-
-        self.log(&format!("Generated kubeconfig: {}", kubeconfig_path));
-        Ok(())
-    }
-
     pub fn save_config(&self) -> io::Result<()> {
         let config_path = PathBuf::from("cluster_config.json");
         self.config.save_to_file(config_path.to_str().unwrap())
     }
 
     pub fn get_status_info(&self) -> Vec<Line> {
-        vec![
+        let mut lines = vec![
             Line::from(vec![
                 Span::styled("Current Operation: ", ui::STATUS_LABEL_STYLE),
                 Span::styled(
@@ -1007,33 +1235,54 @@ impl CertManager {
                 Span::styled("SSH Key: ", ui::STATUS_LABEL_STYLE),
                 Span::styled(&self.config.ssh_key_path, ui::LOG_DEBUG_STYLE),
             ]),
-        ]
+        ];
+
+        if let Some(progress) = self.distribution_progress() {
+            let failed = progress.count(TransferState::Failed) + progress.count(TransferState::Skipped);
+            lines.push(Line::from(vec![
+                Span::styled("Distribution: ", ui::STATUS_LABEL_STYLE),
+                Span::styled(
+                    format!(
+                        "{}/{} placed, {} failed{}",
+                        progress.count(TransferState::Placed),
+                        progress.total(),
+                        failed,
+                        if progress.is_finished() { "" } else { " (running)" }
+                    ),
+                    if failed > 0 {
+                        ui::LOG_ERROR_STYLE
+                    } else {
+                        ui::LOG_SUCCESS_STYLE
+                    },
+                ),
+            ]));
+        }
+
+        lines
     }
 
     // Update handle_confirmation
     pub fn handle_confirmation(&mut self, confirmed: bool) -> io::Result<()> {
         if let Some(dialog) = self.confirmation_dialog.take() {
-            let mut cert_ops = self.create_certificate_operations()?;
+            let cert_ops = self.create_certificate_operations()?;
             match dialog.callback {
                 ConfirmationCallback::RootCA => {
                     if confirmed {
                         let control_plane = self.config.control_plane.clone();
-
-                        if let Err(e) =
-                            cert_ops.copy_to_k8s_paths("certs/root-ca/ca.crt", &control_plane)
-                        {
-                            self.log(&format!("Failed to distribute Root CA certificates: {}", e));
-                        } else {
-                            self.log("Root CA certificates distributed successfully");
-                            // After successful distribution, prompt for chain distribution
-                            self.confirmation_dialog = Some(ConfirmationDialog {
+                        let transfers = host_transfers(
+                            &cert_ops,
+                            "root-ca",
+                            "certs/root-ca/ca.crt",
+                            &[control_plane],
+                        );
+                        // Chain certificates are offered once the Root CA is in place
+                        if self.distribute(transfers, "Distributing Root CA") {
+                            self.distribution_followup = Some(ConfirmationDialog {
                                 message:
                                     "Do you want to create and distribute CA chain certificates?"
                                         .to_string(),
                                 callback: ConfirmationCallback::CAChain,
                             });
-                            self.cert_tracker.mark_distributed("Root CA");
-                            self.mode = AppMode::Confirmation;
                         }
                     } else {
                         self.log("Distribution of Root CA certificates was canceled by the user.");
@@ -1041,25 +1290,14 @@ impl CertManager {
                 }
                 ConfirmationCallback::KubernetesCA => {
                     if confirmed {
-                        let all_hosts = self.get_all_hosts();
-                        let mut success = true;
-
-                        self.cert_tracker.mark_verified("Kubernetes CA", false);
-                        for host in &all_hosts {
-                            if let Err(e) =
-                                cert_ops.copy_to_k8s_paths("certs/kubernetes-ca/ca.crt", host)
-                            {
-                                self.log(&format!(
-                                    "Failed to distribute Kubernetes CA certificates to {}: {}",
-                                    host, e
-                                ));
-                                success = false;
-                            }
-                        }
-                        if success {
-                            self.cert_tracker.mark_distributed("Kubernetes CA");
-                            self.cert_tracker.mark_verified("Kubernetes CA", true);
-                            self.log("Kubernetes CA certificates distributed successfully");
+                        let transfers = host_transfers(
+                            &cert_ops,
+                            "ca.crt",
+                            "certs/kubernetes-ca/ca.crt",
+                            &self.get_all_hosts(),
+                        );
+                        if self.distribute(transfers, "Distributing Kubernetes CA") {
+                            self.cert_tracker.mark_verified("ca.crt", false);
                         }
                     } else {
                         self.log(
@@ -1069,27 +1307,13 @@ impl CertManager {
                 }
                 ConfirmationCallback::CAChain => {
                     if confirmed {
-                        let hosts = self.get_all_hosts();
-                        let mut success = true;
-
-                        for host in &hosts {
-                            if let Err(e) =
-                                cert_ops.copy_to_k8s_paths("certs/kubernetes-ca/ca-chain.crt", host)
-                            {
-                                self.log(&format!(
-                                    "Failed to distribute CA chain certificates to {}: {}",
-                                    host, e
-                                ));
-                                success = false;
-                            }
-                        }
-
-                        if success {
-                            self.log("CA chain certificates created and distributed successfully");
-                            self.cert_tracker.mark_distributed("ca-chain");
-                        } else {
-                            self.log("Failed to distribute some CA chain certificates");
-                        }
+                        let transfers = host_transfers(
+                            &cert_ops,
+                            "ca-chain",
+                            "certs/kubernetes-ca/ca-chain.crt",
+                            &self.get_all_hosts(),
+                        );
+                        self.distribute(transfers, "Distributing CA Chain");
                     } else {
                         self.log("CA chain certificate distribution was canceled by the user.");
                     }
@@ -1106,6 +1330,21 @@ impl CertManager {
                         self.log("Automation cancelled by user");
                     }
                 }
+                ConfirmationCallback::Revoke { ca, serial, reason } => {
+                    if confirmed {
+                        self.set_current_operation(&format!(
+                            "Revoking {} issued by {}",
+                            serial, ca
+                        ));
+                        if let Err(e) =
+                            self.revoke_certificate(&RevocationTarget::Serial(serial), reason)
+                        {
+                            self.log(&format!("Revocation failed: {}", e));
+                        }
+                    } else {
+                        self.log("Revocation cancelled by user");
+                    }
+                }
                 ConfirmationCallback::VerifyChains => {
                     if confirmed {
                         self.log("Starting verification of distributed certificates...");
@@ -1115,67 +1354,23 @@ impl CertManager {
                         }
                     }
                 }
+                ConfirmationCallback::Restore { host, generation } => {
+                    if confirmed {
+                        self.set_current_operation(&format!(
+                            "Restoring backup {} on {}",
+                            generation, host
+                        ));
+                        if let Err(e) = self.restore_backup(&host, &generation) {
+                            self.log(&format!("Restore failed: {}", e));
+                        }
+                    } else {
+                        self.log("Restore cancelled by user");
+                    }
+                }
                 ConfirmationCallback::DistributePending => {
                     if confirmed {
                         self.mode = AppMode::Normal;
-                        let pending_certs: Vec<(String, String, Vec<String>)> = self
-                            .cert_tracker
-                            .get_undistributed()
-                            .iter()
-                            .map(|cert| {
-                                (
-                                    cert.cert_type.clone(),
-                                    cert.path.clone(),
-                                    cert.hosts.clone(),
-                                )
-                            })
-                            .collect();
-
-                        self.log(&format!("Distributing {:?} ", pending_certs));
-                        if pending_certs.is_empty() {
-                            self.log("No certificates pending distribution");
-                        } else {
-                            for (cert_type, path, hosts) in pending_certs {
-                                self.log(&format!("Distributing {} certificate...", cert_type));
-
-                                let mut cert_success = true;
-
-                                if cert_type == "root-ca" {
-                                    continue;
-                                }
-
-                                for host in hosts {
-                                    self.log(&format!("Distributing to host: {}", host));
-                                    let source_path = if cert_type.starts_with("kubeconfig-") {
-                                        format!("{}", path)
-                                    } else if cert_type == "encryption-config" {
-                                        "encryption-config.yaml".to_string()
-                                    } else {
-                                        format!("{}", path)
-                                    };
-
-                                    match cert_ops.copy_to_k8s_paths(&source_path, &host) {
-                                        Ok(_) => {
-                                            self.log(&format!(
-                                                "Successfully distributed {} to {}",
-                                                cert_type, host
-                                            ));
-                                        }
-                                        Err(e) => {
-                                            self.log(&format!(
-                                                "Failed to distribute {} to {}: {}",
-                                                cert_type, host, e
-                                            ));
-                                            cert_success = false;
-                                        }
-                                    }
-                                }
-
-                                if cert_success {
-                                    self.cert_tracker.mark_distributed(&cert_type);
-                                }
-                            }
-                        }
+                        self.start_distribution(&cert_ops);
                     } else {
                         self.log("Distribution of pending certificates cancelled by user");
                     }
@@ -1186,89 +1381,881 @@ impl CertManager {
         Ok(())
     }
 
-    fn create_kubernetes_ca_chain(&mut self) -> io::Result<()> {
-        self.debug_log("Creating Kubernetes CA chain");
-
-        // Check if root CA exists
-        let root_ca_path = "certs/root-ca/ca.crt";
-        let kubernetes_ca_path = "certs/kubernetes-ca/ca.crt";
-        let chain_path = "certs/kubernetes-ca/ca-chain.crt";
+    /// Queues every pending certificate for its hosts and starts copying in
+    /// the background. Results are applied by [`Self::poll_distribution`].
+    fn start_distribution(&mut self, cert_ops: &CertificateOperations) {
+        let transfers = host_files(cert_ops, &self.cert_tracker.get_undistributed());
 
-        if !Path::new(root_ca_path).exists() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Root CA certificate not found. Please generate root CA first.",
-            ));
+        if transfers.is_empty() {
+            self.log("No certificates pending distribution");
+            return;
         }
 
-        if !Path::new(kubernetes_ca_path).exists() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Kubernetes CA certificate not found",
-            ));
-        }
+        self.distribute(transfers, "Distributing Pending Certificates");
+    }
 
-        // Read both certificates
-        let root_ca_content = fs::read_to_string(root_ca_path)?;
-        let kubernetes_ca_content = fs::read_to_string(kubernetes_ca_path)?;
+    /// Starts copying `transfers` in the background, one transaction per
+    /// host. With `preflight.gate` set the job first runs the preflight
+    /// checks of those hosts and stops there if one fails. Returns whether
+    /// a job was started.
+    fn distribute(&mut self, transfers: Vec<Transfer>, operation: &str) -> bool {
+        if self.distribution.as_ref().is_some_and(|job| !job.is_finished()) {
+            self.log("A distribution is already running");
+            return false;
+        }
+        if transfers.is_empty() {
+            self.log("Nothing to distribute");
+            return false;
+        }
 
-        // Create chain file by concatenating both CAs
-        let chain_content = format!("{}\n{}", root_ca_content, kubernetes_ca_content);
-        fs::write(chain_path, chain_content)?;
+        // Local and mock transports have nothing to prepare
+        let gate = (self.config.preflight.gate && self.config.transport.is_ssh()).then(|| {
+            let mut hosts: Vec<String> = transfers.iter().map(|file| file.host.clone()).collect();
+            hosts.sort();
+            hosts.dedup();
+            PreflightGate {
+                nodes: self.preflight_nodes(&hosts),
+                settings: self.config.preflight.clone(),
+            }
+        });
 
-        // Verify the chain
-        let output = Command::new("openssl")
-            .args(&["verify", "-CAfile", root_ca_path, kubernetes_ca_path])
-            .output()?;
+        let settings = self.config.distribution.clone();
+        self.set_current_operation(operation);
+        self.log(&format!(
+            "Distributing {} files to up to {} hosts at once",
+            transfers.len(),
+            settings.parallelism
+        ));
+        self.distribution = Some(DistributionJob::start(
+            self.transport(),
+            transfers,
+            settings,
+            self.config.reload.clone(),
+            gate,
+        ));
+        true
+    }
 
-        if !output.status.success() {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            self.log(&format!("CA chain verification failed: {}", error_msg));
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("CA chain verification failed: {}", error_msg),
-            ));
+    /// Streams progress of a running distribution into the log and, once it
+    /// has finished, marks certificates that reached all of their hosts as
+    /// distributed and logs where every file landed.
+    pub fn poll_distribution(&mut self) {
+        let Some(job) = self.distribution.as_mut() else {
+            return;
+        };
+        let messages = job.take_messages();
+        let result = job.take_result();
+
+        for (debug, message) in messages {
+            if debug {
+                self.debug_log(&message);
+            } else {
+                self.log(&message);
+            }
         }
+        let Some(result) = result else {
+            return;
+        };
+        self.record_preflight(result.preflight.clone());
 
-        self.log("CA chain created and verified successfully");
-        Ok(())
-    }
-
-    pub fn generate_worker_node_certs(&mut self) -> io::Result<()> {
-        self.set_current_operation("Generating Worker node certificates.");
-        let worker_nodes = self.config.worker_nodes.clone();
+        let mut outcome: HashMap<&str, bool> = HashMap::new();
+        for file in result.hosts.iter().flat_map(|host| &host.files) {
+            *outcome.entry(&file.cert_type).or_insert(true) &= file.state == TransferState::Placed;
+        }
+        for (cert_type, placed) in &outcome {
+            if *placed {
+                self.cert_tracker.mark_distributed(cert_type);
+            }
+        }
+        // Hashes of rolled back files describe what is no longer there,
+        // except for the mismatch that caused the rollback
+        for host in &result.hosts {
+            for file in &host.files {
+                if let Some(check) = &file.checksum {
+                    let committed =
+                        matches!(host.state, HostState::Committed | HostState::Unhealthy);
+                    if committed || !check.matches() {
+                        self.cert_tracker
+                            .record_checksum(&file.cert_type, &host.host, check);
+                    }
+                }
+            }
+        }
 
-        let nodes: Vec<(usize, String)> = worker_nodes
-            .iter()
-            .enumerate()
-            .map(|(i, node)| (i, node.clone()))
-            .collect();
+        let distributed = Utc::now();
+        for host in &result.hosts {
+            let committed = matches!(host.state, HostState::Committed | HostState::Unhealthy);
+            for file in &host.files {
+                // A failed host explains what happened to all of its files
+                let error = if committed {
+                    file.error.clone()
+                } else {
+                    host.error.clone().or_else(|| file.error.clone())
+                };
+                self.cert_tracker.record_distribution(
+                    &file.cert_type,
+                    &host.host,
+                    HostDistribution {
+                        remote: file.remote_path.clone(),
+                        state: file.state,
+                        host_state: host.state,
+                        error,
+                        distributed,
+                    },
+                );
+            }
+        }
 
-        let cert_ops = self.get_cert_ops();
-        let mut generator = NodeCertGenerator::new(cert_ops);
+        for host in &result.hosts {
+            for reload in &host.reloads {
+                for cert_type in &reload.cert_types {
+                    self.cert_tracker.record_reload(cert_type, &host.host, reload);
+                }
+            }
+        }
+
+        self.log(&format!(
+            "Distribution finished: {} of {} files placed",
+            result.count(TransferState::Placed),
+            result.total()
+        ));
+        for host in &result.hosts {
+            self.log(&format!(
+                "{}: {:?}, {} placed, {} failed, {} skipped, {} rolled back",
+                host.host,
+                host.state,
+                host.count(TransferState::Placed),
+                host.count(TransferState::Failed),
+                host.count(TransferState::Skipped),
+                host.count(TransferState::RolledBack)
+            ));
+            if let Some(error) = &host.error {
+                self.log(&format!("  {}", error));
+            }
+            for file in &host.files {
+                match &file.error {
+                    None => self.log(&format!("  placed {} -> {}", file.source, file.remote_path)),
+                    Some(error) => self.log(&format!(
+                        "  {:?} {} -> {}: {}",
+                        file.state, file.source, file.remote_path, error
+                    )),
+                }
+            }
+            for reload in &host.reloads {
+                self.log(&format!(
+                    "  reload {}: {:?} after {} ms{}",
+                    reload.component,
+                    reload.state,
+                    reload.elapsed_ms,
+                    reload
+                        .error
+                        .as_ref()
+                        .map(|error| format!(": {}", error))
+                        .unwrap_or_default()
+                ));
+            }
+        }
+        if let Some(dialog) = self.distribution_followup.take() {
+            if outcome.values().all(|placed| *placed)
+                && self.mode == AppMode::Normal
+                && self.confirmation_dialog.is_none()
+            {
+                self.confirmation_dialog = Some(dialog);
+                self.mode = AppMode::Confirmation;
+            }
+        }
+        self.set_current_operation("Distribution complete");
+    }
+
+    /// Recomputes the SHA-256 of every distributed file on its hosts without
+    /// copying anything and records what was found. Returns the number of
+    /// files that are missing or differ from the local copy.
+    pub fn audit_distributed_files(&mut self) -> io::Result<usize> {
+        let cert_ops = self.cert_ops.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "CertificateOperations not initialized",
+            )
+        })?;
+        let distributed: Vec<&CertificateStatus> = self
+            .cert_tracker
+            .certificates
+            .iter()
+            .filter(|cert| cert.distributed.is_some())
+            .collect();
+        let files = host_files(cert_ops, &distributed);
+
+        if files.is_empty() {
+            self.log("No distributed certificates to audit");
+            return Ok(0);
+        }
+
+        self.set_current_operation("Auditing Distributed Files");
+        let transport = self.transport();
+        let mut down: HashSet<String> = HashSet::new();
+        let (mut matched, mut mismatched, mut unchecked) = (0, 0, 0);
+        for file in &files {
+            if down.contains(&file.host) {
+                unchecked += 1;
+                continue;
+            }
+            match remote::compare_checksums(
+                transport.as_ref(),
+                &file.host,
+                Path::new(&file.source),
+                &file.remote,
+            ) {
+                Ok(check) => {
+                    if check.matches() {
+                        matched += 1;
+                        self.debug_log(&format!(
+                            "{} on {}:{} matches {}",
+                            file.cert_type, file.host, file.remote, check.expected
+                        ));
+                    } else {
+                        mismatched += 1;
+                        self.log(&format!(
+                            "{}: {}",
+                            file.cert_type,
+                            check.mismatch(&file.host, &file.remote)
+                        ));
+                    }
+                    self.cert_tracker
+                        .record_checksum(&file.cert_type, &file.host, &check);
+                }
+                Err(e) => {
+                    unchecked += 1;
+                    if e.kind() == io::ErrorKind::NotConnected {
+                        down.insert(file.host.clone());
+                    }
+                    self.log(&format!(
+                        "Could not audit {} on {}: {}",
+                        file.cert_type, file.host, e
+                    ));
+                }
+            }
+        }
+
+        self.log(&format!(
+            "Audit finished: {} files match, {} differ, {} could not be checked",
+            matched, mismatched, unchecked
+        ));
+        Ok(mismatched)
+    }
+
+    /// Opens a TLS connection to every component on the nodes that run it
+    /// and checks the chain it presents. Returns the number of endpoints
+    /// that could not be reached or failed a check.
+    pub fn probe_endpoints(&mut self) -> usize {
+        self.set_current_operation("Probing TLS Endpoints");
+        let targets = self.probe_targets();
+        let timeout = std::time::Duration::from_millis(self.config.probe.timeout_ms);
+
+        // Endpoints are independent, so a slow one only costs its timeout once
+        let probes: Vec<EndpointProbe> = std::thread::scope(|scope| {
+            let handles: Vec<_> = targets
+                .iter()
+                .map(|target| scope.spawn(move || probe::probe_endpoint(target, timeout)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("TLS probe panicked"))
+                .collect()
+        });
+
+        for result in &probes {
+            let endpoint = format!(
+                "{} on {}:{}",
+                result.component.as_str(),
+                result.host,
+                result.port
+            );
+            if result.valid() {
+                self.log(&format!(
+                    "{}: valid, expires in {} days",
+                    endpoint,
+                    result.days_left.unwrap_or_default()
+                ));
+            }
+            for problem in &result.problems {
+                self.log(&format!("{}: {}", endpoint, problem));
+            }
+        }
+        let failed = probes.iter().filter(|result| !result.valid()).count();
+        self.log(&format!(
+            "TLS probe finished: {} of {} endpoints valid",
+            probes.len() - failed,
+            probes.len()
+        ));
+
+        if let Some(store) = self.trust_store.as_mut() {
+            for (node, trust_info) in store.iter_mut() {
+                trust_info.endpoints = probes
+                    .iter()
+                    .filter(|result| &result.host == node)
+                    .cloned()
+                    .collect();
+                if trust_info.endpoints.iter().any(|e| e.chain_valid == Some(false)) {
+                    trust_info.trust_chain_valid = false;
+                }
+            }
+        }
+        self.probes = probes;
+        failed
+    }
+
+    /// Components per node: the control plane runs the API server,
+    /// controller manager and scheduler, etcd members serve the client and
+    /// peer ports, and every node runs a kubelet. Nodes only reached through
+    /// jump hosts are left out, as the probe connects directly.
+    fn probe_targets(&mut self) -> Vec<ProbeTarget> {
+        let control_plane = self.config.control_plane.clone();
+        let members = self.config.etcd.members_or_default(&control_plane);
+        let mut hosts = vec![control_plane.clone()];
+        for host in members
+            .iter()
+            .map(|member| member.address().to_string())
+            .chain(self.config.worker_nodes.clone())
+        {
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+
+        let distributed: Vec<&CertificateStatus> = self
+            .cert_tracker
+            .certificates
+            .iter()
+            .filter(|cert| cert.distributed.is_some() && cert.path.ends_with(".crt"))
+            .collect();
+        let installed = match self.cert_ops.as_ref() {
+            Some(cert_ops) => host_files(cert_ops, &distributed),
+            None => Vec::new(),
+        };
+
+        let mut targets = Vec::new();
+        let mut skipped = Vec::new();
+        for host in hosts {
+            if self.config.ssh.uses_jump_hosts(&host) {
+                skipped.push(host);
+                continue;
+            }
+            let components = Component::ALL.into_iter().filter(|component| match component {
+                Component::KubeApiserver
+                | Component::KubeControllerManager
+                | Component::KubeScheduler => host == control_plane,
+                Component::EtcdClient | Component::EtcdPeer => {
+                    members.iter().any(|member| member.address() == host)
+                }
+                Component::Kubelet => true,
+            });
+            for component in components {
+                let tracked = component.tracked_source().and_then(|source| {
+                    let pattern = glob::Pattern::new(source).ok()?;
+                    installed
+                        .iter()
+                        .find(|file| file.host == host && pattern.matches(&file.source))
+                        .map(|file| (file.cert_type.clone(), file.source.clone()))
+                });
+                let ca_bundle = tracked
+                    .as_ref()
+                    .and_then(|(cert_type, _)| self.ca_chain_for(cert_type))
+                    .unwrap_or_else(|| match component {
+                        Component::EtcdClient | Component::EtcdPeer => {
+                            format!("{}/ca-chain.crt", self.config.etcd.ca_dir())
+                        }
+                        _ => "certs/kubernetes-ca/ca-chain.crt".to_string(),
+                    });
+                targets.push(ProbeTarget {
+                    host: host.clone(),
+                    port: self.config.probe.port(component),
+                    component,
+                    ca_bundle,
+                    tracked,
+                });
+            }
+        }
+        if !skipped.is_empty() {
+            self.log(&format!(
+                "Not probing {}: only reachable through jump hosts",
+                skipped.join(", ")
+            ));
+        }
+        targets
+    }
+
+    /// Runs the preflight checks on `hosts` in parallel. Returns the number
+    /// of hosts failing a check. Only SSH hosts are checked; local and mock
+    /// transports have nothing to prepare.
+    pub fn run_preflight(&mut self, hosts: &[String]) -> usize {
+        if !self.config.transport.is_ssh() {
+            self.log("Preflight checks only apply to the SSH transport");
+            return 0;
+        }
+
+        self.set_current_operation("Running Preflight Checks");
+        let reports = remote::preflight_hosts(
+            self.transport().as_ref(),
+            &self.preflight_nodes(hosts),
+            &self.config.preflight,
+        );
+
+        for report in &reports {
+            self.log(&format!(
+                "Preflight {}: {}",
+                report.host,
+                report.status.as_str()
+            ));
+            for check in &report.checks {
+                let message = format!(
+                    "  {} {}: {}",
+                    check.status.as_str(),
+                    check.name,
+                    check.detail
+                );
+                if check.status == CheckStatus::Pass {
+                    self.debug_log(&message);
+                } else {
+                    self.log(&message);
+                }
+            }
+        }
+        let failed = reports
+            .iter()
+            .filter(|report| report.status == CheckStatus::Fail)
+            .count();
+        self.record_preflight(reports);
+        failed
+    }
+
+    /// Replaces the reports of the hosts in `reports`; hosts not checked
+    /// this time keep their last report.
+    fn record_preflight(&mut self, reports: Vec<HostPreflight>) {
+        self.preflight
+            .retain(|old| reports.iter().all(|report| report.host != old.host));
+        self.preflight.extend(reports);
+        self.preflight.sort_by(|a, b| a.host.cmp(&b.host));
+    }
+
+    /// What each host should provide: the directories its tracked files are
+    /// installed to and the backup directory, kubelet and containerd on every
+    /// node, and etcd on the etcd members.
+    fn preflight_nodes(&self, hosts: &[String]) -> Vec<PreflightNode> {
+        let tracked: Vec<&CertificateStatus> = self.cert_tracker.certificates.iter().collect();
+        let files = match self.cert_ops.as_ref() {
+            Some(cert_ops) => host_files(cert_ops, &tracked),
+            None => Vec::new(),
+        };
+        let members = self
+            .config
+            .etcd
+            .members_or_default(&self.config.control_plane);
+
+        hosts
+            .iter()
+            .map(|host| {
+                let mut directories: Vec<String> = files
+                    .iter()
+                    .filter(|file| &file.host == host)
+                    .filter_map(|file| {
+                        Path::new(&file.remote)
+                            .parent()
+                            .map(|dir| dir.to_string_lossy().to_string())
+                    })
+                    .collect();
+                directories.push(self.config.distribution.backup_dir.clone());
+                directories.sort();
+                directories.dedup();
+
+                let mut services = vec!["kubelet".to_string(), "containerd".to_string()];
+                if members.iter().any(|member| member.address() == host) {
+                    services.push("etcd".to_string());
+                }
+                PreflightNode {
+                    host: host.clone(),
+                    directories,
+                    services,
+                }
+            })
+            .collect()
+    }
+
+    /// Progress of the current or last distribution.
+    pub fn distribution_progress(&self) -> Option<DistributionProgress> {
+        self.distribution.as_ref().map(DistributionJob::snapshot)
+    }
+
+    pub fn is_distributing(&self) -> bool {
+        self.distribution
+            .as_ref()
+            .is_some_and(|job| !job.is_finished())
+    }
+
+    pub fn open_restore_picker(&mut self) {
+        if self.is_distributing() {
+            self.log("Cannot restore a backup while a distribution is running");
+            return;
+        }
+
+        let transport = self.transport();
+        let mut candidates = Vec::new();
+        for host in self.get_all_hosts() {
+            match remote::list_backups(
+                transport.as_ref(),
+                &host,
+                &self.config.distribution.backup_dir,
+            ) {
+                Ok(manifests) => candidates.extend(manifests.into_iter().rev()),
+                Err(e) => self.log(&format!("Failed to list backups on {}: {}", host, e)),
+            }
+        }
+
+        if candidates.is_empty() {
+            self.log("No backups found on any host");
+            return;
+        }
+
+        self.restore_picker = Some(RestorePicker::new(candidates));
+        self.mode = AppMode::Restore;
+    }
+
+    pub fn handle_restore_picker(&mut self, key: KeyCode) {
+        let Some(picker) = self.restore_picker.as_mut() else {
+            self.mode = AppMode::Normal;
+            return;
+        };
+
+        match key {
+            KeyCode::Up => {
+                picker.selected = picker
+                    .selected
+                    .checked_sub(1)
+                    .unwrap_or(picker.candidates.len() - 1);
+            }
+            KeyCode::Down => {
+                picker.selected = (picker.selected + 1) % picker.candidates.len();
+            }
+            KeyCode::Esc => {
+                self.restore_picker = None;
+                self.mode = AppMode::Normal;
+                self.log("Restore cancelled");
+            }
+            KeyCode::Enter => {
+                let manifest = picker.candidates[picker.selected].clone();
+                self.restore_picker = None;
+                self.confirmation_dialog = Some(ConfirmationDialog {
+                    message: format!(
+                        "Restore {} files on {} to before distribution {}?",
+                        manifest.entries.len(),
+                        manifest.host,
+                        manifest.generation
+                    ),
+                    callback: ConfirmationCallback::Restore {
+                        host: manifest.host,
+                        generation: manifest.generation,
+                    },
+                });
+                self.mode = AppMode::Confirmation;
+            }
+            _ => {}
+        }
+    }
+
+    /// Puts the files replaced by distribution `generation` back on `host`.
+    pub fn restore_backup(&mut self, host: &str, generation: &str) -> io::Result<()> {
+        if self.is_distributing() {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "a distribution is running",
+            ));
+        }
+        let manifest = remote::restore_generation(
+            self.transport().as_ref(),
+            host,
+            &self.config.distribution.backup_dir,
+            generation,
+        )?;
+        self.note_restored(&manifest);
+        Ok(())
+    }
+
+    /// Queues certificates whose files were replaced by a restore for
+    /// distribution again, since their hosts no longer hold the current copy.
+    pub fn note_restored(&mut self, manifest: &BackupManifest) {
+        let stale: Vec<String> = match self.cert_ops.as_ref() {
+            Some(cert_ops) => self
+                .cert_tracker
+                .certificates
+                .iter()
+                .filter(|cert| cert.hosts.contains(&manifest.host))
+                .filter(|cert| {
+                    let (_, remote) = cert_ops.k8s_paths(&cert.path, &manifest.host);
+                    manifest.entries.iter().any(|entry| entry.target == remote)
+                })
+                .map(|cert| cert.cert_type.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        for cert_type in &stale {
+            self.cert_tracker.mark_undistributed(cert_type);
+        }
+
+        self.log(&format!(
+            "Restored {} files on {} from backup {}",
+            manifest.entries.len(),
+            manifest.host,
+            manifest.generation
+        ));
+        if !stale.is_empty() {
+            self.log(&format!(
+                "Queued for redistribution: {}",
+                stale.join(", ")
+            ));
+        }
+    }
+
+    fn create_kubernetes_ca_chain(&mut self) -> io::Result<()> {
+        self.debug_log("Creating Kubernetes CA chain");
+
+        // Check if root CA exists
+        let root_ca_path = "certs/root-ca/ca.crt";
+        let kubernetes_ca_path = "certs/kubernetes-ca/ca.crt";
+        let chain_path = "certs/kubernetes-ca/ca-chain.crt";
+
+        if !Path::new(root_ca_path).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Root CA certificate not found. Please generate root CA first.",
+            ));
+        }
+
+        if !Path::new(kubernetes_ca_path).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Kubernetes CA certificate not found",
+            ));
+        }
+
+        // Read both certificates
+        let root_ca_content = fs::read_to_string(root_ca_path)?;
+        let kubernetes_ca_content = fs::read_to_string(kubernetes_ca_path)?;
+
+        // Create chain file by concatenating both CAs
+        let chain_content = format!("{}\n{}", root_ca_content, kubernetes_ca_content);
+        fs::write(chain_path, chain_content)?;
+
+        // Verify the chain
+        if let Err(e) = verify_chain(kubernetes_ca_path, root_ca_path) {
+            self.log(&format!("CA chain verification failed: {}", e));
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("CA chain verification failed: {}", e),
+            ));
+        }
+
+        self.log("CA chain created and verified successfully");
+        Ok(())
+    }
+
+    /// Worker nodes with the names their kubelets register under, as far as
+    /// `node_hostnames` in the configuration knows them; the others are
+    /// listed as `node-N` until `discover_worker_hostnames` has run.
+    pub fn worker_nodes(&self) -> Vec<WorkerNode> {
+        self.config
+            .worker_nodes
+            .iter()
+            .enumerate()
+            .map(|(index, address)| WorkerNode {
+                index,
+                address: address.clone(),
+                hostname: self.config.node_hostnames.get(address).cloned(),
+            })
+            .collect()
+    }
+
+    /// Discovers the hostname of every worker missing from `node_hostnames`
+    /// over SSH and caches it there. A worker that cannot be asked is an
+    /// error: the Node authorizer only accepts the name the kubelet
+    /// registers with, so there is nothing to fall back to.
+    fn discover_worker_hostnames(&mut self) -> io::Result<()> {
+        for address in self.config.worker_nodes.clone() {
+            if self.config.node_hostnames.contains_key(&address) {
+                continue;
+            }
+            let name = self.get_cert_ops().remote_hostname(&address).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!(
+                        "Could not discover the hostname of worker {}: {}. Set its node name in node_hostnames",
+                        address, e
+                    ),
+                )
+            })?;
+            self.log(&format!("Discovered hostname {} for {}", name, address));
+            self.config.node_hostnames.insert(address, name);
+        }
+        Ok(())
+    }
+
+    pub fn generate_worker_node_certs(&mut self) -> io::Result<()> {
+        self.set_current_operation("Generating Worker node certificates.");
+        self.discover_worker_hostnames()?;
+        let nodes = self.worker_nodes();
+
+        let cert_ops = self.get_cert_ops();
+        let mut generator = NodeCertGenerator::new(cert_ops);
 
         generator.generate_node_certificates(&nodes)?;
 
-        // Generate and distribute kubeconfigs after certificates
+        // Kubelet certificates go to /var/lib/kubelet/pki on their own node
+        for node in &nodes {
+            let name = node.name();
+            for (cert_type, file) in [
+                ("kubelet-client", "kubelet-client"),
+                ("kubelet-serving", "kubelet"),
+            ] {
+                let cert_type = format!("{}-{}", cert_type, name);
+                let base = format!("{}/{}", node.cert_dir(), file);
+                self.cert_tracker.add_certificate(
+                    &cert_type,
+                    &format!("{}.crt", base),
+                    vec![node.address.clone()],
+                );
+                self.cert_tracker.add_certificate(
+                    &format!("{}-key", cert_type),
+                    &format!("{}.key", base),
+                    vec![node.address.clone()],
+                );
+            }
+        }
+
+        // Generate kubeconfigs after certificates
         self.generate_worker_kubeconfigs()?;
 
         Ok(())
     }
 
     fn generate_worker_kubeconfigs(&mut self) -> io::Result<()> {
-        let mut cert_ops = self.create_certificate_operations()?;
+        if self.kubeconfig_generator.is_none() {
+            self.init_generators();
+        }
 
-        for (i, node) in self.config.worker_nodes.clone().iter().enumerate() {
-            let node_name = format!("node-{}", i + 1);
-            let credential_name = format!("system:node:{}", node_name);
+        let nodes = self.worker_nodes();
+        self.kubeconfig_generator
+            .as_ref()
+            .unwrap()
+            .generate_node_kubeconfigs(&nodes)?;
+
+        for node in &nodes {
+            let name = node.name();
+            self.cert_tracker.add_certificate(
+                &format!("kubeconfig-kubelet-{}", name),
+                &format!("kubeconfig/{}/kubelet.conf", name),
+                vec![node.address.clone()],
+            );
+        }
+        Ok(())
+    }
+
+    /// Revokes the target and re-publishes the CRL of each affected CA.
+    pub fn revoke_certificate(
+        &mut self,
+        target: &RevocationTarget,
+        reason: RevocationReason,
+    ) -> io::Result<()> {
+        let validity_days = self.config.revocation.crl_validity_days;
+        let mut logger = OperationsLogger::new(self.log_sender.clone(), self.debug);
+        revocation::revoke_and_publish(target, reason, validity_days, &mut logger)?;
+        Ok(())
+    }
 
-            // Generate kubeconfig
-            self.generate_kubeconfig(&node_name, &credential_name)?;
+    pub fn open_revocation_picker(&mut self) {
+        let mut candidates = Vec::new();
+        for ca_dir in revocation::ca_directories() {
+            match RevocationDb::open(&ca_dir) {
+                Ok(db) => {
+                    let ca = db.ca_name();
+                    candidates.extend(
+                        db.entries()
+                            .iter()
+                            .filter(|e| e.status == EntryStatus::Valid)
+                            .map(|e| (ca.clone(), e.clone())),
+                    );
+                }
+                Err(e) => self.log(&format!(
+                    "Failed to read revocation database in {}: {}",
+                    ca_dir.display(),
+                    e
+                )),
+            }
+        }
 
-            // Use cert_ops for distribution
-            cert_ops.copy_to_k8s_paths(&format!("kubeconfig/{}.conf", node_name), node)?;
+        if candidates.is_empty() {
+            self.log("No issued certificates available to revoke");
+            return;
+        }
+
+        self.revocation_picker = Some(RevocationPicker::new(candidates));
+        self.mode = AppMode::Revoke;
+    }
+
+    pub fn handle_revoke_picker(&mut self, key: KeyCode) {
+        let Some(picker) = self.revocation_picker.as_mut() else {
+            self.mode = AppMode::Normal;
+            return;
+        };
+
+        match (picker.step, key) {
+            (RevokeStep::SelectCertificate, KeyCode::Up) => {
+                picker.selected = picker
+                    .selected
+                    .checked_sub(1)
+                    .unwrap_or(picker.candidates.len() - 1);
+            }
+            (RevokeStep::SelectCertificate, KeyCode::Down) => {
+                picker.selected = (picker.selected + 1) % picker.candidates.len();
+            }
+            (RevokeStep::SelectCertificate, KeyCode::Enter) => {
+                picker.step = RevokeStep::SelectReason;
+            }
+            (RevokeStep::SelectCertificate, KeyCode::Esc) => {
+                self.revocation_picker = None;
+                self.mode = AppMode::Normal;
+                self.log("Revocation cancelled");
+            }
+            (RevokeStep::SelectReason, KeyCode::Up) => {
+                picker.reason = picker
+                    .reason
+                    .checked_sub(1)
+                    .unwrap_or(RevocationReason::ALL.len() - 1);
+            }
+            (RevokeStep::SelectReason, KeyCode::Down) => {
+                picker.reason = (picker.reason + 1) % RevocationReason::ALL.len();
+            }
+            (RevokeStep::SelectReason, KeyCode::Esc) => {
+                picker.step = RevokeStep::SelectCertificate;
+            }
+            (RevokeStep::SelectReason, KeyCode::Enter) => {
+                let (ca, entry) = picker.candidates[picker.selected].clone();
+                let reason = picker.selected_reason();
+                self.revocation_picker = None;
+                self.confirmation_dialog = Some(ConfirmationDialog {
+                    message: format!(
+                        "Revoke {} (serial {}) as {}?",
+                        entry.name(),
+                        entry.serial,
+                        reason
+                    ),
+                    callback: ConfirmationCallback::Revoke {
+                        ca,
+                        serial: entry.serial,
+                        reason,
+                    },
+                });
+                self.mode = AppMode::Confirmation;
+            }
+            _ => {}
         }
-        Ok(())
     }
 
     pub fn set_current_operation(&mut self, operation: &str) {
@@ -1385,6 +2372,163 @@ impl CertManager {
         Ok(())
     }
 
+    /// Takes over the PKI of a cluster built by kubeadm: fetches it from
+    /// every node, reports what each file is and which checks it failed, and
+    /// tracks the adopted files as distributed to the hosts holding them.
+    /// Local files that differ from the host's copy are kept. Returns the
+    /// number of files that failed a check.
+    pub fn import_kubeadm_pki(&mut self) -> io::Result<usize> {
+        self.set_current_operation("Importing kubeadm PKI");
+        let nodes = self.import_nodes();
+        let import = kubeadm::import_kubeadm_pki(
+            self.transport().as_ref(),
+            &nodes,
+            &self.config.remote_layout(),
+        );
+
+        for (host, error) in &import.failed_hosts {
+            self.log(&format!("Could not import from {}: {}", host, error));
+        }
+        let mut failed = 0;
+        for file in &import.files {
+            self.log(&format!(
+                "{}:{}: {}{}",
+                file.host,
+                file.remote,
+                file.kind.as_str(),
+                file.subject
+                    .as_ref()
+                    .map(|subject| format!(" ({})", subject))
+                    .unwrap_or_default()
+            ));
+            for problem in &file.problems {
+                self.log(&format!("  {}", problem));
+            }
+            if !file.problems.is_empty() {
+                failed += 1;
+            }
+        }
+
+        // One entry per type, holding the copy of every host
+        let mut adopted: Vec<(&str, &str, Vec<&kubeadm::ImportedFile>)> = Vec::new();
+        for file in &import.files {
+            let Some((cert_type, local)) = &file.adopted else {
+                continue;
+            };
+            match adopted.iter_mut().find(|(known, ..)| known == cert_type) {
+                Some((.., copies)) => copies.push(file),
+                None => adopted.push((cert_type, local, vec![file])),
+            }
+        }
+
+        let mut written = HashSet::new();
+        for (cert_type, local, copies) in adopted {
+            let contents = fs::read(&copies[0].fetched)?;
+            if fs::read(local).is_ok_and(|existing| existing != contents) {
+                self.log(&format!(
+                    "Keeping the local {}, which differs from {}:{}",
+                    local, copies[0].host, copies[0].remote
+                ));
+                continue;
+            }
+            if let Some(parent) = Path::new(local).parent() {
+                fs::create_dir_all(parent)?;
+            }
+            remote::write_private(Path::new(local), &contents)?;
+            written.insert(local);
+
+            let hosts = copies.iter().map(|copy| copy.host.clone()).collect();
+            self.cert_tracker.add_certificate(cert_type, local, hosts);
+            self.cert_tracker.mark_distributed(cert_type);
+            self.cert_tracker.mark_verified(
+                cert_type,
+                copies.iter().all(|copy| copy.problems.is_empty()),
+            );
+            // Hosts holding another copy are queued for distribution
+            let expected = remote::sha256_hex(&contents);
+            for copy in &copies {
+                let check = IntegrityCheck {
+                    expected: expected.clone(),
+                    actual: Some(remote::sha256_hex(&fs::read(&copy.fetched)?)),
+                };
+                if !check.matches() {
+                    self.log(&format!(
+                        "{} on {} differs from the adopted copy",
+                        cert_type, copy.host
+                    ));
+                }
+                self.cert_tracker
+                    .record_checksum(cert_type, &copy.host, &check);
+            }
+        }
+
+        for (from, to) in kubeadm::ISSUER_COPIES {
+            if written.contains(from) && !Path::new(to).exists() {
+                fs::copy(from, to)?;
+            }
+        }
+        if written.contains("certs/etcd-ca/ca.key") && !self.config.etcd.separate_ca {
+            self.log(
+                "The etcd CA was adopted; set etcd.separate_ca to issue etcd certificates from it",
+            );
+        }
+
+        self.log(&format!(
+            "kubeadm import finished: {} files from {} host(s), {} adopted, {} failed a check",
+            import.files.len(),
+            nodes.len() - import.failed_hosts.len(),
+            written.len(),
+            failed
+        ));
+        Ok(failed)
+    }
+
+    /// The control plane, the etcd members and the workers, each once.
+    fn import_nodes(&mut self) -> Vec<kubeadm::ImportNode> {
+        let control_plane = self.config.control_plane.clone();
+        let members = self.config.etcd.members_or_default(&control_plane);
+        let member_of = |host: &str| {
+            members
+                .iter()
+                .find(|member| member.address() == host)
+                .map(|member| member.name.clone())
+        };
+
+        let mut nodes = vec![kubeadm::ImportNode {
+            node_name: self
+                .config
+                .node_hostnames
+                .get(&control_plane)
+                .cloned()
+                .unwrap_or_else(|| "control-plane-0".to_string()),
+            etcd_member: member_of(&control_plane),
+            host: control_plane,
+        }];
+        let workers = self.worker_nodes();
+        for member in &members {
+            let host = member.address();
+            if nodes.iter().all(|node| node.host != host)
+                && workers.iter().all(|worker| worker.address != host)
+            {
+                nodes.push(kubeadm::ImportNode {
+                    host: host.to_string(),
+                    node_name: member.hostname.clone().unwrap_or(member.name.clone()),
+                    etcd_member: Some(member.name.clone()),
+                });
+            }
+        }
+        for worker in workers {
+            if nodes.iter().all(|node| node.host != worker.address) {
+                nodes.push(kubeadm::ImportNode {
+                    node_name: worker.name(),
+                    etcd_member: member_of(&worker.address),
+                    host: worker.address,
+                });
+            }
+        }
+        nodes
+    }
+
     // Helper function to determine standard certificate type
     pub fn determine_cert_type(&mut self, cert_info: &CertificateInfo) -> String {
         // Check filename first
@@ -1470,28 +2614,61 @@ impl CertManager {
         let mut trust_store = discovery.get_trust_store_contents().await;
         trust_store.clear();
 
+        // Nodes that received files are judged by what they hold
+        let installed = self.installed_certificates();
+
         // Validate control plane certificates
         let control_plane_certs = discovery
             .discover_certificates(Path::new("certs"), self)
             .await?;
+        let control_plane = self.config.control_plane.clone();
+        let held = self
+            .held_certificates(discovery, &installed, &control_plane)
+            .await
+            .unwrap_or_else(|| control_plane_certs.clone());
         // Validate control plane trust
-        discovery
-            .validate_node_trust(&self.config.control_plane, control_plane_certs.clone())
-            .await?;
+        discovery.validate_node_trust(&control_plane, held).await?;
 
         // Validate worker nodes
-        for worker in self.config.worker_nodes.clone() {
-            // Try to find worker-specific certificates
-            let worker_certs = discovery
-                .discover_certificates(Path::new(&format!("certs/node-{}", worker)), self)
+        for node in self.worker_nodes() {
+            let worker_certs = match self
+                .held_certificates(discovery, &installed, &node.address)
                 .await
-                .unwrap_or(control_plane_certs.clone());
+            {
+                Some(certs) => certs,
+                // Try to find worker-specific certificates
+                None => discovery
+                    .discover_certificates(Path::new(&node.cert_dir()), self)
+                    .await
+                    .unwrap_or(control_plane_certs.clone()),
+            };
 
-            discovery.validate_node_trust(&worker, worker_certs).await?;
+            discovery
+                .validate_node_trust(&node.address, worker_certs)
+                .await?;
         }
 
         // Retrieve the updated trust store contents
-        let updated_trust_store = discovery.get_trust_store_contents().await;
+        let mut updated_trust_store = discovery.get_trust_store_contents().await;
+
+        for (node, trust_info) in updated_trust_store.iter_mut() {
+            // Permissions are judged once, by the last remote verification
+            if let Some(valid) = self
+                .verification
+                .iter()
+                .find(|report| &report.host == node)
+                .and_then(HostVerification::permissions_valid)
+            {
+                trust_info.permissions_valid = valid;
+            }
+            // Keep what the last probe found
+            trust_info.endpoints = self
+                .probes
+                .iter()
+                .filter(|result| &result.host == node)
+                .cloned()
+                .collect();
+        }
 
         for (node, trust_info) in &updated_trust_store {
             self.log(&format!(
@@ -1521,6 +2698,30 @@ impl CertManager {
         Ok(())
     }
 
+    /// Certificates `host` holds at the paths of the remote layout, or
+    /// `None` when none have been distributed to it.
+    async fn held_certificates(
+        &mut self,
+        discovery: &CertificateDiscovery,
+        installed: &[Transfer],
+        host: &str,
+    ) -> Option<Vec<CertificateInfo>> {
+        let files: Vec<Transfer> = installed
+            .iter()
+            .filter(|file| file.host == host)
+            .cloned()
+            .collect();
+        if files.is_empty() {
+            return None;
+        }
+        let transport = self.transport();
+        Some(
+            discovery
+                .discover_installed_certificates(transport.as_ref(), &files, self)
+                .await,
+        )
+    }
+
     pub fn log(&mut self, message: &str) {
         self.logs.push(format!(
             "{}: {}",
@@ -1587,3 +2788,102 @@ impl CertManager {
         }
     }
 }
+
+/// One file per certificate and host, from its local path to where it lives
+/// on the host.
+fn host_files(cert_ops: &CertificateOperations, certs: &[&CertificateStatus]) -> Vec<Transfer> {
+    certs
+        .iter()
+        .flat_map(|cert| host_transfers(cert_ops, &cert.cert_type, &cert.path, &cert.hosts))
+        .collect()
+}
+
+/// The file at `path` placed on each of `hosts`.
+fn host_transfers(
+    cert_ops: &CertificateOperations,
+    cert_type: &str,
+    path: &str,
+    hosts: &[String],
+) -> Vec<Transfer> {
+    hosts
+        .iter()
+        .map(|host| {
+            let (source, remote) = cert_ops.k8s_paths(path, host);
+            Transfer {
+                cert_type: cert_type.to_string(),
+                host: host.clone(),
+                permissions: cert_ops.file_permissions(cert_type, &remote),
+                source,
+                remote,
+            }
+        })
+        .collect()
+}
+
+/// Re-issues the OCSP responder certificate of every CA whose current one
+/// is missing, close to expiry or signed by a replaced CA key, then re-signs
+/// every CA's CRL so `nextUpdate` never lapses. Returns how many CRLs were
+/// published.
+fn refresh_revocation(cert_ops: &mut CertificateOperations, validity_days: u32) -> usize {
+    for ca_dir in revocation::ca_directories() {
+        if !ocsp::signer_needs_renewal(&ca_dir) {
+            continue;
+        }
+        match cert_ops.issue_ocsp_signer(&ca_dir.to_string_lossy()) {
+            Ok(()) => cert_ops.log(&format!(
+                "Renewed OCSP responder certificate for {}",
+                ca_dir.display()
+            )),
+            Err(e) => cert_ops.log(&format!(
+                "Failed to renew OCSP responder certificate for {}: {}",
+                ca_dir.display(),
+                e
+            )),
+        }
+    }
+    revocation::refresh_crls(validity_days, cert_ops.logger_mut()).len()
+}
+
+/// Re-signs all CRLs, renewing OCSP responder certificates first, on start-up
+/// and then every `crl_refresh_hours`. Keys are generated and CRLs signed on
+/// a blocking thread; the manager is locked only to read the settings and to
+/// record the renewed certificates.
+pub fn start_crl_refresh(cert_manager: Arc<RwLock<CertManager>>) {
+    tokio::spawn(async move {
+        loop {
+            let (cert_ops, validity_days, interval_hours) = {
+                let manager = cert_manager.read().unwrap();
+                (
+                    manager.create_certificate_operations(),
+                    manager.config.revocation.crl_validity_days,
+                    manager.config.revocation.crl_refresh_hours.max(1),
+                )
+            };
+            let refresh = match cert_ops {
+                Ok(mut cert_ops) => tokio::task::spawn_blocking(move || {
+                    let published = refresh_revocation(&mut cert_ops, validity_days);
+                    (cert_ops, published)
+                })
+                .await
+                .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            {
+                let mut manager = cert_manager.write().unwrap();
+                match refresh {
+                    Ok((renewed, published)) => {
+                        if let Some(cert_ops) = manager.cert_ops.as_mut() {
+                            cert_ops.extend_issued(renewed.issued());
+                        }
+                        manager.debug_log(&format!("Refreshed {} CRLs", published));
+                    }
+                    Err(e) => manager.log(&format!("Failed to refresh CRLs: {}", e)),
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(interval_hours * 3600)).await;
+        }
+    });
+}
+
+
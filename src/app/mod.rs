@@ -1,6 +1,8 @@
 mod manager;
 mod run;
 
-pub use manager::{CertManager, NodeInfo, CertStatus, ClusterInfo, ConnectivityStatus};
+pub use manager::{
+    start_crl_refresh, CertManager, CertStatus, ClusterInfo, ConnectivityStatus, NodeInfo,
+};
 pub use run::run_app;
 
@@ -21,6 +21,7 @@ pub async fn run_app(
     loop {
         let mut manager = cert_manager.write().unwrap();
         manager.process_pending_logs();
+        manager.poll_distribution();
 
         terminal.draw(|f| ui::render_all(f, &manager))?;
 
@@ -95,11 +96,9 @@ pub async fn run_app(
                                 manager.scroll_logs(ScrollDirection::Down);
                             }
                             ActiveSection::TrustInfo => {
-                                if let Some(store) = &manager.trust_store {
-                                    let max_scroll = store.len().saturating_sub(8);
-                                    if manager.trust_info_scroll < max_scroll {
-                                        manager.trust_info_scroll += 1;
-                                    }
+                                let max_scroll = manager.trust_info_len().saturating_sub(8);
+                                if manager.trust_info_scroll < max_scroll {
+                                    manager.trust_info_scroll += 1;
                                 }
                             }
                         },
@@ -146,12 +145,7 @@ pub async fn run_app(
                                 manager.scroll_logs(ScrollDirection::PageDown);
                             }
                             ActiveSection::TrustInfo => {
-                                let max_scroll = manager
-                                    .trust_store
-                                    .as_ref()
-                                    .map(|s| s.len())
-                                    .unwrap_or(0)
-                                    .saturating_sub(8);
+                                let max_scroll = manager.trust_info_len().saturating_sub(8);
                                 manager.trust_info_scroll =
                                     (manager.trust_info_scroll + 10).min(max_scroll);
                             }
@@ -297,6 +291,38 @@ pub async fn run_app(
                                 }
                             }
                             15 => {
+                                // Revoke Certificate
+                                manager.open_revocation_picker();
+                            }
+                            16 => {
+                                // Generate etcd Certs
+                                if let Err(e) = manager.generate_etcd_certs() {
+                                    manager.log(&format!("Error: {}", e));
+                                } else {
+                                    manager.confirmation_dialog = Some(ConfirmationDialog {
+                                        message:
+                                            "Do you want to distribute the etcd certificates?"
+                                                .to_string(),
+                                        callback: ConfirmationCallback::DistributePending,
+                                    });
+                                    manager.mode = AppMode::Confirmation;
+                                }
+                            }
+                            17 => {
+                                // Generate Front Proxy Certs
+                                if let Err(e) = manager.generate_front_proxy_certs() {
+                                    manager.log(&format!("Error: {}", e));
+                                } else {
+                                    manager.confirmation_dialog = Some(ConfirmationDialog {
+                                        message:
+                                            "Do you want to distribute the front-proxy certificates?"
+                                                .to_string(),
+                                        callback: ConfirmationCallback::DistributePending,
+                                    });
+                                    manager.mode = AppMode::Confirmation;
+                                }
+                            }
+                            18 => {
                                 // Automate all
                                 manager.confirmation_dialog = Some(ConfirmationDialog {
                                     message: "Do you want to automatically generate and distribute all certificates?".to_string(),
@@ -304,6 +330,31 @@ pub async fn run_app(
                                 });
                                 manager.mode = AppMode::Confirmation;
                             }
+                            19 => {
+                                // Restore Backup
+                                manager.open_restore_picker();
+                            }
+                            20 => {
+                                // Audit Distributed Files
+                                if let Err(e) = manager.audit_distributed_files() {
+                                    manager.log(&format!("Audit failed: {}", e));
+                                }
+                            }
+                            21 => {
+                                // Import kubeadm PKI
+                                if let Err(e) = manager.import_kubeadm_pki() {
+                                    manager.log(&format!("kubeadm import failed: {}", e));
+                                }
+                            }
+                            22 => {
+                                // Probe TLS endpoints
+                                manager.probe_endpoints();
+                            }
+                            23 => {
+                                // Run preflight checks
+                                let hosts = manager.get_all_hosts();
+                                manager.run_preflight(&hosts);
+                            }
 
                             _ => manager.log("Function not implemented yet"),
                         },
@@ -312,6 +363,12 @@ pub async fn run_app(
                     AppMode::EditConfig => {
                         manager.handle_config_edit(key.code);
                     }
+                    AppMode::Revoke => {
+                        manager.handle_revoke_picker(key.code);
+                    }
+                    AppMode::Restore => {
+                        manager.handle_restore_picker(key.code);
+                    }
 
                     AppMode::Confirmation => match key.code {
                         KeyCode::Char('y') | KeyCode::Char('Y') => {
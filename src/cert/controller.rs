@@ -1,10 +1,9 @@
 // src/cert/controller.rs
 
 use super::{
-    operations::CertificateOperations,
-    types::{AltName, CertificateConfig, CertificateType, ClusterEndpoints},
+    operations::CertificateOperations, profiles::TemplateContext, types::ClusterEndpoints,
 };
-use std::{io, path::PathBuf};
+use std::io;
 
 pub struct ControllerCertGenerator<'a> {
     endpoints: ClusterEndpoints,
@@ -21,119 +20,35 @@ impl<'a> ControllerCertGenerator<'a> {
 
     pub fn generate_api_server_cert(&mut self) -> io::Result<()> {
         self.cert_ops.log("Generating API Server Certificate");
-
-        let config = self.get_apiserver_config();
-        self.cert_ops.generate_cert(
-            "kube-apiserver",
-            "certs/kubernetes-ca",
-            &config,
-            &[&self.endpoints.control_plane],
-        )?;
-
-        Ok(())
+        self.generate("kube-apiserver")
     }
 
     pub fn generate_controller_manager_cert(&mut self) -> io::Result<()> {
-        let config = self.get_controller_config();
-        self.cert_ops.generate_cert(
-            "controller-manager",
-            "certs/kubernetes-ca",
-            &config,
-            &[&self.endpoints.control_plane],
-        )?;
-
-        Ok(())
+        self.generate("controller-manager")
     }
 
     pub fn generate_scheduler_cert(&mut self) -> io::Result<()> {
-        let config = self.get_scheduler_config();
-        self.cert_ops.generate_cert(
-            "scheduler",
-            "certs/kubernetes-ca",
-            &config,
-            &[&self.endpoints.control_plane],
-        )?;
-
-        Ok(())
+        self.generate("scheduler")
     }
 
-    fn get_controller_config(&self) -> CertificateConfig {
-        CertificateConfig {
-            cert_type: CertificateType::ControllerManager,
-            common_name: "system:kube-controller-manager".to_string(),
-            organization: Some("system:kube-controller-manager".to_string()),
-            validity_days: 375,
-            key_size: 2048,
-            output_dir: PathBuf::from("certs/controller-manager"),
-            alt_names: vec![
-                AltName::dns("kube-proxy".to_string()),
-                AltName::ip("127.0.0.1".to_string()),
-            ],
-            key_usage: vec![
-                "critical".to_string(),
-                "digitalSignature".to_string(),
-                "keyEncipherment".to_string(),
-            ],
-            extended_key_usage: vec!["clientAuth".to_string(), "serverAuth".to_string()],
-            country: Some("US".to_string()),
-            state: Some("Columbia".to_string()),
-            locality: Some("Columbia".to_string()),
-        }
+    pub fn generate_admin_cert(&mut self) -> io::Result<()> {
+        self.cert_ops.log("Generating Admin Certificate");
+        self.generate("admin")
     }
 
-    fn get_scheduler_config(&self) -> CertificateConfig {
-        CertificateConfig {
-            cert_type: CertificateType::Scheduler,
-            common_name: "system:kube-scheduler".to_string(),
-            organization: Some("system:kube-scheduler".to_string()),
-            validity_days: 375,
-            key_size: 2048,
-            output_dir: PathBuf::from("certs/scheduler"),
-            alt_names: vec![
-                AltName::dns("kube-scheduler".to_string()),
-                AltName::ip("127.0.0.1".to_string()),
-            ],
-            key_usage: vec![
-                "critical".to_string(),
-                "digitalSignature".to_string(),
-                "keyEncipherment".to_string(),
-            ],
-            extended_key_usage: vec!["clientAuth".to_string(), "serverAuth".to_string()],
-            country: Some("US".to_string()),
-            state: Some("Columbia".to_string()),
-            locality: Some("Columbia".to_string()),
-        }
+    pub fn generate_kube_proxy_cert(&mut self) -> io::Result<()> {
+        self.cert_ops.log("Generating kube-proxy Certificate");
+        self.generate("kube-proxy")
     }
 
-    fn get_apiserver_config(&self) -> CertificateConfig {
-        CertificateConfig {
-            cert_type: CertificateType::APIServer,
-            common_name: "kube-apiserver".to_string(),
-            organization: Some("kubernetes".to_string()),
-            validity_days: 375,
-            key_size: 2048,
-            output_dir: PathBuf::from("certs/kube-apiserver"),
-            alt_names: vec![
-                AltName::dns("localhost".to_string()),
-                AltName::ip("127.0.0.1".to_string()),
-                AltName::dns("control-plane-0".to_string()),
-                AltName::ip(self.endpoints.control_plane.clone()),
-                AltName::ip("10.96.0.1".to_string()), // Kubernetes service IP
-                AltName::dns("kubernetes".to_string()),
-                AltName::dns("kubernetes.default".to_string()),
-                AltName::dns("kubernetes.default.svc".to_string()),
-                AltName::dns("kubernetes.default.svc.cluster".to_string()),
-                AltName::dns("kubernetes.default.svc.cluster.local".to_string()),
-            ],
-            key_usage: vec![
-                "critical".to_string(),
-                "digitalSignature".to_string(),
-                "keyEncipherment".to_string(),
-            ],
-            extended_key_usage: vec!["serverAuth".to_string()],
-            country: Some("US".to_string()),
-            state: Some("Columbia".to_string()),
-            locality: Some("Columbia".to_string()),
-        }
+    fn generate(&mut self, profile: &str) -> io::Result<()> {
+        let context = TemplateContext::new().with_control_plane(&self.endpoints.control_plane);
+        self.cert_ops.generate_from_profile(
+            profile,
+            &context,
+            &[&self.endpoints.control_plane],
+        )?;
+
+        Ok(())
     }
 }
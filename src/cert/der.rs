@@ -0,0 +1,218 @@
+// src/cert/der.rs
+//
+// Minimal DER encoding and decoding for the few structures the `openssl`
+// crate cannot build for us (CRL entry extensions, OCSP messages, AIA).
+use chrono::{DateTime, Utc};
+
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_ENUMERATED: u8 = 0x0a;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+
+/// Context-specific tag `[n]`, constructed when it wraps other TLVs.
+pub fn context_tag(n: u8, constructed: bool) -> u8 {
+    0x80 | if constructed { 0x20 } else { 0 } | n
+}
+
+fn push_length(out: &mut Vec<u8>, len: usize) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .iter()
+            .copied()
+            .skip_while(|b| *b == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(&bytes);
+    }
+}
+
+pub fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    push_length(&mut out, value.len());
+    out.extend_from_slice(value);
+    out
+}
+
+pub fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+/// Encodes an unsigned big-endian magnitude as an INTEGER.
+pub fn integer(magnitude: &[u8]) -> Vec<u8> {
+    let mut value: Vec<u8> = magnitude.iter().copied().skip_while(|b| *b == 0).collect();
+    if value.is_empty() || value[0] & 0x80 != 0 {
+        value.insert(0, 0);
+    }
+    tlv(TAG_INTEGER, &value)
+}
+
+pub fn enumerated(value: u8) -> Vec<u8> {
+    tlv(TAG_ENUMERATED, &[value])
+}
+
+pub fn null() -> Vec<u8> {
+    tlv(TAG_NULL, &[])
+}
+
+pub fn octet_string(value: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, value)
+}
+
+pub fn bit_string(value: &[u8]) -> Vec<u8> {
+    let mut contents = vec![0];
+    contents.extend_from_slice(value);
+    tlv(TAG_BIT_STRING, &contents)
+}
+
+pub fn generalized_time(time: DateTime<Utc>) -> Vec<u8> {
+    tlv(
+        TAG_GENERALIZED_TIME,
+        time.format("%Y%m%d%H%M%SZ").to_string().as_bytes(),
+    )
+}
+
+/// Encodes a dotted object identifier such as `2.5.29.21`.
+pub fn oid(dotted: &str) -> Vec<u8> {
+    let arcs: Vec<u64> = dotted.split('.').filter_map(|a| a.parse().ok()).collect();
+    let mut contents = Vec::new();
+    if arcs.len() >= 2 {
+        push_base128(&mut contents, arcs[0] * 40 + arcs[1]);
+        for arc in &arcs[2..] {
+            push_base128(&mut contents, *arc);
+        }
+    }
+    tlv(TAG_OID, &contents)
+}
+
+fn push_base128(out: &mut Vec<u8>, mut value: u64) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push(0x80 | (value & 0x7f) as u8);
+        value >>= 7;
+    }
+    bytes.reverse();
+    out.extend_from_slice(&bytes);
+}
+
+/// A decoded TLV. `raw` is the full encoding including tag and length.
+#[derive(Debug, Clone, Copy)]
+pub struct Tlv<'a> {
+    pub contents: &'a [u8],
+    pub raw: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    pub fn reader(&self) -> DerReader<'a> {
+        DerReader::new(self.contents)
+    }
+}
+
+/// Sequential reader over concatenated TLVs.
+pub struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    pub fn read(&mut self) -> Option<Tlv<'a>> {
+        self.data.first()?;
+        let first = *self.data.get(1)?;
+        let (header, len) = if first & 0x80 == 0 {
+            (2, first as usize)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > std::mem::size_of::<usize>() {
+                return None;
+            }
+            let len = self
+                .data
+                .get(2..2 + count)?
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            (2 + count, len)
+        };
+        let end = header.checked_add(len)?;
+        let raw = self.data.get(..end)?;
+        self.data = &self.data[end..];
+        Some(Tlv {
+            contents: &raw[header..],
+            raw,
+        })
+    }
+
+    /// Reads the next TLV only if it carries `tag`.
+    pub fn read_tag(&mut self, tag: u8) -> Option<Tlv<'a>> {
+        if self.peek_tag()? == tag {
+            self.read()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_object_identifiers() {
+        assert_eq!(oid("2.5.29.21"), [0x06, 0x03, 0x55, 0x1d, 0x15]);
+        assert_eq!(
+            oid("1.3.6.1.5.5.7.48.1.2"),
+            [0x06, 0x09, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x02]
+        );
+        assert_eq!(oid("1.2.840.113549.1.1.11")[2..5], [0x2a, 0x86, 0x48]);
+    }
+
+    #[test]
+    fn encodes_integers_as_positive_and_minimal() {
+        assert_eq!(integer(&[0x00, 0x00, 0x7f]), [0x02, 0x01, 0x7f]);
+        assert_eq!(integer(&[0x80]), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(integer(&[]), [0x02, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn reads_back_long_form_lengths() {
+        let value = vec![0xab; 300];
+        let encoded = sequence(&[octet_string(&value), null()]);
+        assert_eq!(encoded[..4], [0x30, 0x82, 0x01, 0x32]);
+
+        let mut reader = DerReader::new(&encoded);
+        let outer = reader.read_tag(TAG_SEQUENCE).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(outer.raw, encoded.as_slice());
+        let mut fields = outer.reader();
+        assert!(fields.read_tag(TAG_NULL).is_none());
+        assert_eq!(fields.read_tag(TAG_OCTET_STRING).unwrap().contents, value);
+        assert!(fields.read_tag(TAG_NULL).unwrap().contents.is_empty());
+        assert!(fields.is_empty());
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let encoded = octet_string(&[1, 2, 3]);
+        assert!(DerReader::new(&encoded[..4]).read().is_none());
+        assert!(DerReader::new(&[0x04, 0x85, 0, 0, 0, 0, 1])
+            .read()
+            .is_none());
+    }
+}
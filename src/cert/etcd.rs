@@ -0,0 +1,89 @@
+// src/cert/etcd.rs
+
+use super::{
+    operations::CertificateOperations,
+    profiles::TemplateContext,
+    types::{EtcdMember, EtcdSettings},
+};
+use std::{fs, io, path::Path};
+
+/// Local directory of the etcd PKI, installed in the etcd directory of the
+/// remote layout on the etcd members.
+pub const ETCD_DIR: &str = "certs/etcd";
+
+pub struct EtcdCertGenerator<'a> {
+    settings: EtcdSettings,
+    cert_ops: &'a mut CertificateOperations,
+}
+
+impl<'a> EtcdCertGenerator<'a> {
+    pub fn new(settings: EtcdSettings, cert_ops: &'a mut CertificateOperations) -> Self {
+        Self { settings, cert_ops }
+    }
+
+    /// Generates the etcd CA (when separate), the server and peer
+    /// certificates of every member and the two etcd client certificates.
+    pub fn generate_all(&mut self, members: &[EtcdMember]) -> io::Result<()> {
+        if self.settings.separate_ca {
+            self.generate_ca()?;
+        }
+        self.write_trust_bundle()?;
+
+        for member in members {
+            self.generate_member_certs(member)?;
+        }
+
+        self.generate("etcd-healthcheck-client", TemplateContext::new())?;
+        self.generate("apiserver-etcd-client", TemplateContext::new())?;
+
+        Ok(())
+    }
+
+    fn generate_ca(&mut self) -> io::Result<()> {
+        self.cert_ops.log("Generating etcd CA");
+        self.cert_ops
+            .generate_from_profile("etcd-ca", &TemplateContext::new(), &[])?;
+
+        let root_ca = fs::read_to_string("certs/root-ca/ca.crt")?;
+        let etcd_ca = fs::read_to_string(format!("{}/ca.crt", EtcdSettings::CA_DIR))?;
+        fs::write(
+            format!("{}/ca-chain.crt", EtcdSettings::CA_DIR),
+            format!("{}\n{}", root_ca, etcd_ca),
+        )?;
+
+        Ok(())
+    }
+
+    /// Copies the issuing CA certificate to `certs/etcd/ca.crt`, the trust
+    /// anchor etcd and kube-apiserver use for etcd connections.
+    fn write_trust_bundle(&mut self) -> io::Result<()> {
+        let ca_cert = format!("{}/ca.crt", self.settings.ca_dir());
+        if !Path::new(&ca_cert).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("etcd CA certificate {} not found", ca_cert),
+            ));
+        }
+
+        fs::create_dir_all(ETCD_DIR)?;
+        fs::copy(&ca_cert, format!("{}/ca.crt", ETCD_DIR))?;
+        Ok(())
+    }
+
+    fn generate_member_certs(&mut self, member: &EtcdMember) -> io::Result<()> {
+        self.cert_ops.log(&format!(
+            "Generating etcd server and peer certificates for {}",
+            member.name
+        ));
+
+        let context = TemplateContext::new().with_etcd_member(member);
+        self.generate("etcd-server", context.clone())?;
+        self.generate("etcd-peer", context)
+    }
+
+    fn generate(&mut self, profile: &str, context: TemplateContext) -> io::Result<()> {
+        let context = context.with_etcd_ca(self.settings.ca_dir());
+        self.cert_ops.generate_from_profile(profile, &context, &[])?;
+        Ok(())
+    }
+}
@@ -1,18 +1,28 @@
 // src/cert/mod.rs
 mod controller;
 pub mod controller_manager;
-pub mod kubelet;
+mod der;
+pub mod etcd;
 mod node;
+pub mod ocsp;
 mod openssl;
 pub mod operations;
-pub mod scheduler;
+pub mod profiles;
+pub mod revocation;
 mod service_account;
+#[cfg(test)]
+pub(crate) mod testing;
 mod types;
 pub mod verification;
 
 pub use controller::ControllerCertGenerator;
+pub use etcd::EtcdCertGenerator;
 pub use node::NodeCertGenerator;
+pub use self::openssl::{load_certificate_bundle, verify_against_bundle, verify_chain};
 pub use operations::{CertOperationError, CertificateOperations};
 pub use service_account::ServiceAccountGenerator;
-pub use types::{CertificateConfig, CertificateType, ClusterEndpoints};
+pub use types::{
+    validate_node_name, CertificateConfig, ClusterEndpoints, EtcdSettings, KeyAlgorithms,
+    RevocationSettings, SubjectDefaults, WorkerNode,
+};
 pub use controller_manager::ControllerManagerGenerator;
@@ -0,0 +1,637 @@
+// src/cert/ocsp.rs
+//
+// RFC 6960 OCSP responder backed by the per-CA revocation databases. Each CA
+// directory carries a delegated responder certificate in `ocsp-signer/`,
+// issued by that CA with the OCSPSigning extended key usage. The `openssl`
+// crate has no OCSP response builder, so requests are parsed and responses
+// assembled as DER here and only the signature is computed by OpenSSL.
+use super::der::{self, DerReader, Tlv};
+use super::openssl::{load_certificate, load_private_key, SigningError};
+use super::revocation::{self, EntryStatus, RevocationDb, RevocationError, RevocationReason};
+use chrono::{Duration, Utc};
+use openssl::{
+    asn1::Asn1Time,
+    error::ErrorStack,
+    hash::{hash, MessageDigest},
+    pkey::{Id, PKey, PKeyRef, Private},
+    sign::Signer,
+    x509::{X509Ref, X509},
+};
+use std::path::{Path, PathBuf};
+
+/// Directory and file stem of the delegated responder inside a CA directory.
+/// Also the name of the profile it is issued from.
+pub const SIGNER_NAME: &str = "ocsp-signer";
+/// The refresh loop re-issues the responder certificate this close to expiry.
+const SIGNER_RENEW_BEFORE_DAYS: u32 = 30;
+/// How long clients may cache a response.
+const RESPONSE_VALIDITY_HOURS: i64 = 1;
+
+const OID_BASIC_RESPONSE: &str = "1.3.6.1.5.5.7.48.1.1";
+const OID_NONCE: &str = "1.3.6.1.5.5.7.48.1.2";
+const OID_SHA1: &str = "1.3.14.3.2.26";
+const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+const OID_SHA384: &str = "2.16.840.1.101.3.4.2.2";
+const OID_SHA512: &str = "2.16.840.1.101.3.4.2.3";
+const OID_SHA256_WITH_RSA: &str = "1.2.840.113549.1.1.11";
+const OID_ECDSA_WITH_SHA256: &str = "1.2.840.10045.4.3.2";
+const OID_ECDSA_WITH_SHA384: &str = "1.2.840.10045.4.3.3";
+const OID_ED25519: &str = "1.3.101.112";
+
+/// Failures that map onto an OCSPResponseStatus other than `successful`.
+#[derive(Debug)]
+pub enum OcspError {
+    Malformed(String),
+    Internal(String),
+    TryLater(String),
+    Unauthorized(String),
+}
+
+impl OcspError {
+    fn status(&self) -> u8 {
+        match self {
+            Self::Malformed(_) => 1,
+            Self::Internal(_) => 2,
+            Self::TryLater(_) => 3,
+            Self::Unauthorized(_) => 6,
+        }
+    }
+
+    /// The unsigned error response carrying only the status.
+    pub fn to_response(&self) -> Vec<u8> {
+        der::sequence(&[der::enumerated(self.status())])
+    }
+}
+
+impl std::fmt::Display for OcspError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(s) => write!(f, "Malformed OCSP request: {}", s),
+            Self::Internal(s) => write!(f, "OCSP responder error: {}", s),
+            Self::TryLater(s) => write!(f, "OCSP responder unavailable: {}", s),
+            Self::Unauthorized(s) => write!(f, "Not authorized to answer for {}", s),
+        }
+    }
+}
+
+impl std::error::Error for OcspError {}
+
+impl From<ErrorStack> for OcspError {
+    fn from(error: ErrorStack) -> Self {
+        OcspError::Internal(error.to_string())
+    }
+}
+
+impl From<SigningError> for OcspError {
+    fn from(error: SigningError) -> Self {
+        OcspError::Internal(error.to_string())
+    }
+}
+
+impl From<RevocationError> for OcspError {
+    fn from(error: RevocationError) -> Self {
+        OcspError::Internal(error.to_string())
+    }
+}
+
+/// A single entry of the request list. `raw` is echoed back unchanged.
+struct CertId<'a> {
+    raw: &'a [u8],
+    hash_algorithm: MessageDigest,
+    issuer_name_hash: &'a [u8],
+    issuer_key_hash: &'a [u8],
+    serial: String,
+}
+
+struct Request<'a> {
+    cert_ids: Vec<CertId<'a>>,
+    nonce: Option<&'a [u8]>,
+}
+
+/// A CA directory together with the keys needed to answer for it.
+struct Issuer {
+    dir: PathBuf,
+    cert: X509,
+}
+
+/// Answers a DER encoded OCSPRequest with a signed BasicOCSPResponse.
+pub fn respond(request: &[u8]) -> Result<Vec<u8>, OcspError> {
+    respond_for(&revocation::ca_directories(), request)
+}
+
+/// Answers on behalf of the CAs in `ca_dirs` only.
+fn respond_for(ca_dirs: &[PathBuf], request: &[u8]) -> Result<Vec<u8>, OcspError> {
+    let request = parse_request(request)?;
+    let first = request
+        .cert_ids
+        .first()
+        .ok_or_else(|| OcspError::Malformed("empty request list".to_string()))?;
+
+    let issuer = find_issuer(ca_dirs, first)?;
+    let (signer_cert, signer_key) = load_signer(&issuer.dir)?;
+    let db = RevocationDb::open(&issuer.dir)?;
+
+    let now = Utc::now();
+    let next_update = now + Duration::hours(RESPONSE_VALIDITY_HOURS);
+
+    let mut responses = Vec::new();
+    for cert_id in &request.cert_ids {
+        let status = if issuer_matches(&issuer.cert, cert_id)? {
+            cert_status(&db, &cert_id.serial)
+        } else {
+            unknown_status()
+        };
+        responses.push(der::sequence(&[
+            cert_id.raw.to_vec(),
+            status,
+            der::generalized_time(now),
+            der::tlv(
+                der::context_tag(0, true),
+                &der::generalized_time(next_update),
+            ),
+        ]));
+    }
+
+    let mut response_data = vec![
+        // ResponderID byKey: SHA-1 of the responder's public key
+        der::tlv(
+            der::context_tag(2, true),
+            &der::octet_string(&hash(MessageDigest::sha1(), &public_key_bits(&signer_cert)?)?),
+        ),
+        der::generalized_time(now),
+        der::sequence(&responses),
+    ];
+    if let Some(nonce) = request.nonce {
+        response_data.push(der::tlv(
+            der::context_tag(1, true),
+            &der::sequence(&[nonce.to_vec()]),
+        ));
+    }
+    let tbs_response_data = der::sequence(&response_data);
+
+    let (algorithm, signature) = sign(&signer_key, &tbs_response_data)?;
+    let basic_response = der::sequence(&[
+        tbs_response_data,
+        algorithm,
+        der::bit_string(&signature),
+        der::tlv(
+            der::context_tag(0, true),
+            &der::sequence(&[signer_cert.to_der()?]),
+        ),
+    ]);
+
+    Ok(der::sequence(&[
+        der::enumerated(0),
+        der::tlv(
+            der::context_tag(0, true),
+            &der::sequence(&[
+                der::oid(OID_BASIC_RESPONSE),
+                der::octet_string(&basic_response),
+            ]),
+        ),
+    ]))
+}
+
+/// Whether the responder certificate of a CA must be (re-)issued: it is
+/// missing, close to expiry, or was signed by a previous CA key.
+pub fn signer_needs_renewal(ca_dir: &Path) -> bool {
+    let current = || -> Result<bool, OcspError> {
+        let ca_cert = load_certificate(&ca_dir.join("ca.crt").to_string_lossy())?;
+        let (signer_cert, _) = load_signer(ca_dir)?;
+        let ca_key = ca_cert.public_key()?;
+        let renew_at = Asn1Time::days_from_now(SIGNER_RENEW_BEFORE_DAYS)?;
+        Ok(signer_cert.verify(&ca_key)? && signer_cert.not_after() > renew_at)
+    };
+    !matches!(current(), Ok(true))
+}
+
+pub fn signer_dir(ca_dir: &Path) -> PathBuf {
+    ca_dir.join(SIGNER_NAME)
+}
+
+fn load_signer(ca_dir: &Path) -> Result<(X509, PKey<Private>), OcspError> {
+    let dir = signer_dir(ca_dir);
+    let cert_path = dir.join(format!("{}.crt", SIGNER_NAME));
+    let key_path = dir.join(format!("{}.key", SIGNER_NAME));
+    if !cert_path.exists() || !key_path.exists() {
+        return Err(OcspError::TryLater(format!(
+            "no responder certificate in {}",
+            dir.display()
+        )));
+    }
+
+    let cert = load_certificate(&cert_path.to_string_lossy())?;
+    if cert.not_after() < Asn1Time::days_from_now(0)? {
+        return Err(OcspError::TryLater(format!(
+            "responder certificate in {} has expired",
+            dir.display()
+        )));
+    }
+    Ok((cert, load_private_key(&key_path.to_string_lossy())?))
+}
+
+fn parse_request(data: &[u8]) -> Result<Request<'_>, OcspError> {
+    let malformed = |what: &str| OcspError::Malformed(what.to_string());
+
+    let ocsp_request = DerReader::new(data)
+        .read_tag(der::TAG_SEQUENCE)
+        .ok_or_else(|| malformed("expected OCSPRequest"))?;
+    let tbs_request = ocsp_request
+        .reader()
+        .read_tag(der::TAG_SEQUENCE)
+        .ok_or_else(|| malformed("expected TBSRequest"))?;
+
+    let mut fields = tbs_request.reader();
+    // Skip the optional version [0] and requestorName [1]
+    fields.read_tag(der::context_tag(0, true));
+    fields.read_tag(der::context_tag(1, true));
+
+    let request_list = fields
+        .read_tag(der::TAG_SEQUENCE)
+        .ok_or_else(|| malformed("expected requestList"))?;
+    let mut cert_ids = Vec::new();
+    let mut requests = request_list.reader();
+    while !requests.is_empty() {
+        let request = requests
+            .read_tag(der::TAG_SEQUENCE)
+            .ok_or_else(|| malformed("expected Request"))?;
+        let cert_id = request
+            .reader()
+            .read_tag(der::TAG_SEQUENCE)
+            .ok_or_else(|| malformed("expected CertID"))?;
+        cert_ids.push(parse_cert_id(cert_id)?);
+    }
+
+    let nonce = match fields.read_tag(der::context_tag(2, true)) {
+        Some(extensions) => find_nonce(extensions)?,
+        None => None,
+    };
+
+    Ok(Request { cert_ids, nonce })
+}
+
+fn parse_cert_id(cert_id: Tlv<'_>) -> Result<CertId<'_>, OcspError> {
+    let malformed = |what: &str| OcspError::Malformed(what.to_string());
+    let mut fields = cert_id.reader();
+
+    let algorithm = fields
+        .read_tag(der::TAG_SEQUENCE)
+        .and_then(|a| a.reader().read_tag(der::TAG_OID))
+        .ok_or_else(|| malformed("expected hashAlgorithm"))?;
+    let hash_algorithm = [
+        (OID_SHA1, MessageDigest::sha1()),
+        (OID_SHA256, MessageDigest::sha256()),
+        (OID_SHA384, MessageDigest::sha384()),
+        (OID_SHA512, MessageDigest::sha512()),
+    ]
+    .into_iter()
+    .find(|(oid, _)| der::oid(oid) == algorithm.raw)
+    .map(|(_, digest)| digest)
+    .ok_or_else(|| malformed("unsupported hash algorithm"))?;
+
+    let issuer_name_hash = fields
+        .read_tag(der::TAG_OCTET_STRING)
+        .ok_or_else(|| malformed("expected issuerNameHash"))?;
+    let issuer_key_hash = fields
+        .read_tag(der::TAG_OCTET_STRING)
+        .ok_or_else(|| malformed("expected issuerKeyHash"))?;
+    let serial = fields
+        .read_tag(der::TAG_INTEGER)
+        .ok_or_else(|| malformed("expected serialNumber"))?;
+
+    Ok(CertId {
+        raw: cert_id.raw,
+        hash_algorithm,
+        issuer_name_hash: issuer_name_hash.contents,
+        issuer_key_hash: issuer_key_hash.contents,
+        serial: serial.contents.iter().map(|b| format!("{:02X}", b)).collect(),
+    })
+}
+
+/// Returns the full nonce extension so it can be echoed verbatim.
+fn find_nonce(extensions: Tlv<'_>) -> Result<Option<&[u8]>, OcspError> {
+    let list = extensions
+        .reader()
+        .read_tag(der::TAG_SEQUENCE)
+        .ok_or_else(|| OcspError::Malformed("expected requestExtensions".to_string()))?;
+
+    let nonce_oid = der::oid(OID_NONCE);
+    let mut reader = list.reader();
+    while let Some(extension) = reader.read() {
+        if extension.reader().read_tag(der::TAG_OID).map(|o| o.raw) == Some(nonce_oid.as_slice()) {
+            return Ok(Some(extension.raw));
+        }
+    }
+    Ok(None)
+}
+
+fn find_issuer(ca_dirs: &[PathBuf], cert_id: &CertId<'_>) -> Result<Issuer, OcspError> {
+    for dir in ca_dirs {
+        let cert = match load_certificate(&dir.join("ca.crt").to_string_lossy()) {
+            Ok(cert) => cert,
+            Err(_) => continue,
+        };
+        if issuer_matches(&cert, cert_id)? {
+            return Ok(Issuer {
+                dir: dir.clone(),
+                cert,
+            });
+        }
+    }
+    Err(OcspError::Unauthorized(format!(
+        "serial {} (issuer not managed here)",
+        cert_id.serial
+    )))
+}
+
+fn issuer_matches(ca_cert: &X509Ref, cert_id: &CertId<'_>) -> Result<bool, OcspError> {
+    let name_hash = hash(cert_id.hash_algorithm, &ca_cert.subject_name().to_der()?)?;
+    let key_hash = hash(cert_id.hash_algorithm, &public_key_bits(ca_cert)?)?;
+    Ok(*name_hash == *cert_id.issuer_name_hash && *key_hash == *cert_id.issuer_key_hash)
+}
+
+/// The subjectPublicKey BIT STRING contents without the unused-bits octet,
+/// which is what issuerKeyHash and ResponderID byKey are computed over.
+fn public_key_bits(cert: &X509Ref) -> Result<Vec<u8>, OcspError> {
+    let spki = cert.public_key()?.public_key_to_der()?;
+    let mut fields = DerReader::new(&spki)
+        .read_tag(der::TAG_SEQUENCE)
+        .map(|s| s.reader())
+        .ok_or_else(|| OcspError::Internal("invalid public key encoding".to_string()))?;
+    fields.read_tag(der::TAG_SEQUENCE);
+    fields
+        .read_tag(der::TAG_BIT_STRING)
+        .and_then(|bits| bits.contents.get(1..))
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| OcspError::Internal("invalid public key encoding".to_string()))
+}
+
+fn cert_status(db: &RevocationDb, serial: &str) -> Vec<u8> {
+    let entry = match db.find(serial) {
+        Some(entry) => entry,
+        None => return unknown_status(),
+    };
+
+    match (entry.status, entry.revoked_at) {
+        (EntryStatus::Revoked, Some(revoked_at)) => {
+            let mut revoked_info = der::generalized_time(revoked_at);
+            // Same rule as the CRL: `unspecified` is expressed by omission
+            if let Some(reason) = entry
+                .reason
+                .filter(|r| *r != RevocationReason::Unspecified)
+            {
+                revoked_info.extend(der::tlv(
+                    der::context_tag(0, true),
+                    &der::enumerated(reason.code()),
+                ));
+            }
+            der::tlv(der::context_tag(1, true), &revoked_info)
+        }
+        _ => der::tlv(der::context_tag(0, false), &[]),
+    }
+}
+
+fn unknown_status() -> Vec<u8> {
+    der::tlv(der::context_tag(2, false), &[])
+}
+
+/// Signs the response data, returning the AlgorithmIdentifier and signature.
+fn sign(key: &PKeyRef<Private>, data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), OcspError> {
+    let (algorithm, mut signer) = match key.id() {
+        Id::RSA => (
+            der::sequence(&[der::oid(OID_SHA256_WITH_RSA), der::null()]),
+            Signer::new(MessageDigest::sha256(), key)?,
+        ),
+        Id::EC if key.bits() > 256 => (
+            der::sequence(&[der::oid(OID_ECDSA_WITH_SHA384)]),
+            Signer::new(MessageDigest::sha384(), key)?,
+        ),
+        Id::EC => (
+            der::sequence(&[der::oid(OID_ECDSA_WITH_SHA256)]),
+            Signer::new(MessageDigest::sha256(), key)?,
+        ),
+        Id::ED25519 => (
+            der::sequence(&[der::oid(OID_ED25519)]),
+            Signer::new_without_digest(key)?,
+        ),
+        _ => {
+            return Err(OcspError::Internal(
+                "unsupported responder key type".to_string(),
+            ))
+        }
+    };
+    let signature = signer.sign_oneshot_to_vec(data)?;
+    Ok((algorithm, signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert::{profiles::TemplateContext, testing::TestPki};
+    use openssl::{
+        bn::BigNum,
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+        ocsp::{
+            OcspBasicResponse, OcspCertId, OcspCertStatus, OcspFlag, OcspRequest, OcspResponse,
+            OcspResponseStatus, OcspRevokedStatus,
+        },
+        stack::Stack,
+        x509::{store::X509StoreBuilder, X509Builder},
+    };
+
+    /// The Kubernetes CA below the root CA, with one certificate in good
+    /// standing and one revoked for key compromise.
+    struct Fixture {
+        pki: TestPki,
+        root_ca: X509,
+        ca: X509,
+        good: X509,
+        revoked: X509,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let mut pki = TestPki::new();
+            let (root_ca, _) = pki.issue("root-ca", &TemplateContext::new());
+            let (ca, _) = pki.issue("kubernetes-ca", &TemplateContext::new());
+            let (good, _) = pki.issue(
+                "kube-apiserver",
+                &TemplateContext::new().with_control_plane("10.0.0.1"),
+            );
+            let (revoked, _) = pki.issue("admin", &TemplateContext::new());
+            let revoked = load_certificate(&revoked).unwrap();
+
+            let mut db = RevocationDb::open(&pki.ca_dir("kubernetes-ca")).unwrap();
+            let serial = revoked
+                .serial_number()
+                .to_bn()
+                .unwrap()
+                .to_hex_str()
+                .unwrap();
+            db.revoke(&serial, RevocationReason::KeyCompromise).unwrap();
+            db.save().unwrap();
+
+            Self {
+                root_ca: load_certificate(&root_ca).unwrap(),
+                ca: load_certificate(&ca).unwrap(),
+                good: load_certificate(&good).unwrap(),
+                revoked,
+                pki,
+            }
+        }
+
+        fn cert_id(&self, cert: &X509Ref) -> OcspCertId {
+            OcspCertId::from_cert(MessageDigest::sha1(), cert, &self.ca).unwrap()
+        }
+
+        fn respond(&self, request: &[u8]) -> Result<Vec<u8>, OcspError> {
+            respond_for(
+                &[self.pki.ca_dir("root-ca"), self.pki.ca_dir("kubernetes-ca")],
+                request,
+            )
+        }
+
+        /// Parses a reply and checks its signature. OpenSSL finds the
+        /// signer among the embedded certificates by the responder ID, and
+        /// requires it to be issued by the CA for OCSP signing.
+        fn basic_response(&self, reply: &[u8]) -> OcspBasicResponse {
+            let response = OcspResponse::from_der(reply).unwrap();
+            assert_eq!(response.status(), OcspResponseStatus::SUCCESSFUL);
+            let basic = response.basic().unwrap();
+
+            let mut store = X509StoreBuilder::new().unwrap();
+            store.add_cert(self.root_ca.clone()).unwrap();
+            store.add_cert(self.ca.clone()).unwrap();
+            basic
+                .verify(&Stack::new().unwrap(), &store.build(), OcspFlag::empty())
+                .unwrap();
+            basic
+        }
+    }
+
+    /// A certificate naming `issuer` that it never issued. Only its issuer
+    /// and serial end up in a CertID.
+    fn stray_certificate(issuer: &X509Ref, serial: u32) -> X509 {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut builder = X509Builder::new().unwrap();
+        builder
+            .set_serial_number(&BigNum::from_u32(serial).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_issuer_name(issuer.subject_name()).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    fn nonce_extension(nonce: &[u8]) -> Vec<u8> {
+        der::sequence(&[
+            der::oid(OID_NONCE),
+            der::octet_string(&der::octet_string(nonce)),
+        ])
+    }
+
+    /// Adds requestExtensions to the TBSRequest of an encoded OCSPRequest.
+    fn with_extension(request: &[u8], extension: Vec<u8>) -> Vec<u8> {
+        let tbs_request = DerReader::new(request)
+            .read_tag(der::TAG_SEQUENCE)
+            .and_then(|r| r.reader().read_tag(der::TAG_SEQUENCE))
+            .unwrap();
+        let extensions = der::tlv(der::context_tag(2, true), &der::sequence(&[extension]));
+        der::sequence(&[der::tlv(
+            der::TAG_SEQUENCE,
+            &[tbs_request.contents, extensions.as_slice()].concat(),
+        )])
+    }
+
+    /// The responseExtensions of an encoded OCSPResponse.
+    fn response_extensions(reply: &[u8]) -> Option<Vec<u8>> {
+        let mut response = DerReader::new(reply).read_tag(der::TAG_SEQUENCE)?.reader();
+        response.read_tag(der::TAG_ENUMERATED)?;
+        let mut response_bytes = response
+            .read_tag(der::context_tag(0, true))?
+            .reader()
+            .read_tag(der::TAG_SEQUENCE)?
+            .reader();
+        response_bytes.read_tag(der::TAG_OID)?;
+        let basic_response = response_bytes.read_tag(der::TAG_OCTET_STRING)?;
+        let mut response_data = DerReader::new(basic_response.contents)
+            .read_tag(der::TAG_SEQUENCE)?
+            .reader()
+            .read_tag(der::TAG_SEQUENCE)?
+            .reader();
+        response_data.read_tag(der::context_tag(2, true))?;
+        response_data.read_tag(der::TAG_GENERALIZED_TIME)?;
+        response_data.read_tag(der::TAG_SEQUENCE)?;
+        response_data
+            .read_tag(der::context_tag(1, true))
+            .map(|extensions| extensions.contents.to_vec())
+    }
+
+    #[test]
+    fn answers_good_revoked_and_unknown_serials() {
+        let fixture = Fixture::new();
+        let stray = stray_certificate(&fixture.ca, 0x0ddba11);
+        let mut request = OcspRequest::new().unwrap();
+        for cert in [&fixture.good, &fixture.revoked, &stray] {
+            request.add_id(fixture.cert_id(cert)).unwrap();
+        }
+
+        let reply = fixture.respond(&request.to_der().unwrap()).unwrap();
+        let basic = fixture.basic_response(&reply);
+        assert_eq!(response_extensions(&reply), None);
+
+        let good_id = fixture.cert_id(&fixture.good);
+        let good = basic.find_status(&good_id).unwrap();
+        assert_eq!(good.status, OcspCertStatus::GOOD);
+        good.check_validity(0, None).unwrap();
+
+        let revoked_id = fixture.cert_id(&fixture.revoked);
+        let revoked = basic.find_status(&revoked_id).unwrap();
+        assert_eq!(revoked.status, OcspCertStatus::REVOKED);
+        assert_eq!(revoked.reason, OcspRevokedStatus::KEY_COMPROMISE);
+        let db = RevocationDb::open(&fixture.pki.ca_dir("kubernetes-ca")).unwrap();
+        let serial = fixture
+            .revoked
+            .serial_number()
+            .to_bn()
+            .unwrap()
+            .to_hex_str()
+            .unwrap();
+        let revoked_at = db.find(&serial).and_then(|e| e.revoked_at).unwrap();
+        assert_eq!(
+            revoked.revocation_time.unwrap().to_string(),
+            Asn1Time::from_unix(revoked_at.timestamp())
+                .unwrap()
+                .to_string()
+        );
+
+        let unknown_id = fixture.cert_id(&stray);
+        let unknown = basic.find_status(&unknown_id).unwrap();
+        assert_eq!(unknown.status, OcspCertStatus::UNKNOWN);
+    }
+
+    #[test]
+    fn echoes_the_request_nonce() {
+        let fixture = Fixture::new();
+        let mut request = OcspRequest::new().unwrap();
+        request.add_id(fixture.cert_id(&fixture.good)).unwrap();
+        let nonce = nonce_extension(b"starquill-nonce-0123456789");
+        let request = with_extension(&request.to_der().unwrap(), nonce.clone());
+
+        let reply = fixture.respond(&request).unwrap();
+        fixture.basic_response(&reply);
+        assert_eq!(response_extensions(&reply), Some(der::sequence(&[nonce])));
+    }
+
+    #[test]
+    fn refuses_certificates_of_unmanaged_issuers() {
+        let fixture = Fixture::new();
+        let mut request = OcspRequest::new().unwrap();
+        request.add_id(fixture.cert_id(&fixture.good)).unwrap();
+
+        let error = respond_for(&[fixture.pki.ca_dir("root-ca")], &request.to_der().unwrap());
+        assert!(matches!(error, Err(OcspError::Unauthorized(_))));
+    }
+}
@@ -1,49 +1,139 @@
 // src/cert/openssl.rs
-use super::types::{AltNameType, CertificateConfig};
-use crate::cert::CertificateType;
+//
+// Native signing engine. Keys, CSRs and certificates are built directly with
+// the `openssl` crate from a `CertificateConfig`; nothing is written to disk
+// except the resulting key, CSR and certificate.
+use super::der;
+use super::types::{validate_dn_attribute, AltNameType, CertificateConfig, KeyAlgorithm};
 use crate::utils::logging::Logger;
-use std::{fs, io, path::Path, process::Command};
+use openssl::{
+    asn1::{Asn1Object, Asn1OctetString, Asn1Time},
+    bn::{BigNum, MsbOption},
+    ec::{EcGroup, EcKey},
+    error::ErrorStack,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{HasPublic, Id, PKey, PKeyRef, Private},
+    rsa::Rsa,
+    stack::Stack,
+    x509::{
+        extension::{
+            AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage,
+            SubjectAlternativeName, SubjectKeyIdentifier,
+        },
+        store::X509StoreBuilder,
+        X509Extension, X509Name, X509NameBuilder, X509Req, X509ReqBuilder, X509StoreContext,
+        X509v3Context, X509,
+    },
+};
+use std::{fs, io, path::Path};
+
+const OID_AUTHORITY_INFO_ACCESS: &str = "1.3.6.1.5.5.7.1.1";
+const OID_AD_OCSP: &str = "1.3.6.1.5.5.7.48.1";
+const OID_OCSP_NO_CHECK: &str = "1.3.6.1.5.5.7.48.1.5";
 
 #[derive(Debug)]
-pub struct OpenSSLError {
-    pub message: String,
-    pub stdout: String,
-    pub stderr: String,
+pub enum SigningError {
+    Io(io::Error),
+    OpenSSL(ErrorStack),
+    KeyNotFound(String),
+    CaNotFound(String),
+    InvalidConfig(String),
+    InvalidCsr(String),
+    Verification(String),
 }
 
-impl std::fmt::Display for OpenSSLError {
+impl std::fmt::Display for SigningError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message)
+        match self {
+            Self::Io(e) => write!(f, "IO error: {}", e),
+            Self::OpenSSL(e) => write!(f, "OpenSSL error: {}", e),
+            Self::KeyNotFound(path) => write!(f, "Private key not found: {}", path),
+            Self::CaNotFound(path) => write!(f, "CA file not found: {}", path),
+            Self::InvalidConfig(s) => write!(f, "Invalid certificate config: {}", s),
+            Self::InvalidCsr(s) => write!(f, "Invalid CSR: {}", s),
+            Self::Verification(s) => write!(f, "Verification failed: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for SigningError {}
+
+impl From<io::Error> for SigningError {
+    fn from(error: io::Error) -> Self {
+        SigningError::Io(error)
     }
 }
 
-impl std::error::Error for OpenSSLError {}
+impl From<ErrorStack> for SigningError {
+    fn from(error: ErrorStack) -> Self {
+        SigningError::OpenSSL(error)
+    }
+}
 
-pub fn generate_private_key(path: &str, key_size: u32, logger: &mut dyn Logger) -> io::Result<()> {
-    logger.debug_log(&format!("Generating private key: {}", path));
+impl From<SigningError> for io::Error {
+    fn from(error: SigningError) -> Self {
+        match error {
+            SigningError::Io(e) => e,
+            SigningError::KeyNotFound(_) | SigningError::CaNotFound(_) => {
+                io::Error::new(io::ErrorKind::NotFound, error.to_string())
+            }
+            SigningError::InvalidConfig(_) | SigningError::InvalidCsr(_) => {
+                io::Error::new(io::ErrorKind::InvalidInput, error.to_string())
+            }
+            _ => io::Error::new(io::ErrorKind::Other, error.to_string()),
+        }
+    }
+}
+
+pub fn generate_private_key(
+    path: &str,
+    algorithm: KeyAlgorithm,
+    logger: &mut dyn Logger,
+) -> Result<(), SigningError> {
+    logger.debug_log(&format!("Generating {} private key: {}", algorithm, path));
 
     // Create directory if it doesn't exist
     if let Some(parent) = Path::new(path).parent() {
         fs::create_dir_all(parent)?;
     }
 
-    let output = Command::new("openssl")
-        .args(&["genrsa", "-out", path, &key_size.to_string()])
-        .output()?;
+    let key = generate_key(algorithm)?;
+    write_private_key(path, &key)?;
 
-    if !output.status.success() {
-        let error = OpenSSLError {
-            message: format!("Failed to generate private key: {}", path),
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        };
-        logger.log(&error.message);
-        logger.debug_log(&format!(
-            "stdout: {}\nstderr: {}",
-            error.stdout, error.stderr
-        ));
-        return Err(io::Error::new(io::ErrorKind::Other, error.message));
+    logger.debug_log(&format!("Successfully generated private key: {}", path));
+    Ok(())
+}
+
+pub fn generate_key(algorithm: KeyAlgorithm) -> Result<PKey<Private>, ErrorStack> {
+    match algorithm {
+        KeyAlgorithm::Rsa2048 => PKey::from_rsa(Rsa::generate(2048)?),
+        KeyAlgorithm::Rsa3072 => PKey::from_rsa(Rsa::generate(3072)?),
+        KeyAlgorithm::Rsa4096 => PKey::from_rsa(Rsa::generate(4096)?),
+        KeyAlgorithm::EcdsaP256 => {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+            PKey::from_ec_key(EcKey::generate(&group)?)
+        }
+        KeyAlgorithm::EcdsaP384 => {
+            let group = EcGroup::from_curve_name(Nid::SECP384R1)?;
+            PKey::from_ec_key(EcKey::generate(&group)?)
+        }
+        KeyAlgorithm::Ed25519 => PKey::generate_ed25519(),
     }
+}
+
+/// Picks the signature digest matching the signing key. Ed25519 signs the
+/// message directly, so it takes the null digest.
+pub fn signing_digest<T: HasPublic>(key: &PKeyRef<T>) -> MessageDigest {
+    match key.id() {
+        Id::ED25519 => MessageDigest::null(),
+        Id::EC if key.bits() > 256 => MessageDigest::sha384(),
+        _ => MessageDigest::sha256(),
+    }
+}
+
+pub fn write_private_key(path: &str, key: &PKey<Private>) -> Result<(), SigningError> {
+    fs::write(path, key.private_key_to_pem_pkcs8()?)?;
 
     // Set proper permissions
     #[cfg(unix)]
@@ -52,62 +142,75 @@ pub fn generate_private_key(path: &str, key_size: u32, logger: &mut dyn Logger)
         fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
     }
 
-    logger.debug_log(&format!("Successfully generated private key: {}", path));
     Ok(())
 }
 
+pub fn write_public_key(key_path: &str, pub_path: &str) -> Result<(), SigningError> {
+    let key = load_private_key(key_path)?;
+    fs::write(pub_path, key.public_key_to_pem()?)?;
+    Ok(())
+}
+
+pub fn load_private_key(path: &str) -> Result<PKey<Private>, SigningError> {
+    if !Path::new(path).exists() {
+        return Err(SigningError::KeyNotFound(path.to_string()));
+    }
+    Ok(PKey::private_key_from_pem(&fs::read(path)?)?)
+}
+
+pub fn load_certificate(path: &str) -> Result<X509, SigningError> {
+    if !Path::new(path).exists() {
+        return Err(SigningError::CaNotFound(path.to_string()));
+    }
+    Ok(X509::from_pem(&fs::read(path)?)?)
+}
+
+/// Loads every certificate in a PEM bundle, e.g. `ca-chain.crt`.
+pub fn load_certificate_bundle(path: &str) -> Result<Vec<X509>, SigningError> {
+    if !Path::new(path).exists() {
+        return Err(SigningError::CaNotFound(path.to_string()));
+    }
+    let certs = X509::stack_from_pem(&fs::read(path)?)?;
+    if certs.is_empty() {
+        return Err(SigningError::Verification(format!(
+            "No certificates found in {}",
+            path
+        )));
+    }
+    Ok(certs)
+}
+
 pub fn generate_csr(
     config: &CertificateConfig,
     key_path: &str,
     csr_path: &str,
     logger: &mut dyn Logger,
-) -> io::Result<()> {
+) -> Result<(), SigningError> {
     logger.debug_log(&format!("Generating CSR: {}", csr_path));
 
-    // Validate key exists
-    if !Path::new(key_path).exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("Private key not found: {}", key_path),
-        ));
-    }
-
-    let config_content = generate_csr_config(config)?;
-    let config_path = format!("{}.cnf", csr_path);
-    fs::write(&config_path, config_content)?;
-
-    logger.debug_log(&format!("Using OpenSSL config: {}", config_path));
-
-    let output = Command::new("openssl")
-        .args(&[
-            "req",
-            "-new",
-            "-key",
-            key_path,
-            "-out",
-            csr_path,
-            "-config",
-            &config_path,
-            "-batch",
-        ])
-        .output()?;
-
-    // Clean up config file
-    let _ = fs::remove_file(&config_path);
-
-    if !output.status.success() {
-        let error = OpenSSLError {
-            message: format!("Failed to generate CSR: {}", csr_path),
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        };
-        logger.log(&error.message);
-        logger.debug_log(&format!(
-            "stdout: {}\nstderr: {}",
-            error.stdout, error.stderr
-        ));
-        return Err(io::Error::new(io::ErrorKind::Other, error.message));
+    let key = load_private_key(key_path)?;
+
+    let mut builder = X509ReqBuilder::new()?;
+    builder.set_version(0)?;
+    let subject = build_subject_name(config)?;
+    builder.set_subject_name(&subject)?;
+    builder.set_pubkey(&key)?;
+
+    let mut extensions = Stack::new()?;
+    extensions.push(BasicConstraints::new().build()?)?;
+    if let Some(ext) = key_usage_extension(&config.key_usage, &key)? {
+        extensions.push(ext)?;
     }
+    if let Some(ext) = extended_key_usage_extension(&config.extended_key_usage)? {
+        extensions.push(ext)?;
+    }
+    if let Some(ext) = subject_alt_name_extension(config, &builder.x509v3_context(None))? {
+        extensions.push(ext)?;
+    }
+    builder.add_extensions(&extensions)?;
+
+    builder.sign(&key, signing_digest(&key))?;
+    fs::write(csr_path, builder.build().to_pem()?)?;
 
     logger.debug_log(&format!("Successfully generated CSR: {}", csr_path));
     Ok(())
@@ -119,68 +222,89 @@ pub fn sign_certificate(
     ca_cert: &str,
     ca_key: &str,
     config: &CertificateConfig,
+    ocsp_url: Option<&str>,
     logger: &mut dyn Logger,
-) -> io::Result<()> {
-    // Check if CA files exist when needed
-    if config.cert_type != CertificateType::RootCA {
-        if !Path::new(ca_cert).exists() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                format!("CA certificate not found: {}", ca_cert),
-            ));
-        }
-        if !Path::new(ca_key).exists() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                format!("CA key not found: {}", ca_key),
-            ));
-        }
-    }
-
+) -> Result<(), SigningError> {
     logger.debug_log(&format!("Signing certificate: {}", cert_path));
 
-    // Create extensions file
-    let extensions_file = format!("{}.ext", cert_path);
-    create_extensions_file(&extensions_file, config)?;
-
-    let mut cmd = Command::new("openssl");
-    cmd.arg("x509").arg("-req");
+    let req = X509Req::from_pem(&fs::read(csr_path)?)?;
+    let req_key = req.public_key()?;
+    if !req.verify(&req_key)? {
+        return Err(SigningError::InvalidCsr(format!(
+            "Signature check failed for {}",
+            csr_path
+        )));
+    }
 
-    // Build command based on whether this is a self-signed cert
-    if config.cert_type == CertificateType::RootCA {
-        cmd.args(&["-signkey", ca_key]);
+    // Self-signed CAs: the CSR key signs and the subject is its own issuer
+    let issuer = if config.cert_type.is_self_signed() {
+        None
     } else {
-        cmd.args(&["-CA", ca_cert, "-CAkey", ca_key, "-CAcreateserial"]);
-    }
-
-    cmd.args(&[
-        "-in",
-        csr_path,
-        "-out",
-        cert_path,
-        "-days",
-        &config.validity_days.to_string(),
-        "-extfile",
-        &extensions_file,
-    ]);
+        if !Path::new(ca_key).exists() {
+            return Err(SigningError::CaNotFound(ca_key.to_string()));
+        }
+        Some(load_certificate(ca_cert)?)
+    };
+    let signing_key = load_private_key(ca_key)?;
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    let serial = random_serial()?.to_asn1_integer()?;
+    builder.set_serial_number(&serial)?;
+    builder.set_subject_name(req.subject_name())?;
+    match &issuer {
+        Some(ca) => builder.set_issuer_name(ca.subject_name())?,
+        None => builder.set_issuer_name(req.subject_name())?,
+    }
+    builder.set_pubkey(&req_key)?;
+    builder.set_not_before(Asn1Time::days_from_now(0)?.as_ref())?;
+    builder.set_not_after(Asn1Time::days_from_now(config.validity_days)?.as_ref())?;
+
+    // Extensions always come from the config, never from the CSR
+    let mut basic_constraints = BasicConstraints::new();
+    basic_constraints.critical();
+    if config.cert_type.is_ca() {
+        basic_constraints.ca();
+    }
+    builder.append_extension(basic_constraints.build()?)?;
 
-    logger.debug_log(&format!("Executing OpenSSL command: {:?}", cmd));
+    if let Some(ext) = key_usage_extension(&config.key_usage, &req_key)? {
+        builder.append_extension(ext)?;
+    }
+    if let Some(ext) = extended_key_usage_extension(&config.extended_key_usage)? {
+        builder.append_extension(ext)?;
+    }
 
-    let output = cmd.output()?;
+    let subject_key_id = SubjectKeyIdentifier::new()
+        .build(&builder.x509v3_context(issuer.as_deref(), None))?;
+    builder.append_extension(subject_key_id)?;
 
-    // Clean up extensions file
-    let _ = fs::remove_file(&extensions_file);
+    if let Some(ca) = &issuer {
+        let authority_key_id = AuthorityKeyIdentifier::new()
+            .keyid(true)
+            .build(&builder.x509v3_context(Some(ca), None))?;
+        builder.append_extension(authority_key_id)?;
+    }
 
-    if !output.status.success() {
-        let error = OpenSSLError {
-            message: (cert_path.to_string()),
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        };
-        // logger.log(&error.message);
-        return Err(io::Error::new(io::ErrorKind::Other, error.message));
+    let san = subject_alt_name_extension(
+        config,
+        &builder.x509v3_context(issuer.as_deref(), None),
+    )?;
+    if let Some(ext) = san {
+        builder.append_extension(ext)?;
     }
 
+    // Responders are trusted for their validity period and never checked
+    // themselves (RFC 6960 4.2.2.2.1); everything else points at the responder.
+    if is_ocsp_signer(config) {
+        builder.append_extension(der_extension(OID_OCSP_NO_CHECK, false, &der::null())?)?;
+    } else if let Some(url) = ocsp_url.filter(|_| !config.cert_type.is_ca()) {
+        builder.append_extension(authority_info_access_extension(url)?)?;
+    }
+
+    builder.sign(&signing_key, signing_digest(&signing_key))?;
+    fs::write(cert_path, builder.build().to_pem()?)?;
+
     logger.debug_log(&format!("Successfully signed certificate: {}", cert_path));
     Ok(())
 }
@@ -189,47 +313,21 @@ pub fn verify_certificate(
     cert_path: &str,
     ca_cert: Option<&str>,
     logger: &mut dyn Logger,
-) -> io::Result<()> {
+) -> Result<(), SigningError> {
     logger.debug_log(&format!("Verifying certificate: {}", cert_path));
 
-    // Basic certificate info check
-    let basic_check = Command::new("openssl")
-        .args(&["x509", "-in", cert_path, "-noout", "-text"])
-        .output()?;
-
-    if !basic_check.status.success() {
-        let error = OpenSSLError {
-            message: format!("Certificate basic check failed: {}", cert_path),
-            stdout: String::from_utf8_lossy(&basic_check.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&basic_check.stderr).to_string(),
-        };
-        logger.log(&error.message);
-        logger.debug_log(&format!(
-            "stdout: {}\nstderr: {}",
-            error.stdout, error.stderr
-        ));
-        return Err(io::Error::new(io::ErrorKind::Other, error.message));
-    }
+    // Basic certificate parse check
+    let cert = load_certificate(cert_path)?;
 
     // Verify against CA if provided
     if let Some(ca) = ca_cert {
         logger.debug_log(&format!("Verifying against CA: {}", ca));
-        let chain_check = Command::new("openssl")
-            .args(&["verify", "-CAfile", ca, cert_path])
-            .output()?;
-
-        if !chain_check.status.success() {
-            let error = OpenSSLError {
-                message: format!("Certificate chain verification failed: {}", cert_path),
-                stdout: String::from_utf8_lossy(&chain_check.stdout).to_string(),
-                stderr: String::from_utf8_lossy(&chain_check.stderr).to_string(),
-            };
-            logger.log(&error.message);
-            logger.debug_log(&format!(
-                "stdout: {}\nstderr: {}",
-                error.stdout, error.stderr
+        if let Err(e) = verify_against_bundle(&cert, &load_certificate_bundle(ca)?) {
+            logger.log(&format!(
+                "Certificate chain verification failed: {}",
+                cert_path
             ));
-            return Err(io::Error::new(io::ErrorKind::Other, error.message));
+            return Err(e);
         }
     }
 
@@ -237,145 +335,210 @@ pub fn verify_certificate(
     Ok(())
 }
 
-fn generate_csr_config(config: &CertificateConfig) -> io::Result<String> {
-    let mut content = format!(
-        r#"[req]
-req_extensions = v3_req
-distinguished_name = req_distinguished_name
-prompt = no
-
-[req_distinguished_name]
-CN = {}
-O = {}
-"#,
-        config.common_name,
-        config.organization.as_deref().unwrap_or("Kubernetes")
-    );
+/// Verifies the certificate at `cert_path` against the CA bundle at `ca_path`.
+pub fn verify_chain(cert_path: &str, ca_path: &str) -> Result<(), SigningError> {
+    verify_against_bundle(&load_certificate(cert_path)?, &load_certificate_bundle(ca_path)?)
+}
 
-    // Add optional location fields if present
-    if let Some(country) = &config.country {
-        content.push_str(&format!("C = {}\n", country));
+/// Verifies `cert` against a set of trusted CA certificates.
+pub fn verify_against_bundle(cert: &X509, trusted: &[X509]) -> Result<(), SigningError> {
+    let mut store = X509StoreBuilder::new()?;
+    for ca in trusted {
+        store.add_cert(ca.clone())?;
     }
-    if let Some(state) = &config.state {
-        content.push_str(&format!("ST = {}\n", state));
+    let store = store.build();
+
+    let chain = Stack::new()?;
+    let mut context = X509StoreContext::new()?;
+    let mut error = None;
+    let valid = context.init(&store, cert, &chain, |ctx| {
+        let valid = ctx.verify_cert()?;
+        if !valid {
+            error = Some(ctx.error().error_string().to_string());
+        }
+        Ok(valid)
+    })?;
+
+    if valid {
+        Ok(())
+    } else {
+        Err(SigningError::Verification(
+            error.unwrap_or_else(|| "unknown verification error".to_string()),
+        ))
     }
-    if let Some(locality) = &config.locality {
-        content.push_str(&format!("L = {}\n", locality));
+}
+
+fn is_ocsp_signer(config: &CertificateConfig) -> bool {
+    config.extended_key_usage.iter().any(|u| u == "OCSPSigning")
+}
+
+/// Builds an extension from its DER encoded value, for the extensions the
+/// `openssl` crate has no builder for.
+pub fn der_extension(oid: &str, critical: bool, value: &[u8]) -> Result<X509Extension, ErrorStack> {
+    let object = Asn1Object::from_str(oid)?;
+    let value = Asn1OctetString::new_from_bytes(value)?;
+    X509Extension::new_from_der(&object, critical, &value)
+}
+
+/// AuthorityInfoAccess with a single OCSP location:
+/// SEQUENCE { SEQUENCE { id-ad-ocsp, [6] IA5String url } }
+fn authority_info_access_extension(url: &str) -> Result<X509Extension, SigningError> {
+    if !url.is_ascii() || url.is_empty() {
+        return Err(SigningError::InvalidConfig(format!("Invalid OCSP URL: {}", url)));
     }
+    let access_description = der::sequence(&[
+        der::oid(OID_AD_OCSP),
+        der::tlv(der::context_tag(6, false), url.as_bytes()),
+    ]);
+    Ok(der_extension(
+        OID_AUTHORITY_INFO_ACCESS,
+        false,
+        &der::sequence(&[access_description]),
+    )?)
+}
 
-    // Add v3_req section
-    content.push_str("\n[v3_req]\nbasicConstraints = CA:FALSE\n");
+fn random_serial() -> Result<BigNum, ErrorStack> {
+    // 127 random bits keeps the serial positive and within the 20 octet limit
+    let mut serial = BigNum::new()?;
+    serial.rand(127, MsbOption::MAYBE_ZERO, false)?;
+    Ok(serial)
+}
+
+fn build_subject_name(config: &CertificateConfig) -> Result<X509Name, SigningError> {
+    let mut name = X509NameBuilder::new()?;
 
-    // Add key usage if present
-    if !config.key_usage.is_empty() {
-        content.push_str(&format!("keyUsage = {}\n", config.key_usage.join(", ")));
+    for (attribute, value) in config.subject_attributes() {
+        validate_dn_attribute(attribute, value).map_err(SigningError::InvalidConfig)?;
+        name.append_entry_by_text(attribute, value)?;
     }
 
-    // Add extended key usage if present
-    if !config.extended_key_usage.is_empty() {
-        content.push_str(&format!(
-            "extendedKeyUsage = {}\n",
-            config.extended_key_usage.join(", ")
-        ));
+    Ok(name.build())
+}
+
+/// Builds the key usage of a certificate for `key`. Profiles list
+/// `keyEncipherment` for RSA; it is left out for EC and Ed25519 keys, which
+/// cannot encipher (RFC 5480, RFC 8410), as kubeadm does.
+fn key_usage_extension<T: HasPublic>(
+    usages: &[String],
+    key: &PKeyRef<T>,
+) -> Result<Option<X509Extension>, SigningError> {
+    if usages.is_empty() {
+        return Ok(None);
     }
 
-    if !config.alt_names.is_empty() {
-        content.push_str("subjectAltName = @alt_names\n\n[alt_names]\n");
-        for (i, alt_name) in config.alt_names.iter().enumerate() {
-            match alt_name.alt_type {
-                AltNameType::IP => {
-                    content.push_str(&format!("IP.{} = {}\n", i + 1, alt_name.value));
-                }
-                AltNameType::DNS => {
-                    content.push_str(&format!("DNS.{} = {}\n", i + 1, alt_name.value));
-                }
+    let mut key_usage = KeyUsage::new();
+    for usage in usages {
+        match usage.as_str() {
+            "keyEncipherment" if key.id() != Id::RSA => &mut key_usage,
+            "critical" => key_usage.critical(),
+            "digitalSignature" => key_usage.digital_signature(),
+            "nonRepudiation" | "contentCommitment" => key_usage.non_repudiation(),
+            "keyEncipherment" => key_usage.key_encipherment(),
+            "dataEncipherment" => key_usage.data_encipherment(),
+            "keyAgreement" => key_usage.key_agreement(),
+            "keyCertSign" => key_usage.key_cert_sign(),
+            "cRLSign" => key_usage.crl_sign(),
+            "encipherOnly" => key_usage.encipher_only(),
+            "decipherOnly" => key_usage.decipher_only(),
+            other => {
+                return Err(SigningError::InvalidConfig(format!(
+                    "Unsupported key usage: {}",
+                    other
+                )))
             }
-        }
+        };
     }
 
-    Ok(content)
+    Ok(Some(key_usage.build()?))
 }
 
-fn create_extensions_file(path: &str, config: &CertificateConfig) -> io::Result<()> {
-    let mut content = String::new();
-
-    // Basic constraints
-    match config.cert_type {
-        CertificateType::RootCA | CertificateType::KubernetesCA => {
-            content.push_str("basicConstraints = critical,CA:TRUE\n");
-        }
-        _ => {
-            content.push_str("basicConstraints = critical,CA:FALSE\n");
-        }
+fn extended_key_usage_extension(
+    usages: &[String],
+) -> Result<Option<X509Extension>, SigningError> {
+    if usages.is_empty() {
+        return Ok(None);
     }
 
-    // Key usage
-    if !config.key_usage.is_empty() {
-        content.push_str(&format!("keyUsage = {}\n", config.key_usage.join(", ")));
+    let mut extended_key_usage = ExtendedKeyUsage::new();
+    for usage in usages {
+        match usage.as_str() {
+            "critical" => extended_key_usage.critical(),
+            "serverAuth" => extended_key_usage.server_auth(),
+            "clientAuth" => extended_key_usage.client_auth(),
+            "codeSigning" => extended_key_usage.code_signing(),
+            "emailProtection" => extended_key_usage.email_protection(),
+            "timeStamping" => extended_key_usage.time_stamping(),
+            "OCSPSigning" => extended_key_usage.other("OCSPSigning"),
+            other => {
+                return Err(SigningError::InvalidConfig(format!(
+                    "Unsupported extended key usage: {}",
+                    other
+                )))
+            }
+        };
     }
 
-    // Extended key usage
-    if !config.extended_key_usage.is_empty() {
-        content.push_str(&format!(
-            "extendedKeyUsage = {}\n",
-            config.extended_key_usage.join(", ")
-        ));
+    Ok(Some(extended_key_usage.build()?))
+}
+
+fn subject_alt_name_extension(
+    config: &CertificateConfig,
+    context: &X509v3Context,
+) -> Result<Option<X509Extension>, SigningError> {
+    if config.alt_names.is_empty() {
+        return Ok(None);
     }
 
-    // Subject Alternative Names
-    if !config.alt_names.is_empty() {
-        content.push_str("subjectAltName = @alt_names\n\n[alt_names]\n");
-        for (i, alt_name) in config.alt_names.iter().enumerate() {
-            match alt_name.alt_type {
-                AltNameType::IP => {
-                    content.push_str(&format!("IP.{} = {}\n", i + 1, alt_name.value));
-                }
-                AltNameType::DNS => {
-                    content.push_str(&format!("DNS.{} = {}\n", i + 1, alt_name.value));
+    let mut san = SubjectAlternativeName::new();
+    for alt_name in &config.alt_names {
+        match alt_name.alt_type {
+            AltNameType::IP => {
+                if alt_name.value.parse::<std::net::IpAddr>().is_err() {
+                    return Err(SigningError::InvalidConfig(format!(
+                        "Invalid IP SAN: {}",
+                        alt_name.value
+                    )));
                 }
+                san.ip(&alt_name.value)
             }
-        }
+            AltNameType::DNS => san.dns(&alt_name.value),
+        };
     }
 
-    fs::write(path, content)
+    Ok(Some(san.build(context)?))
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use tempfile::TempDir;
-//
-//     struct MockLogger {
-//         logs: Vec<String>,
-//     }
-//
-//     impl MockLogger {
-//         fn new() -> Self {
-//             Self { logs: Vec::new() }
-//         }
-//     }
-//
-//     impl Logger for MockLogger {
-//         fn log(&mut self, message: &str) {
-//             self.logs.push(message.to_string());
-//         }
-//
-//         fn debug_log(&mut self, message: &str) {
-//             self.logs.push(format!("DEBUG: {}", message));
-//         }
-//     }
-//
-//     #[test]
-//     fn test_generate_private_key() -> io::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         let key_path = temp_dir.path().join("test.key");
-//         let mut logger = MockLogger::new();
-//
-//         generate_private_key(key_path.to_str().unwrap(), 2048, &mut logger)?;
-//
-//         assert!(key_path.exists());
-//         Ok(())
-//     }
-//
-//     // Add more tests...
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert::{profiles::TemplateContext, testing::TestPki, KeyAlgorithms};
+    use x509_parser::prelude::{FromDer, X509Certificate};
+
+    /// Issues the admin certificate with leaf keys of `algorithm` and
+    /// returns its key usage as (digitalSignature, keyEncipherment).
+    fn admin_key_usage(algorithm: KeyAlgorithm) -> (bool, bool) {
+        let mut pki = TestPki::new().with_key_algorithms(KeyAlgorithms {
+            leaf: algorithm,
+            ..KeyAlgorithms::default()
+        });
+        pki.issue("root-ca", &TemplateContext::new());
+        pki.issue("kubernetes-ca", &TemplateContext::new());
+        let (cert, _) = pki.issue("admin", &TemplateContext::new());
+
+        let der = load_certificate(&cert).unwrap().to_der().unwrap();
+        let (_, parsed) = X509Certificate::from_der(&der).unwrap();
+        let key_usage = parsed.key_usage().unwrap().unwrap();
+        assert!(key_usage.critical);
+        (
+            key_usage.value.digital_signature(),
+            key_usage.value.key_encipherment(),
+        )
+    }
+
+    #[test]
+    fn key_encipherment_is_only_set_for_rsa_keys() {
+        assert_eq!(admin_key_usage(KeyAlgorithm::EcdsaP256), (true, false));
+        assert_eq!(admin_key_usage(KeyAlgorithm::Ed25519), (true, false));
+        assert_eq!(admin_key_usage(KeyAlgorithm::Rsa2048), (true, true));
+    }
+}
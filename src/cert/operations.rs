@@ -1,18 +1,22 @@
 // src/cert/operations.rs
 
-use uuid::Uuid;
-
+use crate::remote::{FilePermissions, PermissionPolicy, RemoteLayout, Transport};
+use crate::state::CertificateRecord;
 use crate::utils::logging::Logger;
 use std::path::Path;
-use std::process::Command;
-use std::{fs, io, path::PathBuf};
+use std::sync::Arc;
+use std::{fs, io};
 
-use super::openssl::{generate_csr, generate_private_key, sign_certificate};
-use super::{CertificateConfig, CertificateType};
+use super::ocsp;
+use super::openssl::{generate_csr, generate_private_key, sign_certificate, SigningError};
+use super::revocation::{record_issued_certificate, RevocationDb};
+use super::profiles::{ProfileSet, TemplateContext};
+use super::{validate_node_name, CertificateConfig, KeyAlgorithms};
 
 #[derive(Debug)]
 pub enum CertOperationError {
     IoError(io::Error),
+    Signing(SigningError),
     CertGeneration(String),
     Distribution(String),
     Verification(String),
@@ -24,6 +28,7 @@ impl From<CertOperationError> for io::Error {
             CertOperationError::IoError(e) => {
                 io::Error::new(e.kind(), format!("Certificate operation IO error: {}", e))
             }
+            CertOperationError::Signing(e) => io::Error::from(e),
             CertOperationError::CertGeneration(s) => io::Error::new(
                 io::ErrorKind::Other,
                 format!("Certificate generation error: {}", s),
@@ -44,6 +49,7 @@ impl std::fmt::Display for CertOperationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::IoError(e) => write!(f, "IO Error: {}", e),
+            Self::Signing(e) => write!(f, "Signing Error: {}", e),
             Self::CertGeneration(s) => write!(f, "Certificate Generation Error: {}", s),
             Self::Distribution(s) => write!(f, "Distribution Error: {}", s),
             Self::Verification(s) => write!(f, "Verification Error: {}", s),
@@ -59,25 +65,43 @@ impl From<io::Error> for CertOperationError {
     }
 }
 
+impl From<SigningError> for CertOperationError {
+    fn from(error: SigningError) -> Self {
+        CertOperationError::Signing(error)
+    }
+}
+
 pub struct CertificateOperations {
     logger: Box<dyn Logger>,
-    remote_dir: String,
-    remote_user: String,
-    ssh_key_path: String,
+    layout: RemoteLayout,
+    transport: Arc<dyn Transport>,
+    key_algorithms: KeyAlgorithms,
+    ocsp_url: Option<String>,
+    profiles: ProfileSet,
+    permissions: PermissionPolicy,
+    /// Certificates issued since the state database was last saved.
+    issued: Vec<CertificateRecord>,
 }
 
 impl CertificateOperations {
     pub fn new(
         logger: Box<dyn Logger>,
-        remote_dir: String,
-        remote_user: String,
-        ssh_key_path: String,
+        layout: RemoteLayout,
+        transport: Arc<dyn Transport>,
+        key_algorithms: KeyAlgorithms,
+        ocsp_url: Option<String>,
+        profiles: ProfileSet,
+        permissions: PermissionPolicy,
     ) -> Self {
         Self {
             logger,
-            remote_dir,
-            remote_user,
-            ssh_key_path,
+            layout,
+            transport,
+            key_algorithms,
+            ocsp_url,
+            profiles,
+            permissions,
+            issued: Vec::new(),
         }
     }
 
@@ -90,32 +114,38 @@ impl CertificateOperations {
         self.logger.debug_log(message);
     }
 
-    fn ensure_remote_directory(&mut self, host: &str) -> io::Result<()> {
-        self.debug_log(&format!("Ensuring remote directory exists on {}", host));
-
-        let ssh_output = Command::new("ssh")
-            .args(&[
-                "-i",
-                &self.ssh_key_path,
-                &format!("{}@{}", self.remote_user, host),
-                &format!(
-                    "sudo mkdir -p {} && sudo chown root:root {}",
-                    self.remote_dir, self.remote_dir
-                ),
-            ])
-            .output()?;
-
-        if !ssh_output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!(
-                    "Failed to create remote directory: {}",
-                    String::from_utf8_lossy(&ssh_output.stderr)
-                ),
-            ));
-        }
+    pub fn logger_mut(&mut self) -> &mut dyn Logger {
+        self.logger.as_mut()
+    }
 
-        Ok(())
+    pub fn key_algorithms(&self) -> &KeyAlgorithms {
+        &self.key_algorithms
+    }
+
+    pub fn issued(&self) -> &[CertificateRecord] {
+        &self.issued
+    }
+
+    /// Forgets the issued certificates once they are in the state database.
+    pub fn clear_issued(&mut self) {
+        self.issued.clear();
+    }
+
+    /// Takes over certificates issued through another instance, e.g. the
+    /// OCSP responder certificates renewed in the background.
+    pub fn extend_issued(&mut self, records: &[CertificateRecord]) {
+        self.issued.extend_from_slice(records);
+    }
+
+    /// Returns the node name the kubelet on `host` registers with: its
+    /// hostname, lowercased.
+    pub fn remote_hostname(&mut self, host: &str) -> io::Result<String> {
+        self.debug_log(&format!("Discovering hostname of {}", host));
+
+        let hostname = self.transport.exec(host, "hostname")?.trim().to_lowercase();
+        validate_node_name(&hostname)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(hostname)
     }
 
     pub fn generate_cert(
@@ -153,15 +183,15 @@ impl CertificateOperations {
             config.cert_type, cert_name
         ));
 
-        // For root CA, use its own directory for CA files since it's self-signed
-        let (ca_cert, ca_key) = if config.cert_type == CertificateType::RootCA {
+        // Self-signed CAs use their own directory for CA files
+        let (ca_cert, ca_key) = if config.cert_type.is_self_signed() {
             (key_path.clone(), key_path.clone())
         } else {
             (format!("{}/ca.crt", ca_dir), format!("{}/ca.key", ca_dir))
         };
 
         self.logger.log("Generating private key");
-        if let Err(e) = generate_private_key(&key_path, config.key_size, self.logger.as_mut()) {
+        if let Err(e) = generate_private_key(&key_path, config.key_algorithm, self.logger.as_mut()) {
             self.logger
                 .log(&format!("Failed to generate private key: {}", e));
             return Err(CertOperationError::from(e));
@@ -184,6 +214,7 @@ impl CertificateOperations {
             &ca_cert,
             &ca_key,
             config,
+            self.ocsp_url.as_deref(),
             self.logger.as_mut(),
         ) {
             self.logger
@@ -191,54 +222,83 @@ impl CertificateOperations {
             return Err(CertOperationError::from(e));
         }
 
+        // A new CA key invalidates its old issuance records
+        if config.cert_type.is_ca() {
+            RevocationDb::reset(Path::new(cert_dir))?;
+        }
+
+        if !config.cert_type.is_self_signed() {
+            if let Err(e) = record_issued_certificate(ca_dir, &cert_path) {
+                self.logger
+                    .log(&format!("Failed to record issued certificate: {}", e));
+                return Err(CertOperationError::IoError(e.into()));
+            }
+        }
+
+        // Every CA answers OCSP through its own delegated responder
+        if config.cert_type.is_ca() {
+            self.issue_ocsp_signer(cert_dir)?;
+        }
+
         Ok(())
     }
 
+    /// Renders a certificate profile and generates the certificate it describes.
+    pub fn generate_from_profile(
+        &mut self,
+        profile: &str,
+        context: &TemplateContext,
+        hosts: &[&str],
+    ) -> Result<(), CertOperationError> {
+        let resolved = self
+            .profiles
+            .resolve(profile, context, &self.key_algorithms)
+            .map_err(|e| CertOperationError::CertGeneration(e.to_string()))?;
+        self.generate_cert(&resolved.cert_name, &resolved.ca_dir, &resolved.config, hosts)?;
+        self.record_issued(profile, &resolved.config.output_dir, &resolved.cert_name);
+        Ok(())
+    }
+
+    /// Issues the delegated OCSP responder certificate of a CA into
+    /// `{ca_dir}/ocsp-signer/`.
+    pub fn issue_ocsp_signer(&mut self, ca_dir: &str) -> Result<(), CertOperationError> {
+        let mut resolved = self
+            .profiles
+            .resolve(
+                ocsp::SIGNER_NAME,
+                &TemplateContext::new().with_ca(ca_dir),
+                &self.key_algorithms,
+            )
+            .map_err(|e| CertOperationError::CertGeneration(e.to_string()))?;
+
+        // The responder loads its certificate from a fixed location
+        resolved.config.output_dir = ocsp::signer_dir(Path::new(ca_dir));
+        self.generate_cert(ocsp::SIGNER_NAME, ca_dir, &resolved.config, &[])?;
+        self.record_issued(ocsp::SIGNER_NAME, &resolved.config.output_dir, ocsp::SIGNER_NAME);
+        Ok(())
+    }
+
+    /// Keeps a certificate just issued for the state database's history. One
+    /// that cannot be read back is only logged; it was issued all the same.
+    fn record_issued(&mut self, profile: &str, output_dir: &Path, cert_name: &str) {
+        let cert_path = format!("{}/{}.crt", output_dir.display(), cert_name);
+        let key_path = format!("{}/{}.key", output_dir.display(), cert_name);
+        match CertificateRecord::read(&cert_path, Some(&key_path), Some(profile)) {
+            Ok(record) => self.issued.push(record),
+            Err(e) => self.logger.log(&format!(
+                "Failed to record {} in the certificate history: {}",
+                cert_path, e
+            )),
+        }
+    }
+
     // New method to set up all CA certificates
     pub fn setup_ca_certificates(&mut self, hosts: &[&str]) -> Result<(), CertOperationError> {
         // 1. Generate Root CA
-        let root_config = CertificateConfig {
-            cert_type: CertificateType::RootCA,
-            common_name: "Kubernetes Root CA".to_string(),
-            organization: Some("Kubernetes".to_string()),
-            validity_days: 3650,
-            key_size: 2048,
-            output_dir: PathBuf::from("certs/root-ca"),
-            alt_names: vec![],
-            key_usage: vec![
-                "critical".to_string(),
-                "keyCertSign".to_string(),
-                "cRLSign".to_string(),
-            ],
-            extended_key_usage: vec![],
-            country: Some("US".to_string()),
-            state: Some("Columbia".to_string()),
-            locality: Some("Columbia".to_string()),
-        };
-
-        self.generate_cert("ca", "certs/root-ca", &root_config, hosts)?;
+        self.generate_from_profile("root-ca", &TemplateContext::new(), hosts)?;
 
         // 2. Generate Kubernetes CA
-        let k8s_config = CertificateConfig {
-            cert_type: CertificateType::KubernetesCA,
-            common_name: "kubernetes-ca".to_string(),
-            organization: Some("Kubernetes".to_string()),
-            validity_days: 3650,
-            key_size: 2048,
-            output_dir: PathBuf::from("certs/kubernetes-ca"),
-            alt_names: vec![],
-            key_usage: vec![
-                "critical".to_string(),
-                "keyCertSign".to_string(),
-                "cRLSign".to_string(),
-            ],
-            extended_key_usage: vec![],
-            country: Some("US".to_string()),
-            state: Some("Columbia".to_string()),
-            locality: Some("Columbia".to_string()),
-        };
-
-        self.generate_cert("ca", "certs/root-ca", &k8s_config, hosts)?;
+        self.generate_from_profile("kubernetes-ca", &TemplateContext::new(), hosts)?;
         self.create_ca_chain()?;
 
         Ok(())
@@ -272,191 +332,16 @@ impl CertificateOperations {
         Ok(())
     }
 
-    // pub fn distribute_certificates(
-    //     &mut self,
-    //     cert_name: &str,
-    //     hosts: &Vec<String>,
-    // ) -> Result<(), CertOperationError> {
-    //     for host in hosts {
-    //         // Ensure remote directory exists before copying
-    //         if let Err(e) = self.ensure_remote_directory(host) {
-    //             self.log(&format!(
-    //                 "Failed to create remote directory on {}: {}",
-    //                 host, e
-    //             ));
-    //             return Err(CertOperationError::Distribution(format!(
-    //                 "Failed to create remote directory on {}: {}",
-    //                 host, e
-    //             )));
-    //         }
-    //
-    //         self.copy_to_k8s_paths(cert_name, host).map_err(|e| {
-    //             CertOperationError::Distribution(format!("Failed to distribute to {}: {}", host, e))
-    //         })?;
-    //     }
-    //     Ok(())
-    // }
-
-    // Distribution methods stay mostly the same but with improved error handling
-    pub fn copy_to_k8s_paths(&mut self, cert_name: &str, remote_host: &str) -> io::Result<()> {
-        self.logger
-            .log(&format!("Copying {} to {}", cert_name, remote_host));
-
-        // Handle different types of files with their full paths
-        let (source_path, remote_path) = match cert_name {
-            name if name.starts_with("kubeconfig/") => (
-                name.to_string(),
-                format!(
-                    "/etc/kubernetes/{}",
-                    name.strip_prefix("kubeconfig/").unwrap()
-                ),
-            ),
-            "encryption-config.yaml" => (
-                cert_name.to_string(),
-                format!("{}/encryption-config.yaml", self.remote_dir),
-            ),
-            name if name.starts_with("certs/") => (
-                name.to_string(),
-                format!(
-                    "{}/{}",
-                    self.remote_dir,
-                    Path::new(name).file_name().unwrap().to_str().unwrap()
-                ),
-            ),
-            _ => (
-                format!("certs/{}", cert_name),
-                format!(
-                    "{}/{}",
-                    self.remote_dir,
-                    Path::new(cert_name).file_name().unwrap().to_str().unwrap()
-                ),
-            ),
-        };
-
-        self.ensure_remote_directory(remote_host)?;
-
-        // Debug log the exact paths being used
-        self.debug_log(&format!("Source path: {}", source_path));
-        self.debug_log(&format!("Remote path: {}", remote_path));
-
-        self.copy_with_sudo(&source_path, &remote_path, remote_host)?;
-
-        self.logger.log(&format!(
-            "Successfully copied {} to {}:{}",
-            cert_name, remote_host, remote_path
-        ));
-        Ok(())
+    /// Owner, group and mode of the file of `cert_type` installed at `remote`.
+    pub fn file_permissions(&self, cert_type: &str, remote: &str) -> FilePermissions {
+        self.permissions.for_file(cert_type, remote)
     }
 
-    pub fn copy_with_sudo(&mut self, source: &str, target: &str, host: &str) -> io::Result<()> {
-        // Generate a unique temporary filename
-        let unique_id = Uuid::new_v4();
-        let temp_file = format!("/tmp/cert_{}", unique_id);
-
-        // First, copy to temporary location with restrictive permissions
-        let scp_output = Command::new("scp")
-            .args(&[
-                "-i",
-                &self.ssh_key_path,
-                source,
-                &format!("{}@{}:{}", self.remote_user, host, temp_file),
-            ])
-            .output()?;
-
-        if !scp_output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!(
-                    "Failed to copy file: {}",
-                    String::from_utf8_lossy(&scp_output.stderr)
-                ),
-            ));
-        }
-
-        // Then move to final location with sudo and clean up
-        let ssh_commands = format!(
-            "sudo mkdir -p $(dirname {}) && \
-             sudo mv {} {} && \
-             sudo chown root:root {} && \
-             sudo chmod 600 {} && \
-             rm -f {}",
-            target, temp_file, target, target, target, temp_file
-        );
-
-        let ssh_output = Command::new("ssh")
-            .args(&[
-                "-i",
-                &self.ssh_key_path,
-                &format!("{}@{}", self.remote_user, host),
-                &ssh_commands,
-            ])
-            .output()?;
-
-        if !ssh_output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!(
-                    "Failed to execute sudo commands: {}",
-                    String::from_utf8_lossy(&ssh_output.stderr)
-                ),
-            ));
-        }
-
-        Ok(())
-    }
-
-    pub fn generate_service_account_keys(&mut self, hosts: &[&str]) -> io::Result<()> {
-        self.logger.log("Generating service account keys");
-
-        let sa_dir = PathBuf::from("certs/service-account");
-        fs::create_dir_all(&sa_dir)?;
-
-        // Generate private key
-        let key_path = sa_dir.join("sa.key");
-        let output = Command::new("openssl")
-            .args(&[
-                "genpkey",
-                "-algorithm",
-                "RSA",
-                "-out",
-                key_path.to_str().unwrap(),
-                "-pkeyopt",
-                "rsa_keygen_bits:2048",
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to generate service account private key",
-            ));
-        }
-
-        // Generate public key
-        let pub_path = sa_dir.join("sa.pub");
-        let output = Command::new("openssl")
-            .args(&[
-                "rsa",
-                "-in",
-                key_path.to_str().unwrap(),
-                "-pubout",
-                "-out",
-                pub_path.to_str().unwrap(),
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to generate service account public key",
-            ));
-        }
-
-        // Distribute keys
-        for host in hosts {
-            self.copy_to_k8s_paths("service-account", host)?;
-        }
-
-        Ok(())
+    /// Local source of a distributed file and where the remote layout
+    /// installs it on `host`.
+    pub fn k8s_paths(&self, cert_name: &str, host: &str) -> (String, String) {
+        let source = self.layout.source(cert_name);
+        let remote = self.layout.target(&source, host);
+        (source, remote)
     }
 }
@@ -0,0 +1,383 @@
+// src/cert/profiles.rs
+//
+// Declarative certificate profiles. The built-in set is embedded from
+// `default_profiles.yaml`; a cluster may override individual fields or add
+// profiles with its own file. Profiles are rendered against a
+// `TemplateContext` into the `CertificateConfig` the signing engine consumes.
+use super::types::{
+    AltName, CertificateConfig, CertificateType, EtcdMember, KeyAlgorithm, KeyAlgorithms,
+    SubjectDefaults, WorkerNode,
+};
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_PROFILES: &str = include_str!("default_profiles.yaml");
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(io::Error),
+    Parse(String),
+    UnknownProfile(String),
+    Template { profile: String, message: String },
+}
+
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "IO error: {}", e),
+            Self::Parse(s) => write!(f, "Invalid certificate profiles: {}", s),
+            Self::UnknownProfile(s) => write!(f, "No certificate profile named {}", s),
+            Self::Template { profile, message } => {
+                write!(f, "Cannot render profile {}: {}", profile, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<io::Error> for ProfileError {
+    fn from(error: io::Error) -> Self {
+        ProfileError::Io(error)
+    }
+}
+
+impl From<ProfileError> for io::Error {
+    fn from(error: ProfileError) -> Self {
+        match error {
+            ProfileError::Io(e) => e,
+            ProfileError::UnknownProfile(_) => {
+                io::Error::new(io::ErrorKind::NotFound, error.to_string())
+            }
+            _ => io::Error::new(io::ErrorKind::InvalidData, error.to_string()),
+        }
+    }
+}
+
+/// One certificate profile as written in the profile file. The fields listed
+/// in `default_profiles.yaml` may contain `{{variable}}` placeholders.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CertificateProfile {
+    pub cert_type: String,
+    /// File stem of the key and certificate; defaults to the profile name.
+    #[serde(default)]
+    pub cert_name: Option<String>,
+    /// Directory of the issuing CA.
+    pub issuer: String,
+    pub output_dir: String,
+    pub common_name: String,
+    #[serde(default)]
+    pub organization: Option<String>,
+    pub validity_days: u32,
+    /// Overrides the cluster's CA or leaf key algorithm for this profile.
+    #[serde(default)]
+    pub key_algorithm: Option<KeyAlgorithm>,
+    /// `DNS:<name>` or `IP:<address>`.
+    #[serde(default)]
+    pub alt_names: Vec<String>,
+    #[serde(default)]
+    pub key_usage: Vec<String>,
+    #[serde(default)]
+    pub extended_key_usage: Vec<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub locality: Option<String>,
+    #[serde(default)]
+    pub organizational_unit: Option<String>,
+    #[serde(default)]
+    pub email_address: Option<String>,
+}
+
+/// A profile rendered for one concrete certificate.
+#[derive(Debug, Clone)]
+pub struct ResolvedProfile {
+    pub cert_name: String,
+    pub ca_dir: String,
+    pub config: CertificateConfig,
+}
+
+/// Values substituted into profile templates.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    values: HashMap<String, String>,
+}
+
+impl TemplateContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.values.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn with_control_plane(self, address: &str) -> Self {
+        self.with("control_plane.ip", address)
+    }
+
+    /// A worker node. `node.hostname` falls back to the address when that is
+    /// not an IP, and is empty when neither is known.
+    pub fn with_node(self, node: &WorkerNode) -> Self {
+        let address = node.address.as_str();
+        let is_ip = address.parse::<IpAddr>().is_ok();
+        let hostname = match &node.hostname {
+            Some(hostname) => hostname.as_str(),
+            None if is_ip => "",
+            None => address,
+        };
+        self.with("node.name", node.name())
+            .with("node.index", (node.index + 1).to_string())
+            .with("node.address", address)
+            .with("node.ip", if is_ip { address } else { "" })
+            .with("node.hostname", hostname)
+    }
+
+    pub fn with_ca(self, ca_dir: &str) -> Self {
+        let name = Path::new(ca_dir)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| ca_dir.to_string());
+        self.with("ca.name", name).with("ca.dir", ca_dir)
+    }
+
+    pub fn with_etcd_member(self, member: &EtcdMember) -> Self {
+        self.with("member.name", member.name.as_str())
+            .with("member.ip", member.ip.clone().unwrap_or_default())
+            .with("member.hostname", member.hostname.clone().unwrap_or_default())
+    }
+
+    /// Directory of the CA that issues the etcd certificates.
+    pub fn with_etcd_ca(self, ca_dir: &str) -> Self {
+        self.with("etcd.ca_dir", ca_dir)
+    }
+
+    fn render(&self, template: &str) -> Result<String, String> {
+        let mut output = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| format!("unterminated placeholder in '{}'", template))?;
+            let key = after[..end].trim();
+            let value = self
+                .values
+                .get(key)
+                .ok_or_else(|| format!("unknown variable '{}'", key))?;
+            output.push_str(value);
+            rest = &after[end + 2..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+}
+
+/// The profiles available to a cluster: the built-in set with any
+/// per-cluster overrides applied, plus the cluster's subject defaults for
+/// the DN attributes a profile leaves unset.
+#[derive(Debug, Clone)]
+pub struct ProfileSet {
+    profiles: BTreeMap<String, CertificateProfile>,
+    subject: SubjectDefaults,
+}
+
+impl Default for ProfileSet {
+    fn default() -> Self {
+        Self::load(None, SubjectDefaults::default())
+            .expect("built-in certificate profiles are valid")
+    }
+}
+
+impl ProfileSet {
+    /// Loads the built-in profiles and merges `override_path` over them. Each
+    /// profile in the override file replaces only the fields it sets; profiles
+    /// with new names are added.
+    pub fn load(
+        override_path: Option<&str>,
+        subject: SubjectDefaults,
+    ) -> Result<Self, ProfileError> {
+        let mut merged = parse_profiles(DEFAULT_PROFILES)?;
+
+        if let Some(path) = override_path {
+            let overrides = parse_profiles(&fs::read_to_string(path)?)?;
+            for (name, fields) in overrides {
+                match (merged.get_mut(&name), fields) {
+                    (Some(Value::Mapping(base)), Value::Mapping(fields)) => {
+                        for (key, value) in fields {
+                            base.insert(key, value);
+                        }
+                    }
+                    (_, fields) => {
+                        merged.insert(name, fields);
+                    }
+                }
+            }
+        }
+
+        let mut profiles = BTreeMap::new();
+        for (name, value) in merged {
+            let profile: CertificateProfile = serde_yaml::from_value(value)
+                .map_err(|e| ProfileError::Parse(format!("{}: {}", name, e)))?;
+            parse_cert_type(&profile.cert_type, &name)
+                .map_err(|message| ProfileError::Parse(format!("{}: {}", name, message)))?;
+            profiles.insert(name, profile);
+        }
+
+        Ok(Self { profiles, subject })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&CertificateProfile, ProfileError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| ProfileError::UnknownProfile(name.to_string()))
+    }
+
+    /// Renders the named profile into a concrete certificate request.
+    pub fn resolve(
+        &self,
+        name: &str,
+        context: &TemplateContext,
+        key_algorithms: &KeyAlgorithms,
+    ) -> Result<ResolvedProfile, ProfileError> {
+        let profile = self.get(name)?;
+        let template_error = |message: String| ProfileError::Template {
+            profile: name.to_string(),
+            message,
+        };
+        let render = |template: &str| context.render(template).map_err(template_error);
+
+        let cert_name = render(profile.cert_name.as_deref().unwrap_or(name))?;
+        let cert_type = match parse_cert_type(&profile.cert_type, &cert_name) {
+            Ok(cert_type) => cert_type,
+            Err(message) => return Err(template_error(message)),
+        };
+
+        let mut alt_names: Vec<AltName> = Vec::new();
+        for entry in &profile.alt_names {
+            let (kind, value) = entry.split_once(':').ok_or_else(|| {
+                template_error(format!("alt name '{}' must start with DNS: or IP:", entry))
+            })?;
+            let value = render(value.trim())?;
+            if value.is_empty() {
+                continue;
+            }
+            let alt_name = match kind.trim() {
+                "DNS" => AltName::dns(value),
+                "IP" => AltName::ip(value),
+                other => {
+                    return Err(template_error(format!("unknown alt name type '{}'", other)))
+                }
+            };
+            if !alt_names
+                .iter()
+                .any(|a| a.alt_type == alt_name.alt_type && a.value == alt_name.value)
+            {
+                alt_names.push(alt_name);
+            }
+        }
+
+        let key_algorithm = profile.key_algorithm.unwrap_or(if cert_type.is_ca() {
+            key_algorithms.ca
+        } else {
+            key_algorithms.leaf
+        });
+
+        let config = CertificateConfig {
+            cert_type,
+            common_name: render(&profile.common_name)?,
+            organization: match &profile.organization {
+                Some(organization) => Some(render(organization)?),
+                None => self.subject.organization.clone(),
+            },
+            validity_days: profile.validity_days,
+            key_algorithm,
+            output_dir: PathBuf::from(render(&profile.output_dir)?),
+            alt_names,
+            key_usage: profile.key_usage.clone(),
+            extended_key_usage: profile.extended_key_usage.clone(),
+            country: profile.country.clone().or_else(|| self.subject.country.clone()),
+            state: profile.state.clone().or_else(|| self.subject.state.clone()),
+            locality: profile.locality.clone().or_else(|| self.subject.locality.clone()),
+            organizational_unit: profile
+                .organizational_unit
+                .clone()
+                .or_else(|| self.subject.organizational_unit.clone()),
+            email_address: profile
+                .email_address
+                .clone()
+                .or_else(|| self.subject.email_address.clone()),
+        };
+
+        Ok(ResolvedProfile {
+            cert_name,
+            ca_dir: render(&profile.issuer)?,
+            config,
+        })
+    }
+}
+
+fn parse_profiles(contents: &str) -> Result<BTreeMap<String, Value>, ProfileError> {
+    let document: Value =
+        serde_yaml::from_str(contents).map_err(|e| ProfileError::Parse(e.to_string()))?;
+    let profiles = match document.get("profiles") {
+        Some(Value::Mapping(profiles)) => profiles.clone(),
+        Some(_) => return Err(ProfileError::Parse("'profiles' must be a mapping".to_string())),
+        None => Mapping::new(),
+    };
+
+    let mut named = BTreeMap::new();
+    for (name, profile) in profiles {
+        match name {
+            Value::String(name) => {
+                named.insert(name, profile);
+            }
+            other => {
+                return Err(ProfileError::Parse(format!(
+                    "profile names must be strings, found {:?}",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(named)
+}
+
+/// Maps a profile's `cert_type` onto `CertificateType`. Kubelet certificates
+/// carry their rendered certificate name.
+fn parse_cert_type(value: &str, cert_name: &str) -> Result<CertificateType, String> {
+    Ok(match value {
+        "RootCA" => CertificateType::RootCA,
+        "KubernetesCA" => CertificateType::KubernetesCA,
+        "APIServer" => CertificateType::APIServer,
+        "KubeletClient" => CertificateType::KubeletClient,
+        "ServiceAccount" => CertificateType::ServiceAccount,
+        "ControllerManager" => CertificateType::ControllerManager,
+        "Scheduler" => CertificateType::Scheduler,
+        "Node" => CertificateType::Node(cert_name.to_string()),
+        "KubeletServing" => CertificateType::KubeletServing(cert_name.to_string()),
+        "Admin" => CertificateType::Admin,
+        "KubeProxy" => CertificateType::KubeProxy,
+        "OcspSigner" => CertificateType::OcspSigner,
+        "EtcdCA" => CertificateType::EtcdCA,
+        "EtcdServer" => CertificateType::EtcdServer,
+        "EtcdPeer" => CertificateType::EtcdPeer,
+        "EtcdHealthcheckClient" => CertificateType::EtcdHealthcheckClient,
+        "ApiServerEtcdClient" => CertificateType::ApiServerEtcdClient,
+        "FrontProxyCA" => CertificateType::FrontProxyCA,
+        "FrontProxyClient" => CertificateType::FrontProxyClient,
+        other => return Err(format!("unknown cert_type '{}'", other)),
+    })
+}
+
+
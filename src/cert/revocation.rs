@@ -0,0 +1,841 @@
+// src/cert/revocation.rs
+//
+// Per-CA revocation database and CRL publishing. Each CA directory keeps an
+// OpenSSL-compatible `index.txt` of everything it has issued, a `crlnumber`
+// counter and the most recently signed `crl.pem`.
+use super::der;
+use super::openssl::{
+    der_extension, load_certificate, load_private_key, signing_digest, SigningError,
+};
+use crate::utils::logging::Logger;
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use openssl::{
+    asn1::{Asn1Time, Asn1TimeRef},
+    bn::BigNum,
+    x509::{X509Crl, X509CrlBuilder, X509Revoked, X509RevokedBuilder, X509},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const INDEX_FILE: &str = "index.txt";
+const CRL_NUMBER_FILE: &str = "crlnumber";
+const CRL_FILE: &str = "crl.pem";
+const CA_ROOT: &str = "certs";
+/// `openssl ca` writes UTCTime, whose two-digit years cover 1950-2049, and
+/// GeneralizedTime outside of that range.
+const INDEX_TIME_FORMAT: &str = "%y%m%d%H%M%SZ";
+const INDEX_GENERALIZED_TIME_FORMAT: &str = "%Y%m%d%H%M%SZ";
+
+const OID_CRL_NUMBER: &str = "2.5.29.20";
+const OID_AUTHORITY_KEY_ID: &str = "2.5.29.35";
+const OID_CRL_REASON: &str = "2.5.29.21";
+
+#[derive(Debug)]
+pub enum RevocationError {
+    Io(io::Error),
+    Signing(SigningError),
+    UnknownCertificate(String),
+    AlreadyRevoked(String),
+    InvalidDatabase(String),
+}
+
+impl std::fmt::Display for RevocationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "IO error: {}", e),
+            Self::Signing(e) => write!(f, "{}", e),
+            Self::UnknownCertificate(s) => write!(f, "No issued certificate matches {}", s),
+            Self::AlreadyRevoked(s) => write!(f, "Certificate {} is already revoked", s),
+            Self::InvalidDatabase(s) => write!(f, "Invalid revocation database: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for RevocationError {}
+
+impl From<io::Error> for RevocationError {
+    fn from(error: io::Error) -> Self {
+        RevocationError::Io(error)
+    }
+}
+
+impl From<SigningError> for RevocationError {
+    fn from(error: SigningError) -> Self {
+        RevocationError::Signing(error)
+    }
+}
+
+impl From<openssl::error::ErrorStack> for RevocationError {
+    fn from(error: openssl::error::ErrorStack) -> Self {
+        RevocationError::Signing(SigningError::from(error))
+    }
+}
+
+impl From<RevocationError> for io::Error {
+    fn from(error: RevocationError) -> Self {
+        match error {
+            RevocationError::Io(e) => e,
+            RevocationError::Signing(e) => io::Error::from(e),
+            RevocationError::UnknownCertificate(_) => {
+                io::Error::new(io::ErrorKind::NotFound, error.to_string())
+            }
+            RevocationError::AlreadyRevoked(_) => {
+                io::Error::new(io::ErrorKind::InvalidInput, error.to_string())
+            }
+            RevocationError::InvalidDatabase(_) => {
+                io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+            }
+        }
+    }
+}
+
+/// RFC 5280 CRL reason codes. `removeFromCRL` only applies to delta CRLs and
+/// is left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevocationReason {
+    Unspecified,
+    KeyCompromise,
+    CaCompromise,
+    AffiliationChanged,
+    Superseded,
+    CessationOfOperation,
+    CertificateHold,
+    PrivilegeWithdrawn,
+    AaCompromise,
+}
+
+impl RevocationReason {
+    pub const ALL: [RevocationReason; 9] = [
+        RevocationReason::Unspecified,
+        RevocationReason::KeyCompromise,
+        RevocationReason::CaCompromise,
+        RevocationReason::AffiliationChanged,
+        RevocationReason::Superseded,
+        RevocationReason::CessationOfOperation,
+        RevocationReason::CertificateHold,
+        RevocationReason::PrivilegeWithdrawn,
+        RevocationReason::AaCompromise,
+    ];
+
+    pub fn code(&self) -> u8 {
+        match self {
+            RevocationReason::Unspecified => 0,
+            RevocationReason::KeyCompromise => 1,
+            RevocationReason::CaCompromise => 2,
+            RevocationReason::AffiliationChanged => 3,
+            RevocationReason::Superseded => 4,
+            RevocationReason::CessationOfOperation => 5,
+            RevocationReason::CertificateHold => 6,
+            RevocationReason::PrivilegeWithdrawn => 9,
+            RevocationReason::AaCompromise => 10,
+        }
+    }
+
+    /// Name used in `index.txt`, matching `openssl ca -crl_reason`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevocationReason::Unspecified => "unspecified",
+            RevocationReason::KeyCompromise => "keyCompromise",
+            RevocationReason::CaCompromise => "CACompromise",
+            RevocationReason::AffiliationChanged => "affiliationChanged",
+            RevocationReason::Superseded => "superseded",
+            RevocationReason::CessationOfOperation => "cessationOfOperation",
+            RevocationReason::CertificateHold => "certificateHold",
+            RevocationReason::PrivilegeWithdrawn => "privilegeWithdrawn",
+            RevocationReason::AaCompromise => "AACompromise",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|r| r.as_str().eq_ignore_ascii_case(value))
+    }
+}
+
+impl std::fmt::Display for RevocationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryStatus {
+    Valid,
+    Revoked,
+}
+
+/// One line of `index.txt`:
+/// `status  expiry  revocation[,reason]  serial  file  subject`
+#[derive(Debug, Clone)]
+pub struct RevocationEntry {
+    pub status: EntryStatus,
+    pub expires: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub reason: Option<RevocationReason>,
+    pub serial: String,
+    pub path: String,
+    pub subject: String,
+}
+
+impl RevocationEntry {
+    /// The certificate's directory below `certs/`, e.g. `kube-apiserver` or
+    /// `nodes/worker-1`.
+    pub fn name(&self) -> String {
+        let Some(dir) = Path::new(&self.path).parent() else {
+            return self.path.clone();
+        };
+        match dir.strip_prefix(CA_ROOT) {
+            Ok(relative) => relative.to_string_lossy().to_string(),
+            Err(_) => dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.path.clone()),
+        }
+    }
+
+    fn parse(line: &str) -> Result<Self, RevocationError> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 6 {
+            return Err(RevocationError::InvalidDatabase(format!(
+                "expected 6 fields, found {}: {}",
+                fields.len(),
+                line
+            )));
+        }
+
+        let status = match fields[0] {
+            "V" | "E" => EntryStatus::Valid,
+            "R" => EntryStatus::Revoked,
+            other => {
+                return Err(RevocationError::InvalidDatabase(format!(
+                    "unknown status {}",
+                    other
+                )))
+            }
+        };
+
+        let (revoked_at, reason) = if fields[2].is_empty() {
+            (None, None)
+        } else {
+            let mut parts = fields[2].splitn(2, ',');
+            let time = parse_index_time(parts.next().unwrap_or_default())?;
+            let reason = parts.next().and_then(RevocationReason::parse);
+            (Some(time), reason)
+        };
+
+        Ok(Self {
+            status,
+            expires: parse_index_time(fields[1])?,
+            revoked_at,
+            reason,
+            serial: fields[3].to_string(),
+            path: fields[4].to_string(),
+            subject: fields[5].to_string(),
+        })
+    }
+
+    fn to_line(&self) -> String {
+        let status = match self.status {
+            EntryStatus::Valid if self.expires < Utc::now() => "E",
+            EntryStatus::Valid => "V",
+            EntryStatus::Revoked => "R",
+        };
+        let revocation = match (self.revoked_at, self.reason) {
+            (Some(at), Some(reason)) => {
+                format!("{},{}", format_index_time(at), reason.as_str())
+            }
+            (Some(at), None) => format_index_time(at),
+            _ => String::new(),
+        };
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            status,
+            format_index_time(self.expires),
+            revocation,
+            self.serial,
+            self.path,
+            self.subject
+        )
+    }
+}
+
+/// The issuance record of a single CA directory.
+pub struct RevocationDb {
+    ca_dir: PathBuf,
+    entries: Vec<RevocationEntry>,
+}
+
+impl RevocationDb {
+    pub fn open(ca_dir: &Path) -> Result<Self, RevocationError> {
+        let index_path = ca_dir.join(INDEX_FILE);
+        let entries = if index_path.exists() {
+            fs::read_to_string(&index_path)?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(RevocationEntry::parse)
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            ca_dir: ca_dir.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Drops all revocation state for a CA. Called whenever the CA itself is
+    /// regenerated, since the old records refer to a key that no longer exists.
+    pub fn reset(ca_dir: &Path) -> io::Result<()> {
+        for file in [INDEX_FILE, CRL_NUMBER_FILE, CRL_FILE] {
+            let path = ca_dir.join(file);
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn ca_name(&self) -> String {
+        self.ca_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn entries(&self) -> &[RevocationEntry] {
+        &self.entries
+    }
+
+    /// Looks up an entry by serial, in any case and with or without colons.
+    pub fn find(&self, serial: &str) -> Option<&RevocationEntry> {
+        let wanted = normalize_serial(serial);
+        self.entries
+            .iter()
+            .find(|e| normalize_serial(&e.serial) == wanted)
+    }
+
+    pub fn save(&self) -> Result<(), RevocationError> {
+        let mut contents: String = self
+            .entries
+            .iter()
+            .map(|e| e.to_line() + "\n")
+            .collect();
+        if contents.is_empty() {
+            contents.push('\n');
+        }
+        fs::write(self.ca_dir.join(INDEX_FILE), contents)?;
+        Ok(())
+    }
+
+    pub fn record_issued(&mut self, cert: &X509, cert_path: &str) -> Result<(), RevocationError> {
+        let serial = serial_hex(cert)?;
+        self.entries.retain(|e| e.serial != serial);
+        self.entries.push(RevocationEntry {
+            status: EntryStatus::Valid,
+            expires: asn1_to_datetime(cert.not_after())?,
+            revoked_at: None,
+            reason: None,
+            serial,
+            path: cert_path.to_string(),
+            subject: subject_line(cert),
+        });
+        Ok(())
+    }
+
+    /// Marks a certificate as revoked. `serial` may be given in any case and
+    /// with or without colons.
+    pub fn revoke(
+        &mut self,
+        serial: &str,
+        reason: RevocationReason,
+    ) -> Result<RevocationEntry, RevocationError> {
+        let wanted = normalize_serial(serial);
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| normalize_serial(&e.serial) == wanted)
+            .ok_or_else(|| RevocationError::UnknownCertificate(serial.to_string()))?;
+
+        if entry.status == EntryStatus::Revoked {
+            return Err(RevocationError::AlreadyRevoked(serial.to_string()));
+        }
+
+        entry.status = EntryStatus::Revoked;
+        entry.revoked_at = Some(Utc::now());
+        entry.reason = Some(reason);
+        Ok(entry.clone())
+    }
+
+    pub fn crl_path(&self) -> PathBuf {
+        self.ca_dir.join(CRL_FILE)
+    }
+
+    /// Signs a fresh CRL covering every revoked entry and writes it to
+    /// `crl.pem`, bumping `crlnumber`.
+    pub fn generate_crl(
+        &self,
+        validity_days: u32,
+        logger: &mut dyn Logger,
+    ) -> Result<PathBuf, RevocationError> {
+        let ca_cert_path = self.ca_dir.join("ca.crt");
+        let ca_key_path = self.ca_dir.join("ca.key");
+        let ca_cert = load_certificate(&ca_cert_path.to_string_lossy())?;
+        let ca_key = load_private_key(&ca_key_path.to_string_lossy())?;
+
+        let key_id = ca_cert.subject_key_id().ok_or_else(|| {
+            SigningError::InvalidConfig(format!(
+                "{} has no subject key identifier",
+                ca_cert_path.display()
+            ))
+        })?;
+
+        let crl_number = self.next_crl_number()?;
+        logger.debug_log(&format!(
+            "Generating CRL #{} for {}",
+            crl_number,
+            self.ca_name()
+        ));
+
+        let mut builder = X509CrlBuilder::new()?;
+        builder.set_issuer_name(ca_cert.subject_name())?;
+        let last_update = Asn1Time::days_from_now(0)?;
+        let next_update = Asn1Time::days_from_now(validity_days)?;
+        builder.set_last_update(&last_update)?;
+        builder.set_next_update(&next_update)?;
+
+        let mut revoked_count = 0;
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| e.status == EntryStatus::Revoked)
+        {
+            builder.add_revoked(revoked_entry(entry)?)?;
+            revoked_count += 1;
+        }
+
+        builder.append_extension(der_extension(
+            OID_CRL_NUMBER,
+            false,
+            &der::integer(&BigNum::from_u32(crl_number)?.to_vec()),
+        )?)?;
+
+        // AuthorityKeyIdentifier ::= SEQUENCE { keyIdentifier [0] IMPLICIT OCTET STRING }
+        let key_identifier = der::tlv(der::context_tag(0, false), key_id.as_slice());
+        builder.append_extension(der_extension(
+            OID_AUTHORITY_KEY_ID,
+            false,
+            &der::sequence(&[key_identifier]),
+        )?)?;
+
+        builder.sort()?;
+        builder.sign(&ca_key, signing_digest(&ca_key))?;
+        let crl = builder.build()?;
+
+        let crl_path = self.crl_path();
+        fs::write(&crl_path, crl.to_pem()?)?;
+        fs::write(
+            self.ca_dir.join(CRL_NUMBER_FILE),
+            format!("{:02X}\n", crl_number + 1),
+        )?;
+
+        logger.log(&format!(
+            "Published CRL for {} ({} revoked)",
+            self.ca_name(),
+            revoked_count
+        ));
+        Ok(crl_path)
+    }
+
+    fn next_crl_number(&self) -> Result<u32, RevocationError> {
+        let path = self.ca_dir.join(CRL_NUMBER_FILE);
+        if !path.exists() {
+            return Ok(1);
+        }
+        let contents = fs::read_to_string(&path)?;
+        u32::from_str_radix(contents.trim(), 16)
+            .map_err(|_| RevocationError::InvalidDatabase(format!("bad crlnumber: {}", contents)))
+    }
+}
+
+/// What to revoke: a single serial, or every valid certificate with the given
+/// name (its directory under `certs/`, e.g. `admin` or `nodes/worker-2`).
+#[derive(Debug, Clone)]
+pub enum RevocationTarget {
+    Serial(String),
+    Name(String),
+}
+
+/// Every CA directory under `certs/` that keeps a revocation database.
+pub fn ca_directories() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(CA_ROOT)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.join(INDEX_FILE).exists())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Records a freshly signed certificate in its issuing CA's database.
+pub fn record_issued_certificate(ca_dir: &str, cert_path: &str) -> Result<(), RevocationError> {
+    let cert = load_certificate(cert_path)?;
+    let mut db = RevocationDb::open(Path::new(ca_dir))?;
+    db.record_issued(&cert, cert_path)?;
+    db.save()
+}
+
+/// Revokes the target in whichever CA issued it. Returns the CA name and the
+/// updated entry for every certificate that was revoked.
+pub fn revoke_certificate(
+    target: &RevocationTarget,
+    reason: RevocationReason,
+) -> Result<Vec<(String, RevocationEntry)>, RevocationError> {
+    let mut revoked = Vec::new();
+
+    for ca_dir in ca_directories() {
+        let mut db = RevocationDb::open(&ca_dir)?;
+        let serials: Vec<String> = db
+            .entries()
+            .iter()
+            .filter(|e| e.status == EntryStatus::Valid)
+            .filter(|e| match target {
+                RevocationTarget::Serial(serial) => {
+                    normalize_serial(&e.serial) == normalize_serial(serial)
+                }
+                RevocationTarget::Name(name) => e.name() == *name,
+            })
+            .map(|e| e.serial.clone())
+            .collect();
+
+        if serials.is_empty() {
+            continue;
+        }
+
+        for serial in serials {
+            revoked.push((db.ca_name(), db.revoke(&serial, reason)?));
+        }
+        db.save()?;
+    }
+
+    if revoked.is_empty() {
+        let description = match target {
+            RevocationTarget::Serial(serial) => format!("serial {}", serial),
+            RevocationTarget::Name(name) => format!("name {}", name),
+        };
+        return Err(RevocationError::UnknownCertificate(description));
+    }
+
+    Ok(revoked)
+}
+
+/// Revokes the target and re-signs the CRL of every CA that issued a revoked
+/// certificate, so the change is published immediately.
+pub fn revoke_and_publish(
+    target: &RevocationTarget,
+    reason: RevocationReason,
+    validity_days: u32,
+    logger: &mut dyn Logger,
+) -> Result<Vec<(String, RevocationEntry)>, RevocationError> {
+    let revoked = revoke_certificate(target, reason)?;
+
+    let mut affected_cas: Vec<&String> = Vec::new();
+    for (ca, entry) in &revoked {
+        logger.log(&format!(
+            "Revoked {} (serial {}, issued by {}) with reason {}",
+            entry.name(),
+            entry.serial,
+            ca,
+            reason
+        ));
+        if !affected_cas.contains(&ca) {
+            affected_cas.push(ca);
+        }
+    }
+
+    for ca in affected_cas {
+        RevocationDb::open(&Path::new(CA_ROOT).join(ca))?.generate_crl(validity_days, logger)?;
+    }
+
+    Ok(revoked)
+}
+
+/// Re-signs the CRL of every CA. Failures are logged per CA so one broken
+/// directory does not stop the others from being refreshed.
+pub fn refresh_crls(validity_days: u32, logger: &mut dyn Logger) -> Vec<PathBuf> {
+    let mut published = Vec::new();
+    for ca_dir in ca_directories() {
+        match RevocationDb::open(&ca_dir).and_then(|db| db.generate_crl(validity_days, logger)) {
+            Ok(path) => published.push(path),
+            Err(e) => logger.log(&format!(
+                "Failed to refresh CRL for {}: {}",
+                ca_dir.display(),
+                e
+            )),
+        }
+    }
+    published
+}
+
+/// Loads the published CRL of a CA by name.
+pub fn load_crl(ca_name: &str) -> Result<X509Crl, RevocationError> {
+    if ca_name.is_empty()
+        || !ca_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(RevocationError::UnknownCertificate(ca_name.to_string()));
+    }
+
+    let path = Path::new(CA_ROOT).join(ca_name).join(CRL_FILE);
+    if !path.exists() {
+        return Err(RevocationError::UnknownCertificate(format!(
+            "CRL for {}",
+            ca_name
+        )));
+    }
+    Ok(X509Crl::from_pem(&fs::read(path)?)?)
+}
+
+fn revoked_entry(entry: &RevocationEntry) -> Result<X509Revoked, RevocationError> {
+    let revoked_at = entry.revoked_at.ok_or_else(|| {
+        RevocationError::InvalidDatabase(format!("{} has no revocation time", entry.serial))
+    })?;
+
+    let mut builder = X509RevokedBuilder::new()?;
+    let serial = BigNum::from_hex_str(&entry.serial)?.to_asn1_integer()?;
+    let revocation_date = Asn1Time::from_unix(revoked_at.timestamp())?;
+    builder.set_serial_number(&serial)?;
+    builder.set_revocation_date(&revocation_date)?;
+    let revoked = builder.build();
+
+    // RFC 5280 5.3.1: omit the reason code rather than encode `unspecified`.
+    let reason = match entry.reason {
+        Some(reason) if reason != RevocationReason::Unspecified => reason,
+        _ => return Ok(revoked),
+    };
+
+    // The builder has no extension support, so append crlEntryExtensions to
+    // the encoded entry: SEQUENCE { serial, date, SEQUENCE { reasonCode } }.
+    let extension = der::sequence(&[
+        der::oid(OID_CRL_REASON),
+        der::octet_string(&der::enumerated(reason.code())),
+    ]);
+    let extensions = der::sequence(&[extension]);
+
+    let encoded = revoked.to_der()?;
+    let body = der::DerReader::new(&encoded)
+        .read_tag(der::TAG_SEQUENCE)
+        .ok_or_else(|| {
+            RevocationError::InvalidDatabase(format!("could not encode entry {}", entry.serial))
+        })?
+        .contents;
+
+    Ok(X509Revoked::from_der(&der::tlv(
+        der::TAG_SEQUENCE,
+        &[body, extensions.as_slice()].concat(),
+    ))?)
+}
+
+fn serial_hex(cert: &X509) -> Result<String, RevocationError> {
+    Ok(cert.serial_number().to_bn()?.to_hex_str()?.to_string())
+}
+
+fn normalize_serial(serial: &str) -> String {
+    let cleaned: String = serial
+        .chars()
+        .filter(|c| *c != ':')
+        .collect::<String>()
+        .to_uppercase();
+    let trimmed = cleaned.trim_start_matches('0');
+    if trimmed.is_empty() {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn subject_line(cert: &X509) -> String {
+    cert.subject_name()
+        .entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("UNDEF");
+            let value = entry.data().to_string().unwrap_or_default();
+            format!("/{}={}", key, value)
+        })
+        .collect()
+}
+
+fn asn1_to_datetime(time: &Asn1TimeRef) -> Result<DateTime<Utc>, RevocationError> {
+    let diff = Asn1Time::from_unix(0)?.diff(time)?;
+    let seconds = i64::from(diff.days) * 86_400 + i64::from(diff.secs);
+    DateTime::from_timestamp(seconds, 0)
+        .ok_or_else(|| RevocationError::InvalidDatabase(format!("time out of range: {}", time)))
+}
+
+fn format_index_time(time: DateTime<Utc>) -> String {
+    let format = if (1950..2050).contains(&time.year()) {
+        INDEX_TIME_FORMAT
+    } else {
+        INDEX_GENERALIZED_TIME_FORMAT
+    };
+    time.format(format).to_string()
+}
+
+fn parse_index_time(value: &str) -> Result<DateTime<Utc>, RevocationError> {
+    let invalid = || RevocationError::InvalidDatabase(format!("bad timestamp {}", value));
+    let time = if value.len() == 13 {
+        // chrono reads 50-68 as 20xx, UTCTime as 19xx
+        let time =
+            NaiveDateTime::parse_from_str(value, INDEX_TIME_FORMAT).map_err(|_| invalid())?;
+        if time.year() >= 2050 {
+            time.with_year(time.year() - 100).ok_or_else(invalid)?
+        } else {
+            time
+        }
+    } else {
+        NaiveDateTime::parse_from_str(value, INDEX_GENERALIZED_TIME_FORMAT)
+            .map_err(|_| invalid())?
+    };
+    Ok(time.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert::{profiles::TemplateContext, testing::TestPki};
+    use crate::utils::logging::ConsoleLogger;
+    use openssl::x509::{CrlNumber, ReasonCode};
+    use x509_parser::prelude::{CertificateRevocationList, FromDer, ParsedExtension};
+
+    fn serial(cert_path: &str) -> String {
+        serial_hex(&load_certificate(cert_path).unwrap()).unwrap()
+    }
+
+    fn crl_number(crl: &X509Crl) -> u32 {
+        let (_, number) = crl.extension::<CrlNumber>().unwrap().unwrap();
+        number
+            .to_bn()
+            .unwrap()
+            .to_dec_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn published_crl_lists_revoked_certificates() {
+        let mut pki = TestPki::new();
+        pki.issue("root-ca", &TemplateContext::new());
+        pki.issue("kubernetes-ca", &TemplateContext::new());
+        let (compromised, _) = pki.issue("admin", &TemplateContext::new());
+        let (retired, _) = pki.issue(
+            "kube-apiserver",
+            &TemplateContext::new().with_control_plane("10.0.0.1"),
+        );
+
+        let ca_dir = pki.ca_dir("kubernetes-ca");
+        let mut db = RevocationDb::open(&ca_dir).unwrap();
+        db.revoke(&serial(&compromised), RevocationReason::KeyCompromise)
+            .unwrap();
+        db.revoke(&serial(&retired), RevocationReason::Unspecified)
+            .unwrap();
+        db.save().unwrap();
+
+        let db = RevocationDb::open(&ca_dir).unwrap();
+        let mut logger = ConsoleLogger::new(false);
+        let mut publish = || {
+            let path = db.generate_crl(7, &mut logger).unwrap();
+            X509Crl::from_pem(&fs::read(path).unwrap()).unwrap()
+        };
+        let first = publish();
+        let crl = publish();
+        assert_eq!(crl_number(&first), 1);
+        assert_eq!(crl_number(&crl), 2);
+
+        let ca_cert = load_certificate(&ca_dir.join("ca.crt").to_string_lossy()).unwrap();
+        let ca_key = load_private_key(&ca_dir.join("ca.key").to_string_lossy()).unwrap();
+        assert!(crl.verify(&ca_key).unwrap());
+        assert_eq!(
+            crl.issuer_name().to_der().unwrap(),
+            ca_cert.subject_name().to_der().unwrap()
+        );
+
+        let der = crl.to_der().unwrap();
+        let (_, parsed) = CertificateRevocationList::from_der(&der).unwrap();
+        let authority_key_id = parsed
+            .extensions()
+            .iter()
+            .find_map(|extension| match extension.parsed_extension() {
+                ParsedExtension::AuthorityKeyIdentifier(aki) => aki.key_identifier.clone(),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            authority_key_id.0,
+            ca_cert.subject_key_id().unwrap().as_slice()
+        );
+
+        let revoked = crl.get_revoked().unwrap();
+        assert_eq!(revoked.len(), 2);
+        for (cert_path, reason) in [(&compromised, Some(1)), (&retired, None)] {
+            let serial = serial(cert_path);
+            let entry = revoked
+                .iter()
+                .find(|e| {
+                    e.serial_number()
+                        .to_bn()
+                        .unwrap()
+                        .to_hex_str()
+                        .unwrap()
+                        .to_string()
+                        == serial
+                })
+                .unwrap();
+            // `unspecified` is expressed by leaving the reason code out
+            let code = entry
+                .extension::<ReasonCode>()
+                .unwrap()
+                .map(|(critical, code)| {
+                    assert!(!critical);
+                    code.get_i64().unwrap()
+                });
+            assert_eq!(code, reason);
+
+            let revoked_at = db.find(&serial).and_then(|e| e.revoked_at).unwrap();
+            let expected = Asn1Time::from_unix(revoked_at.timestamp()).unwrap();
+            assert_eq!(entry.revocation_date().to_string(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn index_times_round_trip() {
+        for (time, encoded) in [
+            ("1999-12-31T23:59:59Z", "991231235959Z"),
+            ("2026-10-17T08:30:00Z", "261017083000Z"),
+            ("2049-12-31T23:59:59Z", "491231235959Z"),
+            ("2050-01-01T00:00:00Z", "20500101000000Z"),
+            ("2075-06-01T12:00:00Z", "20750601120000Z"),
+        ] {
+            let time: DateTime<Utc> = time.parse().unwrap();
+            assert_eq!(format_index_time(time), encoded);
+            assert_eq!(parse_index_time(encoded).unwrap(), time);
+        }
+        assert_eq!(parse_index_time("650101000000Z").unwrap().year(), 1965);
+        assert!(parse_index_time("2026-10-17").is_err());
+
+        let line = "R\t20750601120000Z\t261017083000Z,keyCompromise\t0A1B\tcerts/admin/admin.crt\t/CN=kubernetes-admin";
+        let entry = RevocationEntry::parse(line).unwrap();
+        assert_eq!(entry.status, EntryStatus::Revoked);
+        assert_eq!(entry.expires.year(), 2075);
+        assert_eq!(entry.reason, Some(RevocationReason::KeyCompromise));
+        assert_eq!(entry.name(), "admin");
+        assert_eq!(entry.to_line(), line);
+    }
+}
@@ -1,8 +1,9 @@
 // src/cert/service_account.rs
+use openssl::pkey::{Id, PKey};
 use std::fs;
-use std::process::Command;
 use std::{io, path::PathBuf};
 
+use super::openssl::{generate_private_key, load_private_key, write_public_key, SigningError};
 use super::CertificateOperations;
 
 pub struct ServiceAccountGenerator<'a> {
@@ -10,18 +11,6 @@ pub struct ServiceAccountGenerator<'a> {
     cert_ops: &'a mut CertificateOperations,
 }
 
-#[derive(Debug)]
-pub enum ServiceAccountError {
-    IoError(io::Error),
-    KeyGeneration(String),
-}
-
-impl From<io::Error> for ServiceAccountError {
-    fn from(error: io::Error) -> Self {
-        ServiceAccountError::IoError(error)
-    }
-}
-
 impl<'a> ServiceAccountGenerator<'a> {
     pub fn new(output_dir: PathBuf, cert_ops: &'a mut CertificateOperations) -> Self {
         Self {
@@ -31,7 +20,18 @@ impl<'a> ServiceAccountGenerator<'a> {
     }
 
     pub fn generate_service_account_keys(&mut self) -> io::Result<()> {
-        self.cert_ops.log("Generating service account key pair");
+        let algorithm = self.cert_ops.key_algorithms().service_account;
+        if !algorithm.supports_service_account_signing() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} cannot be used for service account keys", algorithm),
+            ));
+        }
+
+        self.cert_ops.log(&format!(
+            "Generating {} service account key pair",
+            algorithm
+        ));
 
         // Ensure directory exists
         fs::create_dir_all(&self.output_dir)?;
@@ -49,86 +49,58 @@ impl<'a> ServiceAccountGenerator<'a> {
 
     fn generate_private_key(&mut self) -> io::Result<()> {
         let key_path = self.output_dir.join("sa.key");
-        let output = Command::new("openssl")
-            .args(&[
-                "genpkey",
-                "-algorithm",
-                "RSA",
-                "-out",
-                key_path.to_str().unwrap(),
-                "-pkeyopt",
-                "rsa_keygen_bits:2048",
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to generate SA private key",
-            ));
-        }
-        Ok(())
+        let algorithm = self.cert_ops.key_algorithms().service_account;
+        generate_private_key(key_path.to_str().unwrap(), algorithm, self.cert_ops.logger_mut())
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Failed to generate SA private key: {}", e),
+                )
+            })
     }
 
     fn generate_public_key(&mut self) -> io::Result<()> {
         let key_path = self.output_dir.join("sa.key");
         let pub_path = self.output_dir.join("sa.pub");
 
-        let output = Command::new("openssl")
-            .args(&[
-                "rsa",
-                "-in",
-                key_path.to_str().unwrap(),
-                "-pubout",
-                "-out",
-                pub_path.to_str().unwrap(),
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
+        write_public_key(key_path.to_str().unwrap(), pub_path.to_str().unwrap()).map_err(|e| {
+            io::Error::new(
                 io::ErrorKind::Other,
-                "Failed to generate SA public key",
-            ));
-        }
-        Ok(())
+                format!("Failed to generate SA public key: {}", e),
+            )
+        })
     }
+}
 
-    pub fn verify_keypair(&self) -> io::Result<()> {
-        let key_path = self.output_dir.join("sa.key");
-        // let pub_path = self.output_dir.join("sa.pub");
-
-        // Verify private key
-        let output = Command::new("openssl")
-            .args(&["rsa", "-check", "-in", key_path.to_str().unwrap()])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Service account private key verification failed",
-            ));
+/// Checks that `sa.key` is a sound private key and that `sa.pub` belongs to it.
+pub fn verify_keypair(sa_dir: &std::path::Path) -> Result<(), SigningError> {
+    let key = load_private_key(sa_dir.join("sa.key").to_str().unwrap())?;
+
+    let key_valid = match key.id() {
+        Id::RSA => key.rsa()?.check_key()?,
+        Id::EC => key.ec_key()?.check_key().is_ok(),
+        other => {
+            return Err(SigningError::Verification(format!(
+                "Unsupported service account key type: {:?}",
+                other
+            )))
         }
+    };
+    if !key_valid {
+        return Err(SigningError::Verification(
+            "Service account private key verification failed".to_string(),
+        ));
+    }
 
-        // Verify public key matches private key
-        let output = Command::new("openssl")
-            .args(&[
-                "rsa",
-                "-in",
-                key_path.to_str().unwrap(),
-                "-pubout",
-                "-outform",
-                "PEM",
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Service account key pair verification failed",
+    let pub_path = sa_dir.join("sa.pub");
+    if pub_path.exists() {
+        let public_key = PKey::public_key_from_pem(&fs::read(&pub_path)?)?;
+        if !public_key.public_eq(&key) {
+            return Err(SigningError::Verification(
+                "Service account key pair verification failed".to_string(),
             ));
         }
-
-        Ok(())
     }
+
+    Ok(())
 }
@@ -0,0 +1,79 @@
+// src/cert/testing.rs
+//
+// A throwaway PKI for tests: certificates are issued from the embedded
+// profiles into a temporary directory, which is removed again on drop.
+use super::profiles::{ProfileSet, TemplateContext};
+use super::{CertificateOperations, KeyAlgorithms};
+use crate::remote::{LayoutSettings, MockTransport, PermissionPolicy, RemoteLayout};
+use crate::utils::logging::ConsoleLogger;
+use std::{env, fs, path::PathBuf, sync::Arc};
+use uuid::Uuid;
+
+pub struct TestPki {
+    pub dir: PathBuf,
+    cert_ops: CertificateOperations,
+    key_algorithms: KeyAlgorithms,
+}
+
+impl TestPki {
+    pub fn new() -> Self {
+        let dir = env::temp_dir().join(format!("starquill-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let cert_ops = CertificateOperations::new(
+            Box::new(ConsoleLogger::new(false)),
+            RemoteLayout::new(&LayoutSettings::default(), "", "", Vec::new()),
+            Arc::new(MockTransport::new()),
+            KeyAlgorithms::default(),
+            None,
+            ProfileSet::default(),
+            PermissionPolicy::default(),
+        );
+        Self {
+            dir,
+            cert_ops,
+            key_algorithms: KeyAlgorithms::default(),
+        }
+    }
+
+    pub fn with_key_algorithms(mut self, key_algorithms: KeyAlgorithms) -> Self {
+        self.key_algorithms = key_algorithms;
+        self
+    }
+
+    /// Issues the profile below `dir`, signed by the CA it names there.
+    /// Returns the local paths of the certificate and key.
+    pub fn issue(&mut self, profile: &str, context: &TemplateContext) -> (String, String) {
+        let mut resolved = ProfileSet::default()
+            .resolve(profile, context, &self.key_algorithms)
+            .unwrap();
+        resolved.config.output_dir = self.dir.join(&resolved.config.output_dir);
+        let ca_dir = self
+            .dir
+            .join(&resolved.ca_dir)
+            .to_string_lossy()
+            .to_string();
+        self.cert_ops
+            .generate_cert(&resolved.cert_name, &ca_dir, &resolved.config, &[])
+            .unwrap();
+
+        let output = resolved.config.output_dir.join(&resolved.cert_name);
+        let path = |extension: &str| {
+            output
+                .with_extension(extension)
+                .to_string_lossy()
+                .to_string()
+        };
+        (path("crt"), path("key"))
+    }
+
+    /// The directory of a CA, e.g. `kubernetes-ca`.
+    pub fn ca_dir(&self, name: &str) -> PathBuf {
+        self.dir.join("certs").join(name)
+    }
+}
+
+impl Drop for TestPki {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
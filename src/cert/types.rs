@@ -12,7 +12,97 @@ pub enum CertificateType {
     ControllerManager,
     Scheduler,
     Node(String),
+    KubeletServing(String),
     Admin,
+    KubeProxy,
+    OcspSigner,
+    EtcdCA,
+    EtcdServer,
+    EtcdPeer,
+    EtcdHealthcheckClient,
+    ApiServerEtcdClient,
+    FrontProxyCA,
+    FrontProxyClient,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum KeyAlgorithm {
+    #[serde(rename = "rsa-2048")]
+    Rsa2048,
+    #[serde(rename = "rsa-3072")]
+    Rsa3072,
+    #[serde(rename = "rsa-4096")]
+    Rsa4096,
+    #[default]
+    #[serde(rename = "ecdsa-p256")]
+    EcdsaP256,
+    #[serde(rename = "ecdsa-p384")]
+    EcdsaP384,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+/// Key algorithms per certificate role. CAs may differ from the leaves they sign.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct KeyAlgorithms {
+    pub ca: KeyAlgorithm,
+    pub leaf: KeyAlgorithm,
+    pub service_account: KeyAlgorithm,
+}
+
+/// How long published CRLs stay valid and how often they are re-signed.
+/// `ocsp_url` is embedded as the AIA OCSP location of issued leaf
+/// certificates, e.g. `http://10.0.0.10:3000/ocsp`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RevocationSettings {
+    pub crl_validity_days: u32,
+    pub crl_refresh_hours: u64,
+    pub ocsp_url: Option<String>,
+}
+
+/// The etcd cluster. With `separate_ca` the etcd certificates are issued by
+/// a dedicated etcd CA under the root CA instead of the Kubernetes CA. An
+/// empty `members` list means a single member on the control plane.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct EtcdSettings {
+    pub separate_ca: bool,
+    pub members: Vec<EtcdMember>,
+}
+
+/// One etcd member. `ip` and `hostname` become SANs of its server and peer
+/// certificates; at least one must be set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EtcdMember {
+    pub name: String,
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+}
+
+/// A worker node as the kubelet registers it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkerNode {
+    /// Zero-based position in `worker_nodes`.
+    pub index: usize,
+    pub address: String,
+    pub hostname: Option<String>,
+}
+
+/// Subject DN attributes stamped on every certificate unless its profile sets
+/// its own value.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SubjectDefaults {
+    pub country: Option<String>,
+    pub state: Option<String>,
+    pub locality: Option<String>,
+    pub organization: Option<String>,
+    pub organizational_unit: Option<String>,
+    pub email_address: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,7 +123,7 @@ pub struct CertificateConfig {
     pub common_name: String,
     pub organization: Option<String>,
     pub validity_days: u32,
-    pub key_size: u32,
+    pub key_algorithm: KeyAlgorithm,
     pub output_dir: PathBuf,
     pub alt_names: Vec<AltName>,  // Changed from Vec<String>
     pub key_usage: Vec<String>,
@@ -42,6 +132,8 @@ pub struct CertificateConfig {
     pub country: Option<String>,
     pub state: Option<String>,
     pub locality: Option<String>,
+    pub organizational_unit: Option<String>,
+    pub email_address: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +142,249 @@ pub struct ClusterEndpoints {
     pub worker_nodes: Vec<String>,
 }
 
+impl CertificateType {
+    pub fn is_ca(&self) -> bool {
+        matches!(
+            self,
+            CertificateType::RootCA
+                | CertificateType::KubernetesCA
+                | CertificateType::EtcdCA
+                | CertificateType::FrontProxyCA
+        )
+    }
+
+    /// The front-proxy CA is its own trust domain, so like the root CA it
+    /// signs its own certificate.
+    pub fn is_self_signed(&self) -> bool {
+        matches!(
+            self,
+            CertificateType::RootCA | CertificateType::FrontProxyCA
+        )
+    }
+}
+
+impl KeyAlgorithm {
+    /// kube-apiserver only accepts RSA and ECDSA keys for signing service account tokens.
+    pub fn supports_service_account_signing(&self) -> bool {
+        !matches!(self, KeyAlgorithm::Ed25519)
+    }
+}
+
+impl std::fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            KeyAlgorithm::Rsa2048 => "RSA-2048",
+            KeyAlgorithm::Rsa3072 => "RSA-3072",
+            KeyAlgorithm::Rsa4096 => "RSA-4096",
+            KeyAlgorithm::EcdsaP256 => "ECDSA P-256",
+            KeyAlgorithm::EcdsaP384 => "ECDSA P-384",
+            KeyAlgorithm::Ed25519 => "Ed25519",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl KeyAlgorithms {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.service_account.supports_service_account_signing() {
+            return Err(format!(
+                "{} cannot be used for service account keys",
+                self.service_account
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for RevocationSettings {
+    fn default() -> Self {
+        Self {
+            crl_validity_days: 7,
+            crl_refresh_hours: 24,
+            ocsp_url: None,
+        }
+    }
+}
+
+impl EtcdSettings {
+    pub const CA_DIR: &'static str = "certs/etcd-ca";
+
+    /// Directory of the CA that issues the etcd certificates.
+    pub fn ca_dir(&self) -> &'static str {
+        if self.separate_ca {
+            Self::CA_DIR
+        } else {
+            "certs/kubernetes-ca"
+        }
+    }
+
+    /// The configured members, or a single member on the control plane.
+    pub fn members_or_default(&self, control_plane: &str) -> Vec<EtcdMember> {
+        if !self.members.is_empty() {
+            return self.members.clone();
+        }
+        let is_ip = control_plane.parse::<std::net::IpAddr>().is_ok();
+        vec![EtcdMember {
+            name: "control-plane-0".to_string(),
+            ip: is_ip.then(|| control_plane.to_string()),
+            hostname: (!is_ip).then(|| control_plane.to_string()),
+        }]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut names = std::collections::HashSet::new();
+        for member in &self.members {
+            if member.name.is_empty() || member.name.contains(['/', '\\']) {
+                return Err(format!("invalid etcd member name '{}'", member.name));
+            }
+            if !names.insert(member.name.as_str()) {
+                return Err(format!("duplicate etcd member {}", member.name));
+            }
+            if member.ip.is_none() && member.hostname.is_none() {
+                return Err(format!(
+                    "etcd member {} needs an ip or a hostname",
+                    member.name
+                ));
+            }
+            if let Some(ip) = &member.ip {
+                if ip.parse::<std::net::IpAddr>().is_err() {
+                    return Err(format!("etcd member {} has invalid ip {}", member.name, ip));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WorkerNode {
+    /// The Kubernetes node name: the hostname when known, `node-N` otherwise.
+    pub fn name(&self) -> String {
+        self.hostname
+            .clone()
+            .unwrap_or_else(|| format!("node-{}", self.index + 1))
+    }
+
+    /// Local directory of the node's kubelet certificates, apart from the
+    /// component directories so a node may be called e.g. `etcd` or `admin`.
+    pub fn cert_dir(&self) -> String {
+        format!("certs/nodes/{}", self.name())
+    }
+}
+
+/// Checks a kubelet node name: a lowercase RFC 1123 subdomain.
+pub fn validate_node_name(name: &str) -> Result<(), String> {
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+    if name.len() > 253 || !name.split('.').all(valid_label) {
+        return Err(format!("invalid node name '{}'", name));
+    }
+    Ok(())
+}
+
+impl EtcdMember {
+    /// Address used to reach the member over SSH.
+    pub fn address(&self) -> &str {
+        self.ip
+            .as_deref()
+            .or(self.hostname.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for SubjectDefaults {
+    fn default() -> Self {
+        Self {
+            country: None,
+            state: None,
+            locality: None,
+            organization: Some("Kubernetes".to_string()),
+            organizational_unit: None,
+            email_address: None,
+        }
+    }
+}
+
+impl SubjectDefaults {
+    pub fn validate(&self) -> Result<(), String> {
+        let attributes = [
+            ("C", &self.country),
+            ("ST", &self.state),
+            ("L", &self.locality),
+            ("O", &self.organization),
+            ("OU", &self.organizational_unit),
+            ("emailAddress", &self.email_address),
+        ];
+        for (attribute, value) in attributes {
+            if let Some(value) = value {
+                validate_dn_attribute(attribute, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CertificateConfig {
+    /// Subject attributes in DN order, skipping unset ones.
+    pub fn subject_attributes(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("C", self.country.as_deref()),
+            ("ST", self.state.as_deref()),
+            ("L", self.locality.as_deref()),
+            ("O", self.organization.as_deref()),
+            ("OU", self.organizational_unit.as_deref()),
+            ("CN", Some(self.common_name.as_str())),
+            ("emailAddress", self.email_address.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(attribute, value)| value.map(|v| (attribute, v)))
+        .collect()
+    }
+}
+
+/// Checks a subject attribute against the string type and upper bound that
+/// RFC 5280 (Appendix A) assigns to it.
+pub fn validate_dn_attribute(attribute: &str, value: &str) -> Result<(), String> {
+    let max_len = match attribute {
+        "C" => 2,
+        "ST" | "L" => 128,
+        "O" | "OU" | "CN" => 64,
+        "emailAddress" => 255,
+        other => return Err(format!("unsupported subject attribute {}", other)),
+    };
+
+    if value.trim().is_empty() {
+        return Err(format!("{} must not be empty", attribute));
+    }
+    if value.chars().count() > max_len {
+        return Err(format!(
+            "{} is longer than {} characters: {}",
+            attribute, max_len, value
+        ));
+    }
+    if value.chars().any(char::is_control) {
+        return Err(format!("{} contains control characters", attribute));
+    }
+
+    match attribute {
+        // PrintableString, ISO 3166 alpha-2
+        "C" if value.len() != 2 || !value.chars().all(|c| c.is_ascii_uppercase()) => Err(
+            format!("C must be a two-letter uppercase country code: {}", value),
+        ),
+        // IA5String
+        "emailAddress" if !value.is_ascii() || !value.contains('@') => {
+            Err(format!("emailAddress is not a valid address: {}", value))
+        }
+        _ => Ok(()),
+    }
+}
+
 // Implementation for AltName for easier creation
 impl AltName {
     pub fn dns(value: String) -> Self {
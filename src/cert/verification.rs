@@ -1,156 +1,479 @@
 // src/cert/verification.rs
+use super::openssl::verify_certificate;
+use super::service_account::verify_keypair;
+use crate::remote::{compare_checksums, sha256_hex, IntegrityCheck, Transfer, Transport};
 use crate::utils::logging::Logger;
-use std::{fs, io, path::PathBuf, process::Command};
+use chrono::{TimeZone, Utc};
+use openssl::{pkey::PKey, x509::X509};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, DirBuilder},
+    io,
+    net::IpAddr,
+    os::unix::fs::DirBuilderExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
 
-pub struct CertificateVerifier {
-    logger: Box<dyn Logger>,
-    remote_user: String,
-    remote_dir: String,
-    ssh_key_path: String,
+/// A file the inventory expects on a host.
+pub struct ExpectedFile {
+    pub file: Transfer,
+    /// Local CA chain a certificate must verify against.
+    pub ca_chain: Option<String>,
+    /// The private key installed with a certificate on the same host.
+    pub key: Option<Transfer>,
 }
 
-impl CertificateVerifier {
-    pub fn new(
-        logger: Box<dyn Logger>,
-        remote_user: String,
-        remote_dir: String,
-        ssh_key_path: String,
-    ) -> Self {
+/// Outcome of the checks of one file. A check that does not apply to the
+/// file, or could not run, is `None`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FileVerification {
+    pub cert_type: String,
+    pub remote: String,
+    pub present: bool,
+    /// SHA-256 on the host matches the local copy.
+    pub fingerprint_matches: Option<bool>,
+    /// The checksums behind `fingerprint_matches`, for the tracker.
+    #[serde(skip)]
+    pub checksum: Option<IntegrityCheck>,
+    pub chain_valid: Option<bool>,
+    pub key_matches: Option<bool>,
+    /// Serving certificates only: the SANs cover every address of the host.
+    pub san_covers_host: Option<bool>,
+    pub permissions_valid: Option<bool>,
+    pub not_after: Option<String>,
+    pub days_left: Option<i64>,
+    pub problems: Vec<String>,
+}
+
+impl FileVerification {
+    fn new(file: &Transfer) -> Self {
         Self {
-            logger,
-            remote_user,
-            remote_dir,
-            ssh_key_path,
+            cert_type: file.cert_type.clone(),
+            remote: file.remote.clone(),
+            present: false,
+            fingerprint_matches: None,
+            checksum: None,
+            chain_valid: None,
+            key_matches: None,
+            san_covers_host: None,
+            permissions_valid: None,
+            not_after: None,
+            days_left: None,
+            problems: Vec::new(),
         }
     }
 
-    pub fn verify_remote_certificates(&mut self, hosts: &[String]) -> io::Result<()> {
-        self.logger.log("Verifying certificates on remote hosts...");
+    pub fn valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
 
-        for host in hosts {
-            self.logger.log(&format!("Verifying certificates on host {}...", host));
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HostVerification {
+    pub host: String,
+    /// Addresses serving certificates on the host must cover.
+    pub addresses: Vec<String>,
+    pub files: Vec<FileVerification>,
+    /// Every file passed every check.
+    pub valid: bool,
+    pub checked: String,
+}
 
-            // Set up temp directory
-            let temp_dir = format!("/tmp/cert-verify-{}", host);
-            fs::create_dir_all(&temp_dir)?;
+impl HostVerification {
+    fn new(host: &str, addresses: Vec<String>, files: Vec<FileVerification>) -> Self {
+        Self {
+            host: host.to_string(),
+            addresses,
+            valid: files.iter().all(FileVerification::valid),
+            files,
+            checked: Utc::now().to_rfc3339(),
+        }
+    }
 
-            let result = self.verify_host_certificates(host, &temp_dir);
+    /// `None` when no file on the host could be checked.
+    pub fn permissions_valid(&self) -> Option<bool> {
+        let checked: Vec<bool> = self
+            .files
+            .iter()
+            .filter_map(|file| file.permissions_valid)
+            .collect();
+        (!checked.is_empty()).then(|| checked.iter().all(|valid| *valid))
+    }
+}
 
-            // Cleanup
-            let _ = fs::remove_dir_all(&temp_dir);
+/// Whether a DNS or IP subject alternative name matches `address`.
+pub(crate) fn covers(names: &[GeneralName], address: &str) -> bool {
+    let ip = address.parse::<IpAddr>().ok();
+    names.iter().any(|name| match (name, ip) {
+        (GeneralName::DNSName(dns), _) => dns.eq_ignore_ascii_case(address),
+        (GeneralName::IPAddress(bytes), Some(IpAddr::V4(ip))) => ip.octets()[..] == **bytes,
+        (GeneralName::IPAddress(bytes), Some(IpAddr::V6(ip))) => ip.octets()[..] == **bytes,
+        _ => false,
+    })
+}
 
-            if let Err(e) = result {
-                self.logger.log(&format!(
-                    "Failed to verify certificates on {}: {}",
-                    host, e
-                ));
-            }
-        }
+pub struct CertificateVerifier {
+    logger: Box<dyn Logger>,
+    transport: Arc<dyn Transport>,
+}
 
-        Ok(())
+impl CertificateVerifier {
+    pub fn new(logger: Box<dyn Logger>, transport: Arc<dyn Transport>) -> Self {
+        Self { logger, transport }
     }
 
-    fn verify_host_certificates(&mut self, host: &str, temp_dir: &str) -> io::Result<()> {
-        let certificates = [
-            ("kubernetes-ca-chain.crt", None),
-            ("kube-apiserver.crt", Some("kubernetes-ca-chain.crt")),
-            ("controller-manager.crt", Some("kubernetes-ca-chain.crt")),
-            ("scheduler.crt", Some("kubernetes-ca-chain.crt")),
-        ];
-
-        for (cert_name, ca_cert) in certificates {
-            let remote_path = format!("{}/{}", self.remote_dir, cert_name);
-            let local_path = format!("{}/{}", temp_dir, cert_name);
-
-            if let Err(e) = self.copy_from_remote(host, &remote_path, &local_path) {
-                self.logger.log(&format!(
-                    "Failed to copy {} from {}: {}",
-                    cert_name, host, e
-                ));
-                continue;
+    /// Checks every file the inventory expects on its host: presence,
+    /// fingerprint and permissions for all of them, and for certificates
+    /// also chain, key, SAN coverage and expiry. Certificates are fetched
+    /// into a private directory that is removed afterwards.
+    pub fn verify_remote_certificates(
+        &mut self,
+        files: &[ExpectedFile],
+        addresses: &BTreeMap<String, Vec<String>>,
+    ) -> io::Result<Vec<HostVerification>> {
+        self.logger.log("Verifying certificates on remote hosts...");
+
+        let temp_dir = env::temp_dir().join(format!("starquill-verify-{}", Uuid::new_v4()));
+        DirBuilder::new().mode(0o700).create(&temp_dir)?;
+
+        let mut hosts: Vec<&str> = files.iter().map(|file| file.file.host.as_str()).collect();
+        hosts.sort();
+        hosts.dedup();
+        let mut reports = Vec::new();
+        for host in hosts {
+            self.logger.log(&format!("Verifying certificates on host {}...", host));
+            let host_addresses = addresses.get(host).cloned().unwrap_or_default();
+            let mut unreachable = None;
+            let mut results = Vec::new();
+            for (index, expected) in files.iter().filter(|f| f.file.host == host).enumerate() {
+                let mut result = FileVerification::new(&expected.file);
+                match &unreachable {
+                    Some(e) => result.problems.push(format!("not checked: {}", e)),
+                    None => {
+                        let fetched = temp_dir.join(format!("{}-{}", host, index));
+                        if let Err(e) =
+                            self.verify_file(expected, &host_addresses, &fetched, &mut result)
+                        {
+                            if e.kind() == io::ErrorKind::NotConnected {
+                                unreachable = Some(e.to_string());
+                            }
+                            result.problems.push(e.to_string());
+                        }
+                    }
+                }
+                for problem in &result.problems {
+                    self.logger.log(&format!(
+                        "{}:{} ({}): {}",
+                        host, result.remote, result.cert_type, problem
+                    ));
+                }
+                results.push(result);
             }
 
-            // Verify certificate
-            let ca_path = ca_cert.map(|ca| format!("{}/{}", temp_dir, ca));
-            self.verify_certificate(&local_path, ca_path.as_deref())?;
+            let report = HostVerification::new(host, host_addresses, results);
+            self.logger.log(&format!(
+                "{}: {} of {} files verified",
+                host,
+                report.files.iter().filter(|file| file.valid()).count(),
+                report.files.len()
+            ));
+            reports.push(report);
         }
 
-        Ok(())
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(reports)
     }
 
-    fn copy_from_remote(&self, host: &str, remote_path: &str, local_path: &str) -> io::Result<()> {
-        let ssh_key_path = shellexpand::tilde(&self.ssh_key_path).to_string();
+    fn verify_file(
+        &mut self,
+        expected: &ExpectedFile,
+        addresses: &[String],
+        fetched: &Path,
+        result: &mut FileVerification,
+    ) -> io::Result<()> {
+        let file = &expected.file;
+        let Some(found) = self.transport.stat(&file.host, &file.remote)? else {
+            result.problems.push("missing".to_string());
+            result.fingerprint_matches = Some(false);
+            result.checksum = Some(IntegrityCheck {
+                expected: sha256_hex(&fs::read(&file.source)?),
+                actual: None,
+            });
+            return Ok(());
+        };
+        result.present = true;
 
-        let output = Command::new("scp")
-            .args(&[
-                "-i",
-                &ssh_key_path,
-                &format!("{}@{}:{}", self.remote_user, host, remote_path),
-                local_path,
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to copy {} from {}", remote_path, host),
+        let differences = file.permissions.differences(&found);
+        result.permissions_valid = Some(differences.is_empty());
+        if !differences.is_empty() {
+            result.problems.push(format!(
+                "does not match its permission policy: {}",
+                differences.join(", ")
             ));
         }
 
-        Ok(())
-    }
+        let source = Path::new(&file.source);
+        let check = compare_checksums(self.transport.as_ref(), &file.host, source, &file.remote)?;
+        result.fingerprint_matches = Some(check.matches());
+        if !check.matches() {
+            result.problems.push("differs from the local copy".to_string());
+        }
+        result.checksum = Some(check);
 
-    pub fn verify_certificate(&mut self, cert_path: &str, ca_cert: Option<&str>) -> io::Result<()> {
-        // Basic certificate info check
-        let basic_check = Command::new("openssl")
-            .args(&["x509", "-in", cert_path, "-noout", "-text"])
-            .output()?;
+        if !file.source.ends_with(".crt") {
+            return Ok(());
+        }
+        self.transport.get(&file.host, &file.remote, fetched)?;
+        let cert = X509::from_pem(&fs::read(fetched)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-        if !basic_check.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Basic certificate check failed for {}", cert_path)
-            ));
+        if let Some(ca_chain) = &expected.ca_chain {
+            let fetched_path = fetched.to_string_lossy();
+            let valid = verify_certificate(&fetched_path, Some(ca_chain), self.logger.as_mut());
+            result.chain_valid = Some(valid.is_ok());
+            if let Err(e) = valid {
+                result.problems.push(format!("chain: {}", e));
+            }
         }
 
-        // Verify against CA if provided
-        if let Some(ca) = ca_cert {
-            let output = Command::new("openssl")
-                .args(&["verify", "-CAfile", ca, cert_path])
-                .output()?;
+        let der = cert
+            .to_der()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let (_, parsed) = X509Certificate::from_der(&der)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let not_after = parsed.validity().not_after.timestamp();
+        let days_left = (not_after - Utc::now().timestamp()).div_euclid(86_400);
+        result.not_after = Utc
+            .timestamp_opt(not_after, 0)
+            .single()
+            .map(|time| time.to_rfc3339());
+        result.days_left = Some(days_left);
+        if days_left < 0 {
+            result.problems.push("expired".to_string());
+        }
 
-            if !output.status.success() {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Certificate chain verification failed for {}", cert_path)
+        let serving = parsed
+            .extended_key_usage()
+            .ok()
+            .flatten()
+            .is_some_and(|usage| usage.value.server_auth);
+        if serving {
+            let names = parsed
+                .subject_alternative_name()
+                .ok()
+                .flatten()
+                .map(|san| san.value.general_names.clone())
+                .unwrap_or_default();
+            let uncovered: Vec<&str> = addresses
+                .iter()
+                .filter(|address| !covers(&names, address))
+                .map(String::as_str)
+                .collect();
+            result.san_covers_host = Some(uncovered.is_empty());
+            if !uncovered.is_empty() {
+                result.problems.push(format!(
+                    "subject alternative names do not cover {}",
+                    uncovered.join(", ")
                 ));
             }
         }
 
+        // The key is compared by hash, so it never leaves the host
+        if let Some(key) = &expected.key {
+            let local_key = fs::read(&key.source)?;
+            let remote_key = self.transport.checksum(&key.host, &key.remote)?;
+            let pairs = remote_key.as_deref() == Some(sha256_hex(&local_key).as_str())
+                && PKey::private_key_from_pem(&local_key)
+                    .ok()
+                    .zip(cert.public_key().ok())
+                    .is_some_and(|(key, public)| public.public_eq(&key));
+            result.key_matches = Some(pairs);
+            if !pairs {
+                result
+                    .problems
+                    .push(format!("does not match the key at {}", key.remote));
+            }
+        }
+
         Ok(())
     }
 
+    pub fn verify_certificate(&mut self, cert_path: &str, ca_cert: Option<&str>) -> io::Result<()> {
+        verify_certificate(cert_path, ca_cert, self.logger.as_mut()).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("Certificate verification failed for {}: {}", cert_path, e),
+            )
+        })
+    }
+
     pub fn verify_service_account_keypair(&mut self, sa_dir: &PathBuf) -> io::Result<()> {
         self.logger.log("Verifying service account key pair...");
 
-        let output = Command::new("openssl")
-            .args(&[
-                "rsa",
-                "-in",
-                sa_dir.join("sa.key").to_str().unwrap(),
-                "-check",
-                "-noout",
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            return Err(io::Error::new(
+        verify_keypair(sa_dir).map_err(|e| {
+            io::Error::new(
                 io::ErrorKind::Other,
-                "Service account key verification failed",
-            ));
-        }
+                format!("Service account key verification failed: {}", e),
+            )
+        })?;
 
         self.logger.log("Service account key pair verification successful");
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert::{profiles::TemplateContext, testing::TestPki};
+    use crate::remote::{
+        DistributionJob, DistributionSettings, LocalRootTransport, PermissionPolicy,
+        ReloadSettings, TransferState,
+    };
+    use crate::utils::logging::ConsoleLogger;
+    use std::{os::unix::fs::MetadataExt, thread, time::Duration};
+
+    const CONTROL_PLANE: &str = "10.0.0.1";
+    const WORKER: &str = "10.0.0.2";
+
+    fn transfer(host: &str, cert_type: &str, source: &str, remote: &str) -> Transfer {
+        Transfer {
+            cert_type: cert_type.to_string(),
+            host: host.to_string(),
+            source: source.to_string(),
+            remote: remote.to_string(),
+            permissions: PermissionPolicy::default().for_file(cert_type, remote),
+        }
+    }
+
+    #[test]
+    fn distributed_certificates_verify_on_every_host() {
+        let mut pki = TestPki::new();
+        let root = pki.dir.join("hosts");
+        // Files are owned by whoever runs the test; name them root on the hosts
+        fs::create_dir_all(&root).unwrap();
+        let owner = fs::metadata(&root).unwrap();
+        for host in [CONTROL_PLANE, WORKER] {
+            fs::create_dir_all(root.join(host).join("etc")).unwrap();
+            fs::write(
+                root.join(host).join("etc/passwd"),
+                format!("root:x:{}:{}::/root:/bin/sh\n", owner.uid(), owner.gid()),
+            )
+            .unwrap();
+            fs::write(
+                root.join(host).join("etc/group"),
+                format!("root:x:{}:\n", owner.gid()),
+            )
+            .unwrap();
+        }
+        let transport: Arc<dyn Transport> =
+            Arc::new(LocalRootTransport::new(&root.to_string_lossy()));
+
+        let (root_ca, _) = pki.issue("root-ca", &TemplateContext::new());
+        let (kubernetes_ca, _) = pki.issue("kubernetes-ca", &TemplateContext::new());
+        let chain = pki.ca_dir("kubernetes-ca").join("ca-chain.crt");
+        let mut bundle = fs::read(&kubernetes_ca).unwrap();
+        bundle.extend(fs::read(&root_ca).unwrap());
+        fs::write(&chain, bundle).unwrap();
+        let (apiserver, apiserver_key) = pki.issue(
+            "kube-apiserver",
+            &TemplateContext::new().with_control_plane(CONTROL_PLANE),
+        );
+        let (kubelet, kubelet_key) = pki.issue(
+            "kubelet-serving",
+            &TemplateContext::new()
+                .with("node.name", "worker-1")
+                .with("node.ip", WORKER)
+                .with("node.hostname", "worker-1"),
+        );
+
+        let mut files = Vec::new();
+        for host in [CONTROL_PLANE, WORKER] {
+            files.push(ExpectedFile {
+                file: transfer(host, "ca.crt", &kubernetes_ca, "/etc/kubernetes/pki/ca.crt"),
+                ca_chain: None,
+                key: None,
+            });
+        }
+        for (host, cert_type, cert, key, remote) in [
+            (
+                CONTROL_PLANE,
+                "kube-apiserver",
+                &apiserver,
+                &apiserver_key,
+                "/etc/kubernetes/pki/apiserver",
+            ),
+            (
+                WORKER,
+                "kubelet",
+                &kubelet,
+                &kubelet_key,
+                "/var/lib/kubelet/pki/kubelet",
+            ),
+        ] {
+            let key = transfer(host, cert_type, key, &format!("{}.key", remote));
+            files.push(ExpectedFile {
+                file: transfer(host, cert_type, cert, &format!("{}.crt", remote)),
+                ca_chain: Some(chain.to_string_lossy().to_string()),
+                key: Some(key.clone()),
+            });
+            files.push(ExpectedFile {
+                file: key,
+                ca_chain: None,
+                key: None,
+            });
+        }
+
+        let mut job = DistributionJob::start(
+            Arc::clone(&transport),
+            files.iter().map(|expected| expected.file.clone()).collect(),
+            DistributionSettings::default(),
+            ReloadSettings::default(),
+            None,
+        );
+        let result = loop {
+            match job.take_result() {
+                Some(result) => break result,
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        assert_eq!(result.count(TransferState::Placed), files.len());
+
+        let addresses = BTreeMap::from([
+            (CONTROL_PLANE.to_string(), vec![CONTROL_PLANE.to_string()]),
+            (WORKER.to_string(), vec![WORKER.to_string()]),
+        ]);
+        let reports = CertificateVerifier::new(Box::new(ConsoleLogger::new(false)), transport)
+            .verify_remote_certificates(&files, &addresses)
+            .unwrap();
+
+        assert_eq!(reports.len(), 2);
+        for report in &reports {
+            let problems: Vec<&String> = report
+                .files
+                .iter()
+                .flat_map(|file| &file.problems)
+                .collect();
+            assert!(report.valid, "{}: {:?}", report.host, problems);
+            assert_eq!(report.permissions_valid(), Some(true));
+        }
+        let served: Vec<&FileVerification> = reports
+            .iter()
+            .flat_map(|report| &report.files)
+            .filter(|file| file.key_matches.is_some())
+            .collect();
+        assert_eq!(served.len(), 2);
+        for file in served {
+            assert_eq!(file.chain_valid, Some(true));
+            assert_eq!(file.key_matches, Some(true));
+            assert_eq!(file.san_covers_host, Some(true));
+        }
+    }
+}
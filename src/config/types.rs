@@ -1,16 +1,68 @@
 // config/types.rs
 use serde::{Deserialize, Serialize};
-use std::{fs, io};
+use std::{collections::BTreeMap, fs, io, net::IpAddr};
 
-use crate::discovery;
+use crate::cert::profiles::ProfileSet;
+use crate::cert::{
+    validate_node_name, EtcdSettings, KeyAlgorithms, RevocationSettings, SubjectDefaults,
+};
+use crate::discovery::{self, probe::ProbeSettings};
+use crate::remote::{
+    DistributionSettings, HostKeySettings, LayoutSettings, PermissionPolicy, PreflightSettings,
+    ReloadSettings, RemoteLayout, SshOptions, SshSettings, TransportSettings,
+};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ClusterConfig {
     pub control_plane: String,
     pub worker_nodes: Vec<String>,
+    /// Kubelet node name per worker address. Missing entries are discovered
+    /// over SSH when node certificates are generated.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub node_hostnames: BTreeMap<String, String>,
     pub remote_user: String,
     pub ssh_key_path: String,
+    /// Port, agent and jump hosts, and per-node overrides of the login.
+    #[serde(default)]
+    pub ssh: SshSettings,
     pub remote_dir: String,
+    /// Where each file is installed on a node, by profile and custom rules.
+    #[serde(default)]
+    pub layout: LayoutSettings,
+    /// How files reach the nodes; SSH unless configured otherwise.
+    #[serde(default)]
+    pub transport: TransportSettings,
+    /// Pinned SSH host keys and the known-hosts store of this cluster.
+    #[serde(default)]
+    pub host_keys: HostKeySettings,
+    #[serde(default)]
+    pub distribution: DistributionSettings,
+    /// Hooks that restart components once their files were replaced.
+    #[serde(default)]
+    pub reload: ReloadSettings,
+    /// Owner, group and mode of distributed files.
+    #[serde(default)]
+    pub permissions: PermissionPolicy,
+    /// Timeout and ports of the TLS endpoint prober.
+    #[serde(default)]
+    pub probe: ProbeSettings,
+    /// Thresholds of the node preflight checks and whether failures block
+    /// distribution.
+    #[serde(default)]
+    pub preflight: PreflightSettings,
+    #[serde(default)]
+    pub key_algorithms: KeyAlgorithms,
+    #[serde(default)]
+    pub revocation: RevocationSettings,
+    #[serde(default)]
+    pub subject: SubjectDefaults,
+    #[serde(default)]
+    pub etcd: EtcdSettings,
+    /// Optional YAML file overriding the built-in certificate profiles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiles_file: Option<String>,
+    #[serde(skip)]
+    pub profiles: ProfileSet,
 }
 
 impl ClusterConfig {
@@ -18,9 +70,25 @@ impl ClusterConfig {
         Self {
             control_plane: "1.2.3.4".to_string(),
             worker_nodes: vec!["1.2.3.4".to_string()],
+            node_hostnames: BTreeMap::new(),
             remote_user: "adminuser".to_string(),
             remote_dir: "/etc/kubernetes/pki".to_string(),
             ssh_key_path: "~/.ssh/id_rsa".to_string(),
+            layout: LayoutSettings::default(),
+            ssh: SshSettings::default(),
+            transport: TransportSettings::default(),
+            host_keys: HostKeySettings::default(),
+            distribution: DistributionSettings::default(),
+            reload: ReloadSettings::default(),
+            permissions: PermissionPolicy::default(),
+            probe: ProbeSettings::default(),
+            preflight: PreflightSettings::default(),
+            key_algorithms: KeyAlgorithms::default(),
+            revocation: RevocationSettings::default(),
+            subject: SubjectDefaults::default(),
+            etcd: EtcdSettings::default(),
+            profiles_file: None,
+            profiles: ProfileSet::default(),
         }
     }
 
@@ -37,10 +105,77 @@ impl ClusterConfig {
         let mut config: Self = serde_json::from_str(&config_str)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
+        config
+            .key_algorithms
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .subject
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .etcd
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .distribution
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .layout
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .reload
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .ssh
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .host_keys
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .permissions
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .probe
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config
+            .preflight
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        for hostname in config.node_hostnames.values() {
+            validate_node_name(hostname).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+
+        config.profiles =
+            ProfileSet::load(config.profiles_file.as_deref(), config.subject.clone())?;
+
         // Discover control plane IP
         match &config.control_plane {
             hostname => match discovery::resolve_hostname(hostname).await {
-                Ok(ip) => config.control_plane = ip,
+                Ok(ip) => {
+                    config.ssh.rename_node(hostname, &ip);
+                    config.control_plane = ip
+                }
+                // Names behind jump hosts are resolved by the last jump host
+                Err(_) if config.ssh.uses_jump_hosts(hostname) => {}
                 Err(e) => {
                     return Err(io::Error::new(
                         io::ErrorKind::Other,
@@ -56,7 +191,21 @@ impl ClusterConfig {
                 let mut resolved_nodes = Vec::new();
                 for node in nodes {
                     match discovery::resolve_hostname(node).await {
-                        Ok(ip) => resolved_nodes.push(ip),
+                        Ok(ip) => {
+                            // Keep the node name when the worker was given by hostname
+                            if node.parse::<IpAddr>().is_err() {
+                                let hostname = config
+                                    .node_hostnames
+                                    .remove(node)
+                                    .unwrap_or_else(|| node.to_lowercase());
+                                config.node_hostnames.entry(ip.clone()).or_insert(hostname);
+                            }
+                            config.ssh.rename_node(node, &ip);
+                            resolved_nodes.push(ip)
+                        }
+                        Err(_) if config.ssh.uses_jump_hosts(node) => {
+                            resolved_nodes.push(node.clone())
+                        }
                         Err(e) => {
                             return Err(io::Error::new(
                                 io::ErrorKind::Other,
@@ -72,6 +221,30 @@ impl ClusterConfig {
         Ok(config)
     }
 
+    pub fn ssh_options(&self) -> SshOptions {
+        SshOptions {
+            remote_user: self.remote_user.clone(),
+            ssh_key_path: self.ssh_key_path.clone(),
+            ssh: self.ssh.clone(),
+            host_keys: self.host_keys.clone(),
+        }
+    }
+
+    pub fn remote_layout(&self) -> RemoteLayout {
+        let etcd_members = self
+            .etcd
+            .members_or_default(&self.control_plane)
+            .iter()
+            .map(|member| member.address().to_string())
+            .collect();
+        RemoteLayout::new(
+            &self.layout,
+            &self.remote_dir,
+            &self.control_plane,
+            etcd_members,
+        )
+    }
+
     pub fn save_to_file(&self, path: &str) -> io::Result<()> {
         let config_str = serde_json::to_string_pretty(self)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
@@ -1,10 +1,12 @@
 use crate::app::{CertManager, CertStatus}; // Assuming CertStatus is in types module
+use crate::cert;
 use crate::discovery::kubeconfig::{ClusterConfig, ContextConfig, KubeConfig, UserConfig};
+use crate::discovery::probe::EndpointProbe;
+use crate::remote::{Transfer, Transport};
 use chrono::{DateTime, Duration, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use std::sync::Arc;
 use std::{fs, io};
 use tokio::sync::RwLock;
@@ -50,6 +52,9 @@ pub struct NodeTrustInfo {
     pub permissions_valid: bool,
     pub expiring_soon: Vec<String>,
     pub last_checked: DateTime<Utc>,
+    /// What the node's components served when last probed.
+    #[serde(default)]
+    pub endpoints: Vec<EndpointProbe>,
 }
 #[derive(Debug, Serialize, ToSchema)]
 pub struct NodeTrustInfoSchema {
@@ -59,8 +64,13 @@ pub struct NodeTrustInfoSchema {
     pub permissions_valid: bool,
     pub expiring_soon: Vec<String>,
     pub last_checked: String,
+    pub endpoints: Vec<EndpointProbe>,
 }
 
+/// Local copies of the certificates fetched from the nodes, by host and
+/// remote path.
+const DISCOVERED_DIR: &str = "discovered";
+
 pub struct CertificateDiscovery {
     pub trust_store: Arc<RwLock<HashMap<String, NodeTrustInfo>>>,
     verification_interval: Duration,
@@ -160,6 +170,44 @@ impl CertificateDiscovery {
         Ok(certificates)
     }
 
+    /// Fetches the certificates a node holds at the paths of the remote
+    /// layout into `discovered/<host>/` and analyzes them, so its trust
+    /// reflects what is installed rather than the local copies.
+    pub async fn discover_installed_certificates(
+        &self,
+        transport: &dyn Transport,
+        files: &[Transfer],
+        cert_manager: &mut CertManager,
+    ) -> Vec<CertificateInfo> {
+        let mut certificates = Vec::new();
+        for file in files {
+            let local = Path::new(DISCOVERED_DIR)
+                .join(&file.host)
+                .join(file.remote.trim_start_matches('/'));
+            let fetched = match local.parent() {
+                Some(parent) => fs::create_dir_all(parent),
+                None => Ok(()),
+            }
+            .and_then(|_| transport.get(&file.host, &file.remote, &local));
+            if let Err(e) = fetched {
+                cert_manager.log(&format!(
+                    "Failed to fetch {} from {}:{}: {}",
+                    file.cert_type, file.host, file.remote, e
+                ));
+                continue;
+            }
+
+            match self.analyze_certificate(&local).await {
+                Ok(cert_info) => certificates.push(cert_info),
+                Err(e) => cert_manager.log(&format!(
+                    "Error analyzing {} from {}:{}: {}",
+                    file.cert_type, file.host, file.remote, e
+                )),
+            }
+        }
+        certificates
+    }
+
     pub async fn analyze_certificate(&self, path: &Path) -> io::Result<CertificateInfo> {
         let cert_pem = fs::read(path)?;
 
@@ -263,16 +311,7 @@ impl CertificateDiscovery {
         cert_path: &Path,
         ca_path: &Path,
     ) -> io::Result<bool> {
-        let output = Command::new("openssl")
-            .args(&[
-                "verify",
-                "-CAfile",
-                &ca_path.to_string_lossy(),
-                &cert_path.to_string_lossy(),
-            ])
-            .output()?;
-
-        Ok(output.status.success())
+        Ok(cert::verify_chain(&cert_path.to_string_lossy(), &ca_path.to_string_lossy()).is_ok())
     }
 
     pub async fn check_certificate_expiration(&self, cert_info: &CertificateInfo) -> CertStatus {
@@ -291,6 +330,8 @@ impl CertificateDiscovery {
             cert_type: cert_info.subject.clone(),
             status: status.to_string(),
             last_updated,
+            sha256: None,
+            checksum_matches: None,
         }
     }
 
@@ -306,6 +347,7 @@ impl CertificateDiscovery {
             permissions_valid: true,
             expiring_soon: Vec::new(),
             last_checked: Utc::now(),
+            endpoints: Vec::new(),
         };
 
         for cert in &certs {
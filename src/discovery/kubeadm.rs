@@ -0,0 +1,735 @@
+// src/discovery/kubeadm.rs
+//
+// Takes over the PKI of a cluster built by kubeadm. The PKI tree and the
+// kubeconfigs under /etc/kubernetes are fetched from every node into
+// `imported/<host>/`, each file is classified, keys are paired with their
+// certificates and certificates are checked against the CAs found next to
+// them. The files kubeadm installs at fixed paths are adopted under the types
+// and local paths Starquill gives them, as long as the remote layout installs
+// them back where they were found.
+use crate::cert::verify_against_bundle;
+use crate::remote::{self, RemoteLayout, Transport};
+use base64::{engine::general_purpose, Engine as _};
+use openssl::{
+    pkey::{PKey, Private, Public},
+    x509::X509,
+};
+use serde_yaml::Value;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+const KUBERNETES_DIR: &str = "/etc/kubernetes";
+const PKI_DIRS: [&str; 2] = ["/etc/kubernetes/pki", "/etc/kubernetes/pki/etcd"];
+
+/// Local copies of the fetched files, by host and remote path.
+const IMPORTED_DIR: &str = "imported";
+
+/// Files kubeadm installs at fixed paths below /etc/kubernetes, with the type
+/// and local path they are tracked under. `{node}` is the node's name and
+/// `{member}` its etcd member name.
+const KUBEADM_FILES: &[(&str, &str, &str)] = &[
+    ("pki/ca.crt", "ca.crt", "certs/kubernetes-ca/ca.crt"),
+    ("pki/ca.key", "ca.key", "certs/kubernetes-ca/ca.key"),
+    (
+        "pki/apiserver.crt",
+        "kube-apiserver",
+        "certs/kube-apiserver/kube-apiserver.crt",
+    ),
+    (
+        "pki/apiserver.key",
+        "kube-apiserver-key",
+        "certs/kube-apiserver/kube-apiserver.key",
+    ),
+    (
+        "pki/apiserver-kubelet-client.crt",
+        "kube-apiserver-kubelet-client",
+        "certs/kube-apiserver-kubelet-client/kube-apiserver-kubelet-client.crt",
+    ),
+    (
+        "pki/apiserver-kubelet-client.key",
+        "kube-apiserver-kubelet-client-key",
+        "certs/kube-apiserver-kubelet-client/kube-apiserver-kubelet-client.key",
+    ),
+    (
+        "pki/apiserver-etcd-client.crt",
+        "apiserver-etcd-client",
+        "certs/apiserver-etcd-client/apiserver-etcd-client.crt",
+    ),
+    (
+        "pki/apiserver-etcd-client.key",
+        "apiserver-etcd-client-key",
+        "certs/apiserver-etcd-client/apiserver-etcd-client.key",
+    ),
+    (
+        "pki/front-proxy-ca.crt",
+        "front-proxy-ca",
+        "certs/front-proxy-ca/ca.crt",
+    ),
+    (
+        "pki/front-proxy-ca.key",
+        "front-proxy-ca-key",
+        "certs/front-proxy-ca/ca.key",
+    ),
+    (
+        "pki/front-proxy-client.crt",
+        "front-proxy-client",
+        "certs/front-proxy-client/front-proxy-client.crt",
+    ),
+    (
+        "pki/front-proxy-client.key",
+        "front-proxy-client-key",
+        "certs/front-proxy-client/front-proxy-client.key",
+    ),
+    (
+        "pki/sa.key",
+        "SA Private Key",
+        "certs/service-account/sa.key",
+    ),
+    (
+        "pki/sa.pub",
+        "SA Public Key",
+        "certs/service-account/sa.pub",
+    ),
+    ("pki/etcd/ca.crt", "etcd-ca", "certs/etcd/ca.crt"),
+    ("pki/etcd/ca.key", "etcd-ca-key", "certs/etcd-ca/ca.key"),
+    (
+        "pki/etcd/server.crt",
+        "etcd-server-{member}",
+        "certs/etcd/{member}/server.crt",
+    ),
+    (
+        "pki/etcd/server.key",
+        "etcd-server-{member}-key",
+        "certs/etcd/{member}/server.key",
+    ),
+    (
+        "pki/etcd/peer.crt",
+        "etcd-peer-{member}",
+        "certs/etcd/{member}/peer.crt",
+    ),
+    (
+        "pki/etcd/peer.key",
+        "etcd-peer-{member}-key",
+        "certs/etcd/{member}/peer.key",
+    ),
+    (
+        "pki/etcd/healthcheck-client.crt",
+        "etcd-healthcheck-client",
+        "certs/etcd/healthcheck-client/healthcheck-client.crt",
+    ),
+    (
+        "pki/etcd/healthcheck-client.key",
+        "etcd-healthcheck-client-key",
+        "certs/etcd/healthcheck-client/healthcheck-client.key",
+    ),
+    ("admin.conf", "kubeconfig-admin", "kubeconfig/admin.conf"),
+    (
+        "super-admin.conf",
+        "kubeconfig-super-admin",
+        "kubeconfig/super-admin.conf",
+    ),
+    (
+        "controller-manager.conf",
+        "kubeconfig-controller-manager",
+        "kubeconfig/controller-manager.conf",
+    ),
+    (
+        "scheduler.conf",
+        "kubeconfig-scheduler",
+        "kubeconfig/scheduler.conf",
+    ),
+    (
+        "kubelet.conf",
+        "kubeconfig-kubelet-{node}",
+        "kubeconfig/{node}/kubelet.conf",
+    ),
+];
+
+/// Copies the issuing CA directories need once a CA has been adopted. kubeadm's
+/// CAs are self-signed, so the chain of the Kubernetes CA is the CA itself, and
+/// its etcd CA issues from `certs/etcd-ca`.
+pub const ISSUER_COPIES: &[(&str, &str)] = &[
+    (
+        "certs/kubernetes-ca/ca.crt",
+        "certs/kubernetes-ca/ca-chain.crt",
+    ),
+    ("certs/etcd/ca.crt", "certs/etcd-ca/ca.crt"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PkiFileKind {
+    Ca,
+    Leaf,
+    PrivateKey,
+    /// The service account signing key or its public half.
+    ServiceAccountKey,
+    /// A kubeconfig carrying its client certificate and key.
+    EmbeddedKubeconfig,
+    /// A kubeconfig referring to certificate files, like the kubelet's.
+    Kubeconfig,
+    Unknown,
+}
+
+impl PkiFileKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ca => "CA",
+            Self::Leaf => "certificate",
+            Self::PrivateKey => "private key",
+            Self::ServiceAccountKey => "service account key",
+            Self::EmbeddedKubeconfig => "kubeconfig with embedded certificates",
+            Self::Kubeconfig => "kubeconfig",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// A node to import from.
+pub struct ImportNode {
+    pub host: String,
+    /// Name the kubelet registers, used for `kubelet.conf`.
+    pub node_name: String,
+    /// etcd member name when the node runs etcd.
+    pub etcd_member: Option<String>,
+}
+
+pub struct ImportedFile {
+    pub host: String,
+    pub remote: String,
+    /// The fetched copy.
+    pub fetched: PathBuf,
+    pub kind: PkiFileKind,
+    pub subject: Option<String>,
+    /// Failed pairing, chain or layout checks.
+    pub problems: Vec<String>,
+    /// Type and local path the file is adopted as.
+    pub adopted: Option<(String, String)>,
+}
+
+#[derive(Default)]
+pub struct KubeadmImport {
+    pub files: Vec<ImportedFile>,
+    /// Hosts that could not be read, with the reason.
+    pub failed_hosts: Vec<(String, String)>,
+}
+
+/// Contents of one fetched file, parsed as far as they could be.
+struct Fetched {
+    remote: String,
+    local: PathBuf,
+    contents: Vec<u8>,
+    cert: Option<X509>,
+    key: Option<PKey<Private>>,
+    public: Option<PKey<Public>>,
+}
+
+impl Fetched {
+    fn new(remote: String, local: PathBuf, contents: Vec<u8>) -> Self {
+        Self {
+            cert: X509::from_pem(&contents).ok(),
+            key: PKey::private_key_from_pem(&contents).ok(),
+            public: PKey::public_key_from_pem(&contents).ok(),
+            remote,
+            local,
+            contents,
+        }
+    }
+
+    fn stem(&self) -> &str {
+        self.remote
+            .rsplit_once('.')
+            .map_or(self.remote.as_str(), |(stem, _)| stem)
+    }
+}
+
+/// Fetches and checks the kubeadm PKI of every node.
+pub fn import_kubeadm_pki(
+    transport: &dyn Transport,
+    nodes: &[ImportNode],
+    layout: &RemoteLayout,
+) -> KubeadmImport {
+    let mut import = KubeadmImport::default();
+    for node in nodes {
+        match fetch_host(transport, &node.host) {
+            Ok(fetched) => import.files.extend(check_host(node, &fetched, layout)),
+            Err(e) => import.failed_hosts.push((node.host.clone(), e.to_string())),
+        }
+    }
+    import
+}
+
+fn fetch_host(transport: &dyn Transport, host: &str) -> io::Result<Vec<Fetched>> {
+    let mut remotes = Vec::new();
+    for dir in PKI_DIRS {
+        // Entries without an extension are directories such as `etcd`
+        for name in transport.list(host, dir)? {
+            if name.contains('.') {
+                remotes.push(format!("{}/{}", dir, name));
+            }
+        }
+    }
+    for name in transport.list(host, KUBERNETES_DIR)? {
+        if name.ends_with(".conf") {
+            remotes.push(format!("{}/{}", KUBERNETES_DIR, name));
+        }
+    }
+    remotes.sort();
+
+    // The copies include private keys
+    remote::create_private_dir(Path::new(IMPORTED_DIR))?;
+    let mut fetched = Vec::new();
+    for remote in remotes {
+        let local = Path::new(IMPORTED_DIR)
+            .join(host)
+            .join(remote.trim_start_matches('/'));
+        if let Some(parent) = local.parent() {
+            remote::create_private_dir(parent)?;
+        }
+        transport.get(host, &remote, &local)?;
+        let contents = fs::read(&local)?;
+        fetched.push(Fetched::new(remote, local, contents));
+    }
+    Ok(fetched)
+}
+
+fn check_host(node: &ImportNode, fetched: &[Fetched], layout: &RemoteLayout) -> Vec<ImportedFile> {
+    let cas: Vec<X509> = fetched
+        .iter()
+        .filter_map(|file| file.cert.clone())
+        .filter(is_ca)
+        .collect();
+    let cluster_ca = fetched
+        .iter()
+        .find(|file| file.remote == format!("{}/ca.crt", PKI_DIRS[0]))
+        .and_then(|file| file.cert.as_ref());
+
+    fetched
+        .iter()
+        .map(|file| {
+            let mut problems = Vec::new();
+            let mut subject = None;
+            let kind = if let Some(cert) = &file.cert {
+                subject = Some(subject_of(cert));
+                if let Err(e) = verify_against_bundle(cert, &cas) {
+                    problems.push(format!("chain: {}", e));
+                }
+                if is_ca(cert) {
+                    PkiFileKind::Ca
+                } else {
+                    PkiFileKind::Leaf
+                }
+            } else if let Some(key) = &file.key {
+                let partner = |extension: &str| {
+                    let path = format!("{}.{}", file.stem(), extension);
+                    fetched.iter().find(|other| other.remote == path)
+                };
+                match (partner("crt"), partner("pub")) {
+                    (Some(cert), _) => {
+                        let pairs = cert
+                            .cert
+                            .as_ref()
+                            .and_then(|cert| cert.public_key().ok())
+                            .is_some_and(|public| public.public_eq(key));
+                        if !pairs {
+                            problems.push(format!("does not match {}", cert.remote));
+                        }
+                        PkiFileKind::PrivateKey
+                    }
+                    (None, Some(public)) => {
+                        let pairs = public
+                            .public
+                            .as_ref()
+                            .is_some_and(|public| public.public_eq(key));
+                        if !pairs {
+                            problems.push(format!("does not match {}", public.remote));
+                        }
+                        PkiFileKind::ServiceAccountKey
+                    }
+                    (None, None) => {
+                        problems.push("no certificate or public key next to it".to_string());
+                        PkiFileKind::PrivateKey
+                    }
+                }
+            } else if file.public.is_some() {
+                PkiFileKind::ServiceAccountKey
+            } else {
+                check_kubeconfig(&file.contents, cluster_ca, &mut subject, &mut problems)
+            };
+
+            let mut adopted = adoption(node, &file.remote);
+            if let Some((_, local)) = &adopted {
+                let target = layout.target(&layout.source(local), &node.host);
+                if target != file.remote {
+                    problems.push(format!(
+                        "the remote layout installs {} at {}; not adopted",
+                        local, target
+                    ));
+                    adopted = None;
+                }
+            }
+
+            ImportedFile {
+                host: node.host.clone(),
+                remote: file.remote.clone(),
+                fetched: file.local.clone(),
+                kind,
+                subject,
+                problems,
+                adopted,
+            }
+        })
+        .collect()
+}
+
+/// Classifies a file that is neither a certificate nor a key, checking the
+/// credentials of kubeconfigs that embed them.
+fn check_kubeconfig(
+    contents: &[u8],
+    cluster_ca: Option<&X509>,
+    subject: &mut Option<String>,
+    problems: &mut Vec<String>,
+) -> PkiFileKind {
+    let config: Value = match serde_yaml::from_slice(contents) {
+        Ok(config) => config,
+        Err(_) => return PkiFileKind::Unknown,
+    };
+    if config["kind"].as_str() != Some("Config") {
+        return PkiFileKind::Unknown;
+    }
+
+    let data = |value: &Value| {
+        value
+            .as_str()
+            .and_then(|data| general_purpose::STANDARD.decode(data).ok())
+    };
+    let user = &config["users"][0]["user"];
+    let (cert, key) = match (
+        data(&user["client-certificate-data"]),
+        data(&user["client-key-data"]),
+    ) {
+        (Some(cert), Some(key)) => (cert, key),
+        _ => return PkiFileKind::Kubeconfig,
+    };
+
+    let cert = match X509::from_pem(&cert) {
+        Ok(cert) => cert,
+        Err(_) => {
+            problems.push("client-certificate-data is not a certificate".to_string());
+            return PkiFileKind::EmbeddedKubeconfig;
+        }
+    };
+    *subject = Some(subject_of(&cert));
+
+    let pairs = PKey::private_key_from_pem(&key)
+        .ok()
+        .zip(cert.public_key().ok())
+        .is_some_and(|(key, public)| public.public_eq(&key));
+    if !pairs {
+        problems.push("client-key-data does not match the client certificate".to_string());
+    }
+
+    let authority = data(&config["clusters"][0]["cluster"]["certificate-authority-data"])
+        .and_then(|ca| X509::stack_from_pem(&ca).ok())
+        .unwrap_or_default();
+    if authority.is_empty() {
+        problems.push("no certificate-authority-data".to_string());
+    } else if let Err(e) = verify_against_bundle(&cert, &authority) {
+        problems.push(format!("chain: {}", e));
+    }
+    let same_ca = |ca: &X509| {
+        authority
+            .iter()
+            .any(|other| other.to_der().ok() == ca.to_der().ok())
+    };
+    if cluster_ca.is_some_and(|ca| !same_ca(ca)) {
+        problems.push("certificate-authority-data is not pki/ca.crt".to_string());
+    }
+    PkiFileKind::EmbeddedKubeconfig
+}
+
+/// The type and local path `remote` is adopted as on `node`, if kubeadm
+/// installs it at a fixed path.
+fn adoption(node: &ImportNode, remote: &str) -> Option<(String, String)> {
+    let relative = remote.strip_prefix(KUBERNETES_DIR)?.trim_start_matches('/');
+    let (_, cert_type, local) = KUBEADM_FILES.iter().find(|(path, ..)| *path == relative)?;
+    if cert_type.contains("{member}") && node.etcd_member.is_none() {
+        return None;
+    }
+    let member = node.etcd_member.as_deref().unwrap_or_default();
+    let fill = |template: &str| {
+        template
+            .replace("{node}", &node.node_name)
+            .replace("{member}", member)
+    };
+    Some((fill(cert_type), fill(local)))
+}
+
+fn is_ca(cert: &X509) -> bool {
+    inspect(cert, |parsed| parsed.is_ca()).unwrap_or(false)
+}
+
+fn subject_of(cert: &X509) -> String {
+    inspect(cert, |parsed| parsed.subject().to_string()).unwrap_or_default()
+}
+
+fn inspect<T>(cert: &X509, f: impl FnOnce(&X509Certificate) -> T) -> Option<T> {
+    let der = cert.to_der().ok()?;
+    let (_, parsed) = X509Certificate::from_der(&der).ok()?;
+    Some(f(&parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert::{profiles::TemplateContext, testing::TestPki};
+    use crate::remote::LayoutSettings;
+    use openssl::rsa::Rsa;
+
+    const CONTROL_PLANE: &str = "10.0.0.1";
+
+    fn fetched(remote: &str, contents: Vec<u8>) -> Fetched {
+        Fetched::new(remote.to_string(), PathBuf::from(remote), contents)
+    }
+
+    fn read(path: &str) -> Vec<u8> {
+        fs::read(path).unwrap()
+    }
+
+    /// The files of a kubeadm control plane, issued by `pki` and installed
+    /// under kubeadm's names.
+    fn control_plane(pki: &mut TestPki) -> Vec<Fetched> {
+        let (root_ca, _) = pki.issue("root-ca", &TemplateContext::new());
+        let (ca, ca_key) = pki.issue("kubernetes-ca", &TemplateContext::new());
+        let (apiserver, apiserver_key) = pki.issue(
+            "kube-apiserver",
+            &TemplateContext::new().with_control_plane(CONTROL_PLANE),
+        );
+        let sa = Rsa::generate(2048).unwrap();
+        vec![
+            fetched("/etc/kubernetes/pki/root-ca.crt", read(&root_ca)),
+            fetched("/etc/kubernetes/pki/ca.crt", read(&ca)),
+            fetched("/etc/kubernetes/pki/ca.key", read(&ca_key)),
+            fetched("/etc/kubernetes/pki/apiserver.crt", read(&apiserver)),
+            fetched("/etc/kubernetes/pki/apiserver.key", read(&apiserver_key)),
+            fetched(
+                "/etc/kubernetes/pki/sa.key",
+                sa.private_key_to_pem().unwrap(),
+            ),
+            fetched(
+                "/etc/kubernetes/pki/sa.pub",
+                sa.public_key_to_pem().unwrap(),
+            ),
+        ]
+    }
+
+    /// A kubeconfig embedding `cert` and `key`, trusting `ca`.
+    fn kubeconfig(ca: &[u8], cert: &[u8], key: &[u8]) -> Vec<u8> {
+        let data = |pem: &[u8]| general_purpose::STANDARD.encode(pem);
+        format!(
+            "apiVersion: v1\n\
+             kind: Config\n\
+             clusters:\n\
+             - name: kubernetes\n  cluster:\n    certificate-authority-data: {}\n\
+             users:\n\
+             - name: admin\n  user:\n    client-certificate-data: {}\n    client-key-data: {}\n",
+            data(ca),
+            data(cert),
+            data(key)
+        )
+        .into_bytes()
+    }
+
+    fn check(files: &[Fetched], layout: &LayoutSettings) -> Vec<ImportedFile> {
+        let node = ImportNode {
+            host: CONTROL_PLANE.to_string(),
+            node_name: "control-plane".to_string(),
+            etcd_member: None,
+        };
+        let layout = RemoteLayout::new(layout, "/etc/kubernetes/pki", CONTROL_PLANE, Vec::new());
+        check_host(&node, files, &layout)
+    }
+
+    fn file<'a>(checked: &'a [ImportedFile], remote: &str) -> &'a ImportedFile {
+        checked.iter().find(|file| file.remote == remote).unwrap()
+    }
+
+    #[test]
+    fn files_are_classified_and_adopted_under_starquills_names() {
+        let mut pki = TestPki::new();
+        let checked = check(&control_plane(&mut pki), &LayoutSettings::default());
+
+        let kinds: Vec<(&str, PkiFileKind)> = checked
+            .iter()
+            .map(|file| (file.remote.as_str(), file.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("/etc/kubernetes/pki/root-ca.crt", PkiFileKind::Ca),
+                ("/etc/kubernetes/pki/ca.crt", PkiFileKind::Ca),
+                ("/etc/kubernetes/pki/ca.key", PkiFileKind::PrivateKey),
+                ("/etc/kubernetes/pki/apiserver.crt", PkiFileKind::Leaf),
+                ("/etc/kubernetes/pki/apiserver.key", PkiFileKind::PrivateKey),
+                ("/etc/kubernetes/pki/sa.key", PkiFileKind::ServiceAccountKey),
+                ("/etc/kubernetes/pki/sa.pub", PkiFileKind::ServiceAccountKey),
+            ]
+        );
+        for file in &checked {
+            assert!(
+                file.problems.is_empty(),
+                "{}: {:?}",
+                file.remote,
+                file.problems
+            );
+        }
+
+        let apiserver = file(&checked, "/etc/kubernetes/pki/apiserver.crt");
+        assert_eq!(
+            apiserver.adopted,
+            Some((
+                "kube-apiserver".to_string(),
+                "certs/kube-apiserver/kube-apiserver.crt".to_string()
+            ))
+        );
+        // kubeadm has no root CA of its own
+        assert_eq!(
+            file(&checked, "/etc/kubernetes/pki/root-ca.crt").adopted,
+            None
+        );
+    }
+
+    #[test]
+    fn mismatched_keys_and_foreign_certificates_are_reported() {
+        let mut pki = TestPki::new();
+        let mut files = control_plane(&mut pki);
+        let (_, admin_key) = pki.issue("admin", &TemplateContext::new());
+        files.retain(|file| file.remote != "/etc/kubernetes/pki/apiserver.key");
+        files.push(fetched(
+            "/etc/kubernetes/pki/apiserver.key",
+            read(&admin_key),
+        ));
+        files.push(fetched("/etc/kubernetes/pki/orphan.key", read(&admin_key)));
+
+        let mut foreign = TestPki::new();
+        foreign.issue("root-ca", &TemplateContext::new());
+        foreign.issue("kubernetes-ca", &TemplateContext::new());
+        let (stray, _) = foreign.issue("admin", &TemplateContext::new());
+        files.push(fetched(
+            "/etc/kubernetes/pki/front-proxy-client.crt",
+            read(&stray),
+        ));
+
+        let checked = check(&files, &LayoutSettings::default());
+        assert_eq!(
+            file(&checked, "/etc/kubernetes/pki/apiserver.key").problems,
+            ["does not match /etc/kubernetes/pki/apiserver.crt"]
+        );
+        assert_eq!(
+            file(&checked, "/etc/kubernetes/pki/orphan.key").problems,
+            ["no certificate or public key next to it"]
+        );
+        let stray = file(&checked, "/etc/kubernetes/pki/front-proxy-client.crt");
+        assert_eq!(stray.kind, PkiFileKind::Leaf);
+        assert!(stray.problems[0].starts_with("chain:"));
+    }
+
+    #[test]
+    fn kubeconfigs_are_checked_against_the_cluster_ca() {
+        let mut pki = TestPki::new();
+        let mut files = control_plane(&mut pki);
+        let (admin, admin_key) = pki.issue("admin", &TemplateContext::new());
+        let ca = read(&pki.ca_dir("kubernetes-ca").join("ca.crt").to_string_lossy());
+        let root_ca = read(&pki.ca_dir("root-ca").join("ca.crt").to_string_lossy());
+        let (admin, admin_key) = (read(&admin), read(&admin_key));
+
+        files.push(fetched(
+            "/etc/kubernetes/admin.conf",
+            kubeconfig(&[ca.as_slice(), &root_ca].concat(), &admin, &admin_key),
+        ));
+        files.push(fetched(
+            "/etc/kubernetes/super-admin.conf",
+            kubeconfig(&root_ca, &admin, &admin_key),
+        ));
+        files.push(fetched(
+            "/etc/kubernetes/kubelet.conf",
+            b"apiVersion: v1\nkind: Config\nusers:\n- name: kubelet\n  user:\n    \
+              client-certificate: /var/lib/kubelet/pki/kubelet-client-current.pem\n"
+                .to_vec(),
+        ));
+        files.push(fetched(
+            "/etc/kubernetes/notes.conf",
+            b"not yaml: [".to_vec(),
+        ));
+
+        let checked = check(&files, &LayoutSettings::default());
+        let admin = file(&checked, "/etc/kubernetes/admin.conf");
+        assert_eq!(admin.kind, PkiFileKind::EmbeddedKubeconfig);
+        assert!(admin.problems.is_empty(), "{:?}", admin.problems);
+        assert_eq!(
+            admin.adopted,
+            Some((
+                "kubeconfig-admin".to_string(),
+                "kubeconfig/admin.conf".to_string()
+            ))
+        );
+
+        let super_admin = file(&checked, "/etc/kubernetes/super-admin.conf");
+        assert!(super_admin
+            .problems
+            .contains(&"certificate-authority-data is not pki/ca.crt".to_string()));
+
+        let kubelet = file(&checked, "/etc/kubernetes/kubelet.conf");
+        assert_eq!(kubelet.kind, PkiFileKind::Kubeconfig);
+        assert_eq!(
+            kubelet.adopted,
+            Some((
+                "kubeconfig-kubelet-control-plane".to_string(),
+                "kubeconfig/control-plane/kubelet.conf".to_string()
+            ))
+        );
+        assert_eq!(
+            file(&checked, "/etc/kubernetes/notes.conf").kind,
+            PkiFileKind::Unknown
+        );
+    }
+
+    #[test]
+    fn files_are_only_adopted_where_the_layout_puts_them_back() {
+        let mut pki = TestPki::new();
+        let hard_way: LayoutSettings = serde_json::from_str(r#"{"profile": "hard_way"}"#).unwrap();
+        let checked = check(&control_plane(&mut pki), &hard_way);
+
+        let apiserver = file(&checked, "/etc/kubernetes/pki/apiserver.crt");
+        assert_eq!(apiserver.adopted, None);
+        assert_eq!(
+            apiserver.problems,
+            [
+                "the remote layout installs certs/kube-apiserver/kube-apiserver.crt at \
+              /var/lib/kubernetes/kube-apiserver.crt; not adopted"
+            ]
+        );
+    }
+
+    #[test]
+    fn etcd_member_files_are_only_adopted_on_members() {
+        let mut node = ImportNode {
+            host: CONTROL_PLANE.to_string(),
+            node_name: "control-plane".to_string(),
+            etcd_member: None,
+        };
+        assert_eq!(adoption(&node, "/etc/kubernetes/pki/etcd/server.crt"), None);
+
+        node.etcd_member = Some("etcd-1".to_string());
+        assert_eq!(
+            adoption(&node, "/etc/kubernetes/pki/etcd/server.crt"),
+            Some((
+                "etcd-server-etcd-1".to_string(),
+                "certs/etcd/etcd-1/server.crt".to_string()
+            ))
+        );
+        assert_eq!(adoption(&node, "/etc/kubernetes/pki/unknown.crt"), None);
+    }
+}
@@ -1,4 +1,6 @@
 mod discover;
+pub mod kubeadm;
+pub mod probe;
 mod kubeconfig;
 mod ssh;
 
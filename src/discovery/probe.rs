@@ -0,0 +1,524 @@
+// src/discovery/probe.rs
+//
+// Checks what the cluster's components actually serve. A TLS connection is
+// opened to each component's port on the nodes that run it, and the chain it
+// presents is checked against the local CA bundle, the address it was reached
+// at and the certificate tracked for it. The handshake itself does not verify
+// anything, so a chain is captured even when it would be rejected.
+use crate::cert::verification::covers;
+use crate::cert::{load_certificate_bundle, verify_against_bundle};
+use chrono::{TimeZone, Utc};
+use openssl::{
+    hash::MessageDigest,
+    ssl::{HandshakeError, SslConnector, SslMethod, SslRef, SslVerifyMode},
+    x509::X509,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+use utoipa::ToSchema;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// A component serving TLS on a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    KubeApiserver,
+    Kubelet,
+    /// etcd's client port.
+    EtcdClient,
+    EtcdPeer,
+    KubeControllerManager,
+    KubeScheduler,
+}
+
+impl Component {
+    pub const ALL: [Component; 6] = [
+        Component::KubeApiserver,
+        Component::Kubelet,
+        Component::EtcdClient,
+        Component::EtcdPeer,
+        Component::KubeControllerManager,
+        Component::KubeScheduler,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::KubeApiserver => "kube-apiserver",
+            Self::Kubelet => "kubelet",
+            Self::EtcdClient => "etcd-client",
+            Self::EtcdPeer => "etcd-peer",
+            Self::KubeControllerManager => "kube-controller-manager",
+            Self::KubeScheduler => "kube-scheduler",
+        }
+    }
+
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Self::KubeApiserver => 6443,
+            Self::Kubelet => 10250,
+            Self::EtcdClient => 2379,
+            Self::EtcdPeer => 2380,
+            Self::KubeControllerManager => 10257,
+            Self::KubeScheduler => 10259,
+        }
+    }
+
+    /// Glob over the local path of the certificate the component serves,
+    /// when Starquill issues one.
+    pub fn tracked_source(&self) -> Option<&'static str> {
+        match self {
+            Self::KubeApiserver => Some("certs/kube-apiserver/kube-apiserver.crt"),
+            Self::Kubelet => Some("certs/nodes/*/kubelet.crt"),
+            Self::EtcdClient => Some("certs/etcd/*/server.crt"),
+            Self::EtcdPeer => Some("certs/etcd/*/peer.crt"),
+            // Both serve a self-signed certificate unless configured otherwise
+            Self::KubeControllerManager | Self::KubeScheduler => None,
+        }
+    }
+}
+
+/// `probe` in cluster_config.json.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProbeSettings {
+    /// Connect and handshake timeout per endpoint.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Ports differing from the components' defaults.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ports: BTreeMap<Component, u16>,
+}
+
+fn default_timeout_ms() -> u64 {
+    3000
+}
+
+impl Default for ProbeSettings {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_timeout_ms(),
+            ports: BTreeMap::new(),
+        }
+    }
+}
+
+impl ProbeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.timeout_ms == 0 {
+            return Err("probe.timeout_ms must be at least 1".into());
+        }
+        if let Some((component, _)) = self.ports.iter().find(|(_, port)| **port == 0) {
+            return Err(format!(
+                "probe.ports.{}: port must not be 0",
+                component.as_str()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn port(&self, component: Component) -> u16 {
+        self.ports
+            .get(&component)
+            .copied()
+            .unwrap_or_else(|| component.default_port())
+    }
+}
+
+/// An endpoint to probe and what it should present.
+#[derive(Debug, Clone)]
+pub struct ProbeTarget {
+    pub host: String,
+    pub port: u16,
+    pub component: Component,
+    /// Local CA bundle the presented chain must verify against.
+    pub ca_bundle: String,
+    /// Type and local path of the certificate tracked for the endpoint.
+    pub tracked: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PresentedCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub not_after: String,
+    /// SHA-256 of the DER encoding.
+    pub fingerprint: String,
+}
+
+/// What one endpoint presented. A check that does not apply, or could not
+/// run, is `None`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointProbe {
+    pub host: String,
+    pub port: u16,
+    pub component: Component,
+    pub reachable: bool,
+    /// The presented chain, leaf first.
+    pub chain: Vec<PresentedCertificate>,
+    pub chain_valid: Option<bool>,
+    /// The leaf's subject alternative names cover the address probed.
+    pub name_matches: Option<bool>,
+    /// The leaf is the certificate tracked for the endpoint.
+    pub fingerprint_matches: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracked_cert: Option<String>,
+    pub days_left: Option<i64>,
+    pub problems: Vec<String>,
+    pub checked: String,
+}
+
+impl EndpointProbe {
+    fn new(target: &ProbeTarget) -> Self {
+        Self {
+            host: target.host.clone(),
+            port: target.port,
+            component: target.component,
+            reachable: false,
+            chain: Vec::new(),
+            chain_valid: None,
+            name_matches: None,
+            fingerprint_matches: None,
+            tracked_cert: target
+                .tracked
+                .as_ref()
+                .map(|(cert_type, _)| cert_type.clone()),
+            days_left: None,
+            problems: Vec::new(),
+            checked: Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn valid(&self) -> bool {
+        self.reachable && self.problems.is_empty()
+    }
+}
+
+/// Probes one endpoint. Failures end up in the result's problems.
+pub fn probe_endpoint(target: &ProbeTarget, timeout: Duration) -> EndpointProbe {
+    let mut result = EndpointProbe::new(target);
+    let chain = match presented_chain(&target.host, target.port, timeout) {
+        Ok(chain) => chain,
+        Err(e) => {
+            result.problems.push(e);
+            return result;
+        }
+    };
+    result.reachable = true;
+    result.chain = chain.iter().map(describe).collect();
+
+    let Some(leaf) = chain.first() else {
+        result.problems.push("no certificate presented".to_string());
+        return result;
+    };
+
+    match load_certificate_bundle(&target.ca_bundle) {
+        Ok(mut bundle) => {
+            // Intermediates the endpoint sends along are not trusted by
+            // themselves, only if they chain to the bundle
+            let intermediates: Vec<X509> = chain[1..]
+                .iter()
+                .filter(|cert| verify_against_bundle(cert, &bundle).is_ok())
+                .cloned()
+                .collect();
+            bundle.extend(intermediates);
+            let valid = verify_against_bundle(leaf, &bundle);
+            result.chain_valid = Some(valid.is_ok());
+            if let Err(e) = valid {
+                result.problems.push(format!(
+                    "chain does not verify against {}: {}",
+                    target.ca_bundle, e
+                ));
+            }
+        }
+        Err(e) => result
+            .problems
+            .push(format!("CA bundle {} unusable: {}", target.ca_bundle, e)),
+    }
+
+    if let Ok(der) = leaf.to_der() {
+        if let Ok((_, parsed)) = X509Certificate::from_der(&der) {
+            let names = parsed
+                .subject_alternative_name()
+                .ok()
+                .flatten()
+                .map(|san| san.value.general_names.clone())
+                .unwrap_or_default();
+            let matches = covers(&names, &target.host);
+            result.name_matches = Some(matches);
+            if !matches {
+                result.problems.push(format!(
+                    "subject alternative names do not cover {}",
+                    target.host
+                ));
+            }
+
+            let not_after = parsed.validity().not_after.timestamp();
+            let days_left = (not_after - Utc::now().timestamp()).div_euclid(86_400);
+            result.days_left = Some(days_left);
+            if days_left < 0 {
+                result
+                    .problems
+                    .push("presents an expired certificate".to_string());
+            }
+        }
+    }
+
+    if let Some((cert_type, path)) = &target.tracked {
+        let tracked = fs::read(path)
+            .ok()
+            .and_then(|pem| X509::from_pem(&pem).ok())
+            .map(|cert| fingerprint(&cert));
+        match tracked {
+            Some(expected) => {
+                let matches = result.chain[0].fingerprint == expected;
+                result.fingerprint_matches = Some(matches);
+                if !matches {
+                    result.problems.push(format!(
+                        "serves a different certificate than the tracked {}",
+                        cert_type
+                    ));
+                }
+            }
+            None => result
+                .problems
+                .push(format!("tracked certificate {} unreadable", path)),
+        }
+    }
+
+    result
+}
+
+/// Connects and returns the chain the endpoint presents, leaf first. A
+/// handshake the server aborts after presenting its chain, e.g. because it
+/// requires a client certificate, still yields the chain.
+fn presented_chain(host: &str, port: u16, timeout: Duration) -> Result<Vec<X509>, String> {
+    let address = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("cannot resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("cannot resolve {}", host))?;
+    let stream = TcpStream::connect_timeout(&address, timeout)
+        .map_err(|e| format!("cannot connect to {}:{}: {}", host, port, e))?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(|e| e.to_string())?;
+
+    let mut builder = SslConnector::builder(SslMethod::tls_client()).map_err(|e| e.to_string())?;
+    builder.set_verify(SslVerifyMode::NONE);
+    let mut config = builder
+        .build()
+        .configure()
+        .map_err(|e| e.to_string())?
+        .verify_hostname(false);
+    config.set_verify(SslVerifyMode::NONE);
+
+    let chain = match config.connect(host, stream) {
+        Ok(stream) => peer_chain(stream.ssl()),
+        Err(HandshakeError::Failure(stream)) => peer_chain(stream.ssl()),
+        Err(e) => {
+            return Err(format!(
+                "TLS handshake with {}:{} failed: {}",
+                host, port, e
+            ))
+        }
+    };
+    if chain.is_empty() {
+        return Err(format!(
+            "TLS handshake with {}:{} failed before a certificate was presented",
+            host, port
+        ));
+    }
+    Ok(chain)
+}
+
+fn peer_chain(ssl: &SslRef) -> Vec<X509> {
+    ssl.peer_cert_chain()
+        .map(|chain| chain.iter().map(|cert| cert.to_owned()).collect())
+        .unwrap_or_default()
+}
+
+fn fingerprint(cert: &X509) -> String {
+    cert.digest(MessageDigest::sha256())
+        .map(|digest| hex::encode(&*digest))
+        .unwrap_or_default()
+}
+
+fn describe(cert: &X509) -> PresentedCertificate {
+    let der = cert.to_der().unwrap_or_default();
+    let parsed = X509Certificate::from_der(&der).ok();
+    PresentedCertificate {
+        subject: parsed
+            .as_ref()
+            .map(|(_, cert)| cert.subject().to_string())
+            .unwrap_or_default(),
+        issuer: parsed
+            .as_ref()
+            .map(|(_, cert)| cert.issuer().to_string())
+            .unwrap_or_default(),
+        not_after: parsed
+            .as_ref()
+            .and_then(|(_, cert)| {
+                Utc.timestamp_opt(cert.validity().not_after.timestamp(), 0)
+                    .single()
+            })
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default(),
+        fingerprint: fingerprint(cert),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::{
+        asn1::Asn1Time,
+        bn::{BigNum, MsbOption},
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+        pkey::{PKey, Private},
+        ssl::SslAcceptor,
+        x509::{
+            extension::{BasicConstraints, KeyUsage, SubjectAlternativeName},
+            X509Builder, X509NameBuilder,
+        },
+    };
+    use std::{net::TcpListener, path::Path, thread};
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    /// A certificate for `name` valid for `days` and a half, so whole days
+    /// left do not depend on when the test runs, signed by `issuer` or
+    /// self-signed as a CA.
+    fn certificate(
+        name: &str,
+        key: &PKey<Private>,
+        issuer: Option<(&X509, &PKey<Private>)>,
+        days: i64,
+    ) -> X509 {
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", name).unwrap();
+        let subject = subject.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let mut serial = BigNum::new().unwrap();
+        serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+        builder
+            .set_serial_number(&serial.to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(
+                &Asn1Time::from_unix(Utc::now().timestamp() + days * 86_400 + 43_200).unwrap(),
+            )
+            .unwrap();
+
+        match issuer {
+            None => {
+                builder.set_issuer_name(&subject).unwrap();
+                builder
+                    .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                    .unwrap();
+                builder
+                    .append_extension(
+                        KeyUsage::new()
+                            .critical()
+                            .key_cert_sign()
+                            .crl_sign()
+                            .build()
+                            .unwrap(),
+                    )
+                    .unwrap();
+                builder.sign(key, MessageDigest::sha256()).unwrap();
+            }
+            Some((ca, ca_key)) => {
+                builder.set_issuer_name(ca.subject_name()).unwrap();
+                let san = SubjectAlternativeName::new()
+                    .ip("127.0.0.1")
+                    .build(&builder.x509v3_context(Some(ca), None))
+                    .unwrap();
+                builder.append_extension(san).unwrap();
+                builder.sign(ca_key, MessageDigest::sha256()).unwrap();
+            }
+        }
+        builder.build()
+    }
+
+    /// Serves `leaf` on a free port of 127.0.0.1 for `connections` handshakes.
+    fn serve(leaf: &X509, key: &PKey<Private>, connections: usize) -> u16 {
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate(leaf).unwrap();
+        acceptor.set_private_key(key).unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let _ = acceptor.accept(stream.unwrap());
+            }
+        });
+        port
+    }
+
+    fn write(dir: &Path, name: &str, cert: &X509) -> String {
+        let path = dir.join(name);
+        fs::write(&path, cert.to_pem().unwrap()).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn probe_checks_the_presented_chain() {
+        let dir = std::env::temp_dir().join(format!("starquill-probe-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let ca_key = key();
+        let ca = certificate("probe-ca", &ca_key, None, 365);
+        let leaf_key = key();
+        let leaf = certificate("kube-apiserver", &leaf_key, Some((&ca, &ca_key)), 30);
+        let other_key = key();
+        let other = certificate("kube-apiserver", &other_key, Some((&ca, &ca_key)), 30);
+
+        let ca_bundle = write(&dir, "ca.crt", &ca);
+        let port = serve(&leaf, &leaf_key, 2);
+        let target = |tracked: String| ProbeTarget {
+            host: "127.0.0.1".to_string(),
+            port,
+            component: Component::KubeApiserver,
+            ca_bundle: ca_bundle.clone(),
+            tracked: Some(("kube-apiserver".to_string(), tracked)),
+        };
+        let timeout = Duration::from_secs(5);
+
+        let matching = probe_endpoint(&target(write(&dir, "leaf.crt", &leaf)), timeout);
+        let mismatching = probe_endpoint(&target(write(&dir, "other.crt", &other)), timeout);
+        fs::remove_dir_all(&dir).unwrap();
+
+        for probe in [&matching, &mismatching] {
+            assert!(probe.reachable, "{:?}", probe.problems);
+            assert_eq!(probe.chain_valid, Some(true));
+            assert_eq!(probe.name_matches, Some(true));
+            assert_eq!(probe.days_left, Some(30));
+            assert_eq!(probe.chain[0].fingerprint, fingerprint(&leaf));
+        }
+        assert_eq!(matching.fingerprint_matches, Some(true));
+        assert!(matching.valid(), "{:?}", matching.problems);
+        assert_eq!(mismatching.fingerprint_matches, Some(false));
+        assert_eq!(
+            mismatching.problems,
+            ["serves a different certificate than the tracked kube-apiserver"]
+        );
+    }
+}
@@ -1,10 +1,10 @@
 // src/ssh.rs
 
+use crate::remote::{SshOptions, SshPool};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::io;
-use std::process::Command;
 use std::sync::Arc;
 use std::sync::RwLock;
 use std::time::Duration;
@@ -95,8 +95,7 @@ impl SSHConnectionCache {
 
 pub async fn verify_ssh_connection(
     host: &str,
-    user: &str,
-    key_path: &str,
+    ssh: &SshOptions,
     cache: &mut SSHConnectionCache,
 ) -> io::Result<bool> {
     // Check if we need to recheck
@@ -104,22 +103,13 @@ pub async fn verify_ssh_connection(
         return Ok(true);
     }
 
-    let ssh_command = Command::new("ssh")
-        .args([
-            "-i",
-            key_path,
-            "-o",
-            "BatchMode=yes",
-            "-o",
-            "ConnectTimeout=5",
-            "-o",
-            "StrictHostKeyChecking=no",
-            &format!("{}@{}", user, host),
-            "echo 'Connected successfully'",
-        ])
-        .output()?;
-
-    let success = ssh_command.status.success();
+    // A successful check leaves an authenticated session in the shared pool
+    // for the certificate operations that follow.
+    let pool = SshPool::shared(ssh);
+    let target = host.to_string();
+    let success = tokio::task::spawn_blocking(move || pool.check(&target))
+        .await
+        .map_err(io::Error::other)?;
 
     cache.update_status(host, success);
     cache.save()?;
@@ -136,11 +126,11 @@ enum CheckMessage {
 
 pub fn start_periodic_check(
     cache: Arc<RwLock<SSHConnectionCache>>,
-    user: String,
-    key_path: String,
+    ssh: SshOptions,
 ) {
     let (tx, mut rx) = mpsc::channel(32);
     let tx_clone = tx.clone();
+    let pool = SshPool::shared(&ssh);
 
     // Clone Arc for the checker task
     let checker_cache = Arc::clone(&cache);
@@ -150,21 +140,10 @@ pub fn start_periodic_check(
         while let Some(msg) = rx.recv().await {
             match msg {
                 CheckMessage::Check(host) => {
-                    let success = Command::new("ssh")
-                        .args([
-                            "-i",
-                            &key_path,
-                            "-o",
-                            "BatchMode=yes",
-                            "-o",
-                            "ConnectTimeout=5",
-                            "-o",
-                            "StrictHostKeyChecking=no",
-                            &format!("{}@{}", user, host),
-                            "echo 'Connected successfully'",
-                        ])
-                        .output()
-                        .map(|output| output.status.success())
+                    let pool = Arc::clone(&pool);
+                    let target = host.clone();
+                    let success = tokio::task::spawn_blocking(move || pool.check(&target))
+                        .await
                         .unwrap_or(false);
 
                     let _ = tx.send(CheckMessage::UpdateStatus(host, success)).await;
@@ -1,20 +1,29 @@
+use crate::cert::WorkerNode;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub struct KubeConfigGenerator {
     control_plane_ip: String,
     output_dir: PathBuf,
     ca_path: PathBuf,
+    /// Client certificates are read from `<cert_dir>/<name>/<name>.{crt,key}`.
+    cert_dir: PathBuf,
 }
 
 impl KubeConfigGenerator {
-    pub fn new(control_plane_ip: String, output_dir: PathBuf, ca_path: PathBuf) -> Self {
+    pub fn new(
+        control_plane_ip: String,
+        output_dir: PathBuf,
+        ca_path: PathBuf,
+        cert_dir: PathBuf,
+    ) -> Self {
         Self {
             control_plane_ip,
             output_dir,
             ca_path,
+            cert_dir,
         }
     }
 
@@ -37,67 +46,92 @@ impl KubeConfigGenerator {
         Ok(())
     }
 
-    pub fn generate_node_kubeconfigs(&self, node_indices: &[(usize, String)]) -> io::Result<()> {
-        for (i, _) in node_indices {
-            let node_name = format!("node-{}", i + 1);
-            let credential_name = format!("system:node:worker-node-{}", i + 1);
-            self.generate_kubeconfig(&node_name, &credential_name)?;
+    /// Writes `<output_dir>/<node name>/kubelet.conf` for every node from its
+    /// kubelet client certificate in `<cert_dir>/nodes/<node name>/`.
+    pub fn generate_node_kubeconfigs(&self, nodes: &[WorkerNode]) -> io::Result<()> {
+        for node in nodes {
+            let node_name = node.name();
+            self.generate_kubeconfig_with_cert(
+                &format!("{}/kubelet", node_name),
+                &format!("system:node:{}", node_name),
+                &self
+                    .cert_dir
+                    .join("nodes")
+                    .join(&node_name)
+                    .join("kubelet-client"),
+            )?;
         }
         Ok(())
     }
 
     pub fn generate_kubeconfig(&self, config_name: &str, credential_name: &str) -> io::Result<()> {
+        let client_cert = self.cert_dir.join(config_name).join(config_name);
+        self.generate_kubeconfig_with_cert(config_name, credential_name, &client_cert)
+    }
+
+    /// Writes `<output_dir>/<config_name>.conf` embedding the client
+    /// certificate and key at `<client_cert>.{crt,key}`. `config_name` may
+    /// contain a subdirectory.
+    pub fn generate_kubeconfig_with_cert(
+        &self,
+        config_name: &str,
+        credential_name: &str,
+        client_cert: &Path,
+    ) -> io::Result<()> {
         let kubeconfig_path = self.output_dir.join(format!("{}.conf", config_name));
+        if let Some(parent) = kubeconfig_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let kubeconfig = format!("--kubeconfig={}", kubeconfig_path.display());
         let api_server = format!("https://{}:6443", self.control_plane_ip);
 
         // Set cluster
-        Command::new("kubectl")
-            .args(&[
-                "config",
-                "set-cluster",
-                "default-cluster",
-                &format!("--kubeconfig={}", kubeconfig_path.display()),
-                &format!("--server={}", api_server),
-                &format!("--certificate-authority={}", self.ca_path.display()),
-                "--embed-certs=true",
-            ])
-            .output()?;
+        run_kubectl(&[
+            "config",
+            "set-cluster",
+            "default-cluster",
+            &kubeconfig,
+            &format!("--server={}", api_server),
+            &format!("--certificate-authority={}", self.ca_path.display()),
+            "--embed-certs=true",
+        ])?;
 
         // Set credentials
-        Command::new("kubectl")
-            .args(&[
-                "config",
-                "set-credentials",
-                credential_name,
-                &format!("--kubeconfig={}", kubeconfig_path.display()),
-                &format!("--client-certificate={}/{}.crt", config_name, config_name),
-                &format!("--client-key={}/{}.key", config_name, config_name),
-                "--embed-certs=true",
-            ])
-            .output()?;
+        run_kubectl(&[
+            "config",
+            "set-credentials",
+            credential_name,
+            &kubeconfig,
+            &format!("--client-certificate={}.crt", client_cert.display()),
+            &format!("--client-key={}.key", client_cert.display()),
+            "--embed-certs=true",
+        ])?;
 
         // Set context
-        Command::new("kubectl")
-            .args(&[
-                "config",
-                "set-context",
-                "default-system",
-                &format!("--kubeconfig={}", kubeconfig_path.display()),
-                "--cluster=default-cluster",
-                &format!("--user={}", credential_name),
-            ])
-            .output()?;
+        run_kubectl(&[
+            "config",
+            "set-context",
+            "default-system",
+            &kubeconfig,
+            "--cluster=default-cluster",
+            &format!("--user={}", credential_name),
+        ])?;
 
         // Use context
-        Command::new("kubectl")
-            .args(&[
-                "config",
-                "use-context",
-                "default-system",
-                &format!("--kubeconfig={}", kubeconfig_path.display()),
-            ])
-            .output()?;
+        run_kubectl(&["config", "use-context", "default-system", &kubeconfig])?;
 
         Ok(())
     }
 }
+
+fn run_kubectl(args: &[&str]) -> io::Result<()> {
+    let output = Command::new("kubectl").args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "kubectl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -5,12 +5,17 @@ mod config;
 mod discovery;
 mod kubeconfig;
 mod metrics;
+mod remote;
+mod state;
 mod types;
 mod ui;
 mod utils;
 mod web;
 
 use app::CertManager;
+use cert::revocation::{self, RevocationReason, RevocationTarget};
+use cert::profiles::ProfileSet;
+use cert::{EtcdSettings, KeyAlgorithms, RevocationSettings, SubjectDefaults};
 use config::ClusterConfig;
 
 use clap::Parser;
@@ -20,6 +25,7 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use discovery::CertificateDiscovery;
+use remote::{CheckStatus, HostKey, KnownHosts};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::{
     io::{self},
@@ -28,7 +34,8 @@ use std::{
     time::Duration,
 };
 use ui::{LoadingState, OnboardingState, StepStatus};
-use web::WebServerState;
+use utils::logging::ConsoleLogger;
+use web::{ApiToken, WebServerState};
 
 #[derive(Parser)]
 pub struct Args {
@@ -38,24 +45,167 @@ pub struct Args {
     pub debug: bool,
     #[arg(short, long, default_value_t = 3000)]
     pub port: u16,
+    /// Revoke every valid certificate of this type (e.g. admin, nodes/worker-2) and exit
+    #[arg(long, conflicts_with = "revoke_serial")]
+    pub revoke: Option<String>,
+    /// Revoke the certificate with this serial number and exit
+    #[arg(long)]
+    pub revoke_serial: Option<String>,
+    /// CRL reason code used with --revoke or --revoke-serial
+    #[arg(long, default_value = "unspecified")]
+    pub reason: String,
+    /// Compare the SHA-256 of every distributed file on its hosts with the
+    /// local copy and exit, failing if any is missing or differs
+    #[arg(long)]
+    pub audit: bool,
+    /// Fetch the PKI and kubeconfigs of a kubeadm cluster from every node,
+    /// adopt them into certs/ and kubeconfig/ and exit, failing if any file
+    /// fails its pairing or chain checks
+    #[arg(long, conflicts_with = "audit")]
+    pub import_kubeadm: bool,
+    /// Open a TLS connection to every component on the nodes, check the
+    /// chain it presents and exit, failing if any endpoint is unreachable or
+    /// fails a check
+    #[arg(long, conflicts_with_all = ["audit", "import_kubeadm"])]
+    pub probe_tls: bool,
+    /// Check every node is reachable, has passwordless sudo, writable target
+    /// directories, free disk space and a synchronized clock and exit,
+    /// failing if any node fails a check
+    #[arg(long, conflicts_with_all = ["audit", "import_kubeadm", "probe_tls"])]
+    pub preflight: bool,
+}
+
+// Non-interactive revocation: update the CA databases, re-sign the affected
+// CRLs and exit without starting the TUI.
+async fn run_revocation(args: &Args) -> io::Result<()> {
+    let reason = RevocationReason::parse(&args.reason).ok_or_else(|| {
+        let valid: Vec<&str> = RevocationReason::ALL.iter().map(|r| r.as_str()).collect();
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown reason '{}', expected one of: {}",
+                args.reason,
+                valid.join(", ")
+            ),
+        )
+    })?;
+
+    // clap rejects --revoke together with --revoke-serial
+    let target = match (&args.revoke, &args.revoke_serial) {
+        (Some(name), _) => RevocationTarget::Name(name.clone()),
+        (None, Some(serial)) => RevocationTarget::Serial(serial.clone()),
+        (None, None) => return Ok(()),
+    };
+
+    let config = ClusterConfig::load_from_file(&args.config).await?;
+
+    let mut logger = ConsoleLogger::new(args.debug);
+    revocation::revoke_and_publish(
+        &target,
+        reason,
+        config.revocation.crl_validity_days,
+        &mut logger,
+    )?;
+
+    Ok(())
+}
+
+// Non-interactive audit: recompute remote hashes without copying anything,
+// record them in the state database and exit.
+async fn run_audit(args: &Args) -> io::Result<()> {
+    let config = ClusterConfig::load_from_file(&args.config).await?;
+    let mut manager = CertManager::new(config, args.debug, Arc::default());
+    manager.load_certificate_status()?;
+
+    let result = manager.audit_distributed_files();
+    for line in &manager.logs {
+        println!("{}", line);
+    }
+    manager.save_certificate_status()?;
+
+    match result? {
+        0 => Ok(()),
+        mismatched => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} distributed files are missing or differ", mismatched),
+        )),
+    }
+}
+
+// Non-interactive kubeadm import: adopt the cluster's PKI, record it in
+// the state database and exit.
+async fn run_kubeadm_import(args: &Args) -> io::Result<()> {
+    let config = ClusterConfig::load_from_file(&args.config).await?;
+    let mut manager = CertManager::new(config, args.debug, Arc::default());
+    manager.load_certificate_status()?;
+
+    let result = manager.import_kubeadm_pki();
+    for line in &manager.logs {
+        println!("{}", line);
+    }
+    manager.save_certificate_status()?;
+
+    match result? {
+        0 => Ok(()),
+        failed => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} imported files failed their checks", failed),
+        )),
+    }
+}
+
+// Non-interactive TLS probe: check what each component serves and exit.
+async fn run_tls_probe(args: &Args) -> io::Result<()> {
+    let config = ClusterConfig::load_from_file(&args.config).await?;
+    let mut manager = CertManager::new(config, args.debug, Arc::default());
+    manager.load_certificate_status()?;
+
+    let failed = manager.probe_endpoints();
+    for line in &manager.logs {
+        println!("{}", line);
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} endpoints are unreachable or failed their checks", failed),
+        )),
+    }
+}
+
+// Non-interactive preflight: check every node is ready for distribution and
+// exit.
+async fn run_preflight(args: &Args) -> io::Result<()> {
+    let config = ClusterConfig::load_from_file(&args.config).await?;
+    let mut manager = CertManager::new(config, args.debug, Arc::default());
+    manager.load_certificate_status()?;
+
+    let hosts = manager.get_all_hosts();
+    let failed = manager.run_preflight(&hosts);
+    for line in &manager.logs {
+        println!("{}", line);
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} nodes failed their preflight checks", failed),
+        )),
+    }
 }
 
 async fn init_with_loading(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     args: &Args,
+    api_token: ApiToken,
 ) -> io::Result<(Arc<RwLock<WebServerState>>, Arc<RwLock<CertManager>>)> {
     let mut loading_state = LoadingState::new();
-    let mut config = ClusterConfig::default();
+    let config;
     // Initialize SSH cache
     let ssh_cache = Arc::new(RwLock::new(discovery::SSHConnectionCache::load()?));
 
-    // Start periodic checking
-    discovery::start_periodic_check(
-        Arc::clone(&ssh_cache),
-        config.remote_user.clone(),
-        config.ssh_key_path.clone(),
-    );
-
     // let mut ssh_cache = discovery::SSHConnectionCache::load()?;
     let mut failed_nodes = Vec::new();
 
@@ -86,10 +236,13 @@ async fn init_with_loading(
     terminal.draw(|f| ui::loading::render_loading(f, &loading_state))?;
     sleep(Duration::from_millis(500));
 
+    // Start periodic checking with the cluster's user, key and host keys
+    discovery::start_periodic_check(Arc::clone(&ssh_cache), config.ssh_options());
+
     // Initialize web server
     loading_state.steps[1].1 = StepStatus::InProgress;
     terminal.draw(|f| ui::loading::render_loading(f, &loading_state))?;
-    let web_state = Arc::new(RwLock::new(WebServerState::new(Some(args.port))));
+    let web_state = Arc::new(RwLock::new(WebServerState::new(Some(args.port), api_token)));
     loading_state.next_step();
     terminal.draw(|f| ui::loading::render_loading(f, &loading_state))?;
 
@@ -107,13 +260,16 @@ async fn init_with_loading(
     // Use the cache in your initialization
     let mut cache = ssh_cache.write().unwrap();
     let mut connection_failed = false;
-    if !discovery::verify_ssh_connection(
-        &config.control_plane,
-        &config.remote_user,
-        &config.ssh_key_path,
-        &mut cache,
-    )
-    .await?
+    // Local and mock transports have no connection to test
+    let uses_ssh = config.transport.is_ssh();
+    let ssh_options = config.ssh_options();
+    if uses_ssh
+        && !discovery::verify_ssh_connection(
+            &config.control_plane,
+            &ssh_options,
+            &mut cache,
+        )
+        .await?
     {
         failed_nodes.push(config.control_plane.clone());
         connection_failed = true;
@@ -121,13 +277,13 @@ async fn init_with_loading(
 
     // Test worker node connections
     for worker in &config.worker_nodes {
-        if !discovery::verify_ssh_connection(
-            worker,
-            &config.remote_user,
-            &config.ssh_key_path,
-            &mut cache,
-        )
-        .await?
+        if uses_ssh
+            && !discovery::verify_ssh_connection(
+                worker,
+                &ssh_options,
+                &mut cache,
+            )
+            .await?
         {
             failed_nodes.push(worker.clone());
             connection_failed = true;
@@ -142,6 +298,38 @@ async fn init_with_loading(
         })?;
     }
 
+    // Nodes and jump hosts seen for the first time are refused until their
+    // key is confirmed. Trusting a jump host can reveal the keys of the nodes
+    // behind it, so ask again until nothing new turns up.
+    if uses_ssh {
+        let known_hosts = KnownHosts::shared(&config.host_keys);
+        let mut declined = Vec::new();
+        loop {
+            let mut trusted = false;
+            for (host, key) in known_hosts.unconfirmed()? {
+                if declined.contains(&host) {
+                    continue;
+                }
+                if confirm_host_key(terminal, &host, &key)? {
+                    known_hosts.trust(&host, &key.fingerprint)?;
+                    trusted = true;
+                } else {
+                    declined.push(host);
+                }
+            }
+            if !trusted {
+                break;
+            }
+
+            for node in failed_nodes.clone() {
+                if discovery::verify_ssh_connection(&node, &ssh_options, &mut cache).await? {
+                    failed_nodes.retain(|failed| *failed != node);
+                }
+            }
+        }
+        connection_failed = !failed_nodes.is_empty();
+    }
+
     if connection_failed {
         loading_state.steps[2].1 = StepStatus::Warning("Some nodes are unreachable".to_string());
         terminal.draw(|f| {
@@ -216,6 +404,33 @@ async fn init_with_loading(
         }
     }
 
+    terminal.draw(|f| ui::loading::render_loading(f, &loading_state))?;
+    sleep(Duration::from_millis(500));
+
+    // Check the nodes beyond reachability; the report stays in the trust
+    // panel and /api/preflight
+    loading_state.steps[4].1 = StepStatus::InProgress;
+    terminal.draw(|f| ui::loading::render_loading(f, &loading_state))?;
+    if uses_ssh {
+        let mut manager = cert_manager.write().unwrap();
+        let hosts = manager.get_all_hosts();
+        let failed = manager.run_preflight(&hosts);
+        let warned = manager
+            .preflight
+            .iter()
+            .filter(|report| report.status == CheckStatus::Warn)
+            .count();
+        loading_state.steps[4].1 = if failed > 0 {
+            StepStatus::Warning(format!("{} nodes fail preflight checks", failed))
+        } else if warned > 0 {
+            StepStatus::Warning(format!("{} nodes have preflight warnings", warned))
+        } else {
+            StepStatus::Complete
+        };
+    } else {
+        loading_state.steps[4].1 = StepStatus::Complete;
+    }
+
     terminal.draw(|f| ui::loading::render_loading(f, &loading_state))?;
     sleep(Duration::from_secs(1));
 
@@ -269,9 +484,25 @@ fn run_onboarding(
                                 .split(',')
                                 .map(|s| s.trim().to_string())
                                 .collect(),
+                            node_hostnames: Default::default(),
                             ssh_key_path: state.fields[2].value.clone(),
                             remote_user: state.fields[3].value.clone(),
+                            ssh: Default::default(),
                             remote_dir: "/etc/kubernetes/pki".to_string(), // Default value
+                            layout: Default::default(),
+                            transport: Default::default(),
+                            host_keys: Default::default(),
+                            distribution: Default::default(),
+                            reload: Default::default(),
+                            permissions: Default::default(),
+                            probe: Default::default(),
+                            preflight: Default::default(),
+                            key_algorithms: KeyAlgorithms::default(),
+                            revocation: RevocationSettings::default(),
+                            subject: SubjectDefaults::default(),
+                            etcd: EtcdSettings::default(),
+                            profiles_file: None,
+                            profiles: ProfileSet::default(),
                         });
                     } else {
                         state.fields[state.current_field].editing = true;
@@ -301,11 +532,57 @@ fn run_onboarding(
     }
 }
 
+/// Asks whether to trust the key `host` presented on first contact.
+fn confirm_host_key(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    host: &str,
+    key: &HostKey,
+) -> io::Result<bool> {
+    loop {
+        terminal.draw(|f| ui::loading::render_host_key_prompt(f, host, key))?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(true),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> io::Result<()> {
     // Parse command line arguments
     let args = Args::parse();
 
+    if args.revoke.is_some() || args.revoke_serial.is_some() {
+        return run_revocation(&args).await;
+    }
+
+    if args.audit {
+        return run_audit(&args).await;
+    }
+
+    if args.import_kubeadm {
+        return run_kubeadm_import(&args).await;
+    }
+
+    if args.probe_tls {
+        return run_tls_probe(&args).await;
+    }
+
+    if args.preflight {
+        return run_preflight(&args).await;
+    }
+
+    // A generated token is printed once, before the TUI takes over the
+    // screen, and never logged
+    let api_token = ApiToken::load();
+    if api_token.generated {
+        println!("API token for the restore and pin endpoints: {}", api_token.value);
+    }
+
     // Terminal initialization after background tasks are spawned
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -314,7 +591,7 @@ async fn main() -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Initialize with loading screen
-    let (web_state, cert_manager) = init_with_loading(&mut terminal, &args).await?;
+    let (web_state, cert_manager) = init_with_loading(&mut terminal, &args, api_token).await?;
 
     // Setup web state with cert manager reference
     {
@@ -329,6 +606,9 @@ async fn main() -> io::Result<()> {
         web::start_web_server(web_state_clone, shutdown_rx).await;
     });
 
+    // Keep published CRLs fresh in the background
+    app::start_crl_refresh(Arc::clone(&cert_manager));
+
     // Run app
     let res = app::run_app(&mut terminal, Arc::clone(&cert_manager)).await;
 
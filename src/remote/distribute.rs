@@ -0,0 +1,1075 @@
+// src/remote/distribute.rs
+//
+// Copies a batch of files to many hosts on background threads. When gated,
+// every host first runs its preflight checks and a single failure aborts the
+// job before anything is uploaded. Each host is then one transaction: its
+// files are staged next to their targets, the files they replace are backed
+// up, and the staged files are renamed into place and checked. A failure
+// before the swap leaves the host untouched; a failure during or after it
+// restores the backup. Committed hosts then run their reload hooks. At most
+// `parallelism` hosts are updated at once, and progress is shared so the TUI
+// and the web API can show it while the job runs.
+use super::{
+    integrity::{self, IntegrityCheck},
+    preflight::{self, CheckStatus, HostPreflight, PreflightNode, PreflightSettings},
+    reload::{ReloadHook, ReloadOutcome, ReloadSettings, ReloadState},
+    transaction::{self, staging_path, BackupManifest, GENERATION_FORMAT},
+    FilePermissions, Transport,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use utoipa::ToSchema;
+
+/// `distribution` in cluster_config.json.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DistributionSettings {
+    /// Hosts updated at once. The files of one host are uploaded one after
+    /// another, as SSH transfers to a host share its session.
+    #[serde(default = "default_parallelism")]
+    pub parallelism: usize,
+    /// Extra attempts after a failed transfer.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Delay before the first retry; doubled for every further attempt.
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// Directory on each host holding one backup per distribution.
+    #[serde(default = "default_backup_dir")]
+    pub backup_dir: String,
+}
+
+fn default_parallelism() -> usize {
+    8
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    500
+}
+
+fn default_backup_dir() -> String {
+    "/var/lib/starquill/backups".to_string()
+}
+
+impl Default for DistributionSettings {
+    fn default() -> Self {
+        Self {
+            parallelism: default_parallelism(),
+            retries: default_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            backup_dir: default_backup_dir(),
+        }
+    }
+}
+
+impl DistributionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.parallelism == 0 {
+            return Err("distribution.parallelism must be at least 1".into());
+        }
+        if !self.backup_dir.starts_with('/') || self.backup_dir.trim_end_matches('/').is_empty() {
+            return Err("distribution.backup_dir must be an absolute path below /".into());
+        }
+        Ok(())
+    }
+}
+
+/// Preflight checks the hosts of a job must pass before it uploads anything.
+#[derive(Debug, Clone)]
+pub struct PreflightGate {
+    pub nodes: Vec<PreflightNode>,
+    pub settings: PreflightSettings,
+}
+
+/// One local file to place on one host.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub cert_type: String,
+    pub host: String,
+    pub source: String,
+    pub remote: String,
+    /// Owner, group and mode the file is installed with.
+    pub permissions: FilePermissions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferState {
+    Pending,
+    Running,
+    Retrying,
+    /// Uploaded next to its target, waiting for the rest of the host.
+    Staged,
+    Placed,
+    Failed,
+    /// Not applied because the transaction of its host was aborted.
+    Skipped,
+    /// Swapped in, then replaced by the backup again.
+    RolledBack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HostState {
+    Pending,
+    /// Running its preflight checks.
+    Checking,
+    Staging,
+    Swapping,
+    Verifying,
+    Committed,
+    /// Committed, running its reload hooks.
+    Reloading,
+    /// Committed, but a reload hook failed or its health gate did not pass.
+    Unhealthy,
+    /// Failed before any file was replaced; the host is unchanged.
+    Aborted,
+    /// Failed after the swap began; the backup was restored.
+    RolledBack,
+    /// The backup could not be restored and the host needs attention.
+    RollbackFailed,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FileProgress {
+    pub cert_type: String,
+    pub source: String,
+    pub remote_path: String,
+    pub state: TransferState,
+    pub attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Result of the post-copy checksum comparison.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<IntegrityCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HostProgress {
+    pub host: String,
+    pub state: HostState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub files: Vec<FileProgress>,
+    /// Reload hooks run after the commit, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reloads: Vec<ReloadOutcome>,
+}
+
+impl HostProgress {
+    pub fn count(&self, state: TransferState) -> usize {
+        self.files.iter().filter(|f| f.state == state).count()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DistributionProgress {
+    /// Name of the backups taken by this distribution.
+    pub generation: String,
+    pub started: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished: Option<String>,
+    pub hosts: Vec<HostProgress>,
+    /// Reports of the preflight checks run before the transfers.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub preflight: Vec<HostPreflight>,
+    /// Messages not yet picked up by the TUI log, flagged when they are
+    /// only of interest in debug mode.
+    #[serde(skip)]
+    messages: Vec<(bool, String)>,
+}
+
+impl DistributionProgress {
+    fn new(transfers: &[Transfer]) -> Self {
+        let mut hosts: Vec<HostProgress> = Vec::new();
+        for transfer in transfers {
+            let file = FileProgress {
+                cert_type: transfer.cert_type.clone(),
+                source: transfer.source.clone(),
+                remote_path: transfer.remote.clone(),
+                state: TransferState::Pending,
+                attempts: 0,
+                error: None,
+                checksum: None,
+            };
+            match hosts.iter_mut().find(|h| h.host == transfer.host) {
+                Some(host) => host.files.push(file),
+                None => hosts.push(HostProgress {
+                    host: transfer.host.clone(),
+                    state: HostState::Pending,
+                    error: None,
+                    files: vec![file],
+                    reloads: Vec::new(),
+                }),
+            }
+        }
+
+        let now = Utc::now();
+        Self {
+            generation: now.format(GENERATION_FORMAT).to_string(),
+            started: now.to_rfc3339(),
+            finished: None,
+            hosts,
+            preflight: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.hosts.iter().map(|h| h.files.len()).sum()
+    }
+
+    pub fn count(&self, state: TransferState) -> usize {
+        self.hosts.iter().map(|h| h.count(state)).sum()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.is_some()
+    }
+
+    fn file_mut(&mut self, transfer: &Transfer) -> Option<&mut FileProgress> {
+        self.hosts
+            .iter_mut()
+            .find(|h| h.host == transfer.host)?
+            .files
+            .iter_mut()
+            .find(|f| f.remote_path == transfer.remote && f.cert_type == transfer.cert_type)
+    }
+
+    fn update(&mut self, transfer: &Transfer, update: impl FnOnce(&mut FileProgress)) {
+        if let Some(file) = self.file_mut(transfer) {
+            update(file);
+        }
+    }
+
+    fn host_mut(&mut self, host: &str) -> Option<&mut HostProgress> {
+        self.hosts.iter_mut().find(|h| h.host == host)
+    }
+}
+
+pub struct DistributionJob {
+    progress: Arc<Mutex<DistributionProgress>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DistributionJob {
+    /// Starts copying `transfers`, after the checks of `gate` if given, and
+    /// returns immediately.
+    pub fn start(
+        transport: Arc<dyn Transport>,
+        transfers: Vec<Transfer>,
+        settings: DistributionSettings,
+        reload: ReloadSettings,
+        gate: Option<PreflightGate>,
+    ) -> Self {
+        let progress = Arc::new(Mutex::new(DistributionProgress::new(&transfers)));
+        let shared = Arc::clone(&progress);
+        let handle =
+            thread::spawn(move || run(transport, transfers, settings, reload, gate, shared));
+
+        Self {
+            progress,
+            handle: Some(handle),
+        }
+    }
+
+    /// A copy of the current progress.
+    pub fn snapshot(&self) -> DistributionProgress {
+        lock(&self.progress).clone()
+    }
+
+    /// Progress messages produced since the last call, as `(debug, message)`.
+    pub fn take_messages(&self) -> Vec<(bool, String)> {
+        std::mem::take(&mut lock(&self.progress).messages)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        lock(&self.progress).is_finished()
+    }
+
+    /// The final progress once the job has finished; `None` while it runs
+    /// and after the result has been taken once.
+    pub fn take_result(&mut self) -> Option<DistributionProgress> {
+        if !self.is_finished() {
+            return None;
+        }
+        let _ = self.handle.take()?.join();
+        Some(self.snapshot())
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn run(
+    transport: Arc<dyn Transport>,
+    transfers: Vec<Transfer>,
+    settings: DistributionSettings,
+    reload: ReloadSettings,
+    gate: Option<PreflightGate>,
+    progress: Arc<Mutex<DistributionProgress>>,
+) {
+    if let Some(gate) = gate {
+        if !passes_preflight(transport.as_ref(), &gate, &progress) {
+            lock(&progress).finished = Some(Utc::now().to_rfc3339());
+            return;
+        }
+    }
+
+    let generation = lock(&progress).generation.clone();
+    let mut hosts: Vec<(String, Vec<Transfer>)> = Vec::new();
+    for transfer in transfers {
+        match hosts.iter_mut().find(|(host, _)| *host == transfer.host) {
+            Some((_, files)) => files.push(transfer),
+            None => hosts.push((transfer.host.clone(), vec![transfer])),
+        }
+    }
+
+    let workers = settings.parallelism.max(1).min(hosts.len().max(1));
+    let queue = Mutex::new(VecDeque::from(hosts));
+    // Set once a reload failed, so hosts that have not reloaded yet skip it
+    let reload_halted = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let Some((host, transfers)) = lock(&queue).pop_front() else {
+                    return;
+                };
+                HostTransaction {
+                    transport: transport.as_ref(),
+                    settings: &settings,
+                    progress: &progress,
+                    reload: &reload,
+                    reload_halted: &reload_halted,
+                    host: &host,
+                    generation: &generation,
+                    transfers: &transfers,
+                }
+                .run();
+            });
+        }
+    });
+
+    lock(&progress).finished = Some(Utc::now().to_rfc3339());
+}
+
+/// Runs the preflight checks of every host. When one fails, every host is
+/// aborted untouched and `false` is returned.
+fn passes_preflight(
+    transport: &dyn Transport,
+    gate: &PreflightGate,
+    progress: &Mutex<DistributionProgress>,
+) -> bool {
+    for host in &mut lock(progress).hosts {
+        host.state = HostState::Checking;
+    }
+    let reports = preflight::preflight_hosts(transport, &gate.nodes, &gate.settings);
+
+    let mut progress = lock(progress);
+    for report in &reports {
+        progress.messages.push((
+            false,
+            format!("Preflight {}: {}", report.host, report.status.as_str()),
+        ));
+        for check in &report.checks {
+            progress.messages.push((
+                check.status == CheckStatus::Pass,
+                format!(
+                    "  {} {}: {}",
+                    check.status.as_str(),
+                    check.name,
+                    check.detail
+                ),
+            ));
+        }
+    }
+    let failed: Vec<&str> = reports
+        .iter()
+        .filter(|report| report.status == CheckStatus::Fail)
+        .map(|report| report.host.as_str())
+        .collect();
+    let error =
+        (!failed.is_empty()).then(|| format!("preflight checks failed on {}", failed.join(", ")));
+
+    for host in &mut progress.hosts {
+        match &error {
+            None => host.state = HostState::Pending,
+            Some(error) => {
+                host.state = HostState::Aborted;
+                host.error = Some(error.clone());
+                for file in &mut host.files {
+                    file.state = TransferState::Skipped;
+                    file.error = Some("transaction aborted".to_string());
+                }
+            }
+        }
+    }
+    if error.is_some() {
+        progress.messages.push((
+            false,
+            "Distribution blocked by failed preflight checks; fix them or set \
+             preflight.gate to false"
+                .to_string(),
+        ));
+    }
+    progress.preflight = reports;
+    error.is_none()
+}
+
+struct HostTransaction<'a> {
+    transport: &'a dyn Transport,
+    settings: &'a DistributionSettings,
+    progress: &'a Mutex<DistributionProgress>,
+    reload: &'a ReloadSettings,
+    reload_halted: &'a AtomicBool,
+    host: &'a str,
+    generation: &'a str,
+    transfers: &'a [Transfer],
+}
+
+impl HostTransaction<'_> {
+    fn run(&self) {
+        self.set_state(HostState::Staging, None);
+        if let Err(e) = self.stage() {
+            // Nothing can be cleaned up on a host we cannot reach
+            if e.kind() != io::ErrorKind::NotConnected {
+                self.discard_staged();
+            }
+            self.abort(&e);
+            return;
+        }
+
+        self.set_state(HostState::Swapping, None);
+        let targets: Vec<String> = self.transfers.iter().map(|t| t.remote.clone()).collect();
+        let manifest = match transaction::back_up(
+            self.transport,
+            self.host,
+            &self.settings.backup_dir,
+            self.generation,
+            &targets,
+        ) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                self.discard_staged();
+                self.abort(&io::Error::new(e.kind(), format!("backup failed: {}", e)));
+                return;
+            }
+        };
+
+        let result = self.swap().and_then(|_| {
+            self.set_state(HostState::Verifying, None);
+            self.verify()
+        });
+        match result {
+            Ok(()) => {
+                self.commit();
+                self.run_reload_hooks();
+            }
+            Err(e) => {
+                self.discard_staged();
+                self.roll_back(&manifest, &e);
+            }
+        }
+    }
+
+    /// Uploads every file next to its target. The first failure stops the
+    /// remaining uploads.
+    fn stage(&self) -> io::Result<()> {
+        for (index, transfer) in self.transfers.iter().enumerate() {
+            let staged = staging_path(&transfer.remote, self.generation, index);
+            place(
+                self.transport,
+                self.settings,
+                transfer,
+                &staged,
+                self.progress,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Renames the staged files over their targets in order, so when two
+    /// transfers share a target the later one wins.
+    fn swap(&self) -> io::Result<()> {
+        for (index, transfer) in self.transfers.iter().enumerate() {
+            self.transport.rename(
+                self.host,
+                &staging_path(&transfer.remote, self.generation, index),
+                &transfer.remote,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Compares the SHA-256 of every target with the local file last swapped
+    /// over it. All targets are checked so each file records its hash; the
+    /// first mismatch fails the transaction.
+    fn verify(&self) -> io::Result<()> {
+        let mut mismatch = None;
+        for (index, transfer) in self.transfers.iter().enumerate() {
+            if self.transfers[index + 1..]
+                .iter()
+                .any(|later| later.remote == transfer.remote)
+            {
+                continue;
+            }
+            let check = integrity::compare_checksums(
+                self.transport,
+                self.host,
+                Path::new(&transfer.source),
+                &transfer.remote,
+            )?;
+            if !check.matches() && mismatch.is_none() {
+                mismatch = Some(check.mismatch(self.host, &transfer.remote));
+            }
+            lock(self.progress).update(transfer, |file| file.checksum = Some(check));
+        }
+        match mismatch {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Best-effort removal of staged files that were not swapped in.
+    fn discard_staged(&self) {
+        for (index, transfer) in self.transfers.iter().enumerate() {
+            let _ = self.transport.remove(
+                self.host,
+                &staging_path(&transfer.remote, self.generation, index),
+            );
+        }
+    }
+
+    fn commit(&self) {
+        let mut progress = lock(self.progress);
+        for transfer in self.transfers {
+            progress.update(transfer, |file| {
+                file.state = TransferState::Placed;
+                file.error = None;
+            });
+        }
+        if let Some(host) = progress.host_mut(self.host) {
+            host.state = HostState::Committed;
+        }
+        progress.messages.push((
+            false,
+            format!(
+                "Committed {} files on {} (backup {})",
+                self.transfers.len(),
+                self.host,
+                self.generation
+            ),
+        ));
+    }
+
+    /// Runs the hooks triggered by the committed files. A failed hook skips
+    /// the rest on this host and every hook not yet started on other hosts,
+    /// so a change that breaks a component does not spread.
+    fn run_reload_hooks(&self) {
+        let hooks: Vec<(&ReloadHook, Vec<String>)> = self
+            .reload
+            .hooks
+            .iter()
+            .filter_map(|hook| {
+                let mut cert_types: Vec<String> = hook
+                    .triggers(self.transfers)
+                    .into_iter()
+                    .map(|transfer| transfer.cert_type.clone())
+                    .collect();
+                cert_types.sort();
+                cert_types.dedup();
+                (!cert_types.is_empty()).then_some((hook, cert_types))
+            })
+            .collect();
+        if hooks.is_empty() {
+            return;
+        }
+
+        self.set_state(HostState::Reloading, None);
+        let mut skip_reason = None;
+        let mut failure = None;
+        for (hook, cert_types) in hooks {
+            if skip_reason.is_none() && self.reload_halted.load(Ordering::SeqCst) {
+                skip_reason = Some("a reload failed on another host".to_string());
+            }
+            let started = Instant::now();
+            let (state, error) = match &skip_reason {
+                Some(reason) => (ReloadState::Skipped, Some(reason.clone())),
+                None => match hook.run(self.transport, self.host) {
+                    Ok(()) => (ReloadState::Healthy, None),
+                    Err(e) => {
+                        self.reload_halted.store(true, Ordering::SeqCst);
+                        skip_reason = Some(format!("the reload of {} failed", hook.component));
+                        let state = if e.kind() == io::ErrorKind::TimedOut {
+                            ReloadState::Unhealthy
+                        } else {
+                            ReloadState::Failed
+                        };
+                        failure.get_or_insert(format!("{}: {}", hook.component, e));
+                        (state, Some(e.to_string()))
+                    }
+                },
+            };
+
+            let mut progress = lock(self.progress);
+            progress.messages.push((
+                false,
+                match (state, &error) {
+                    (ReloadState::Healthy, _) => {
+                        format!("Reloaded {} on {}", hook.component, self.host)
+                    }
+                    (ReloadState::Skipped, Some(reason)) => format!(
+                        "Skipped reloading {} on {}: {}",
+                        hook.component, self.host, reason
+                    ),
+                    (_, error) => format!(
+                        "Reload of {} on {} failed: {}",
+                        hook.component,
+                        self.host,
+                        error.as_deref().unwrap_or_default()
+                    ),
+                },
+            ));
+            if let Some(host) = progress.host_mut(self.host) {
+                host.reloads.push(ReloadOutcome {
+                    component: hook.component.clone(),
+                    state,
+                    cert_types,
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                    error,
+                });
+            }
+        }
+
+        match failure {
+            Some(error) => self.set_state(HostState::Unhealthy, Some(error)),
+            None => self.set_state(HostState::Committed, None),
+        }
+    }
+
+    fn abort(&self, error: &io::Error) {
+        let mut progress = lock(self.progress);
+        for transfer in self.transfers {
+            progress.update(transfer, |file| {
+                if file.state != TransferState::Failed {
+                    file.state = TransferState::Skipped;
+                    file.error = Some("transaction aborted".to_string());
+                }
+            });
+        }
+        if let Some(host) = progress.host_mut(self.host) {
+            host.state = HostState::Aborted;
+            host.error = Some(error.to_string());
+        }
+        progress.messages.push((
+            false,
+            format!(
+                "Aborted distribution to {}, no files were changed: {}",
+                self.host, error
+            ),
+        ));
+    }
+
+    fn roll_back(&self, manifest: &BackupManifest, error: &io::Error) {
+        let restored = transaction::restore(self.transport, &self.settings.backup_dir, manifest);
+
+        let mut progress = lock(self.progress);
+        let (host_state, file_state, message) = match &restored {
+            Ok(()) => (
+                HostState::RolledBack,
+                TransferState::RolledBack,
+                format!(
+                    "Rolled back {} to its previous files after: {}",
+                    self.host, error
+                ),
+            ),
+            Err(e) => (
+                HostState::RollbackFailed,
+                TransferState::Failed,
+                format!(
+                    "Rollback of {} failed, restore backup {} manually: {} (after: {})",
+                    self.host, self.generation, e, error
+                ),
+            ),
+        };
+        for transfer in self.transfers {
+            progress.update(transfer, |file| {
+                file.state = file_state;
+                file.error = Some(error.to_string());
+            });
+        }
+        if let Some(host) = progress.host_mut(self.host) {
+            host.state = host_state;
+            host.error = Some(match &restored {
+                Ok(()) => error.to_string(),
+                Err(e) => format!("{}; rollback failed: {}", error, e),
+            });
+        }
+        progress.messages.push((false, message));
+    }
+
+    fn set_state(&self, state: HostState, error: Option<String>) {
+        if let Some(host) = lock(self.progress).host_mut(self.host) {
+            host.state = state;
+            host.error = error;
+        }
+    }
+}
+
+/// Uploads one file to `staged`, retrying transient failures with
+/// exponential backoff.
+fn place(
+    transport: &dyn Transport,
+    settings: &DistributionSettings,
+    transfer: &Transfer,
+    staged: &str,
+    progress: &Mutex<DistributionProgress>,
+) -> io::Result<()> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        lock(progress).update(transfer, |file| {
+            file.state = TransferState::Running;
+            file.attempts = attempt;
+        });
+
+        let result = transport.put(
+            &transfer.host,
+            Path::new(&transfer.source),
+            staged,
+            &transfer.permissions,
+        );
+        let mut progress = lock(progress);
+        match result {
+            Ok(()) => {
+                progress.update(transfer, |file| {
+                    file.state = TransferState::Staged;
+                    file.error = None;
+                });
+                progress.messages.push((
+                    true,
+                    format!(
+                        "Staged {} on {}:{}",
+                        transfer.cert_type, transfer.host, staged
+                    ),
+                ));
+                return Ok(());
+            }
+            Err(e) if attempt <= settings.retries && is_transient(&e) => {
+                progress.update(transfer, |file| {
+                    file.state = TransferState::Retrying;
+                    file.error = Some(e.to_string());
+                });
+                progress.messages.push((
+                    false,
+                    format!(
+                        "Retrying {} on {} after attempt {}: {}",
+                        transfer.cert_type, transfer.host, attempt, e
+                    ),
+                ));
+                drop(progress);
+                let backoff = settings
+                    .retry_backoff_ms
+                    .saturating_mul(1 << (attempt - 1).min(16));
+                thread::sleep(Duration::from_millis(backoff));
+            }
+            Err(e) => {
+                progress.update(transfer, |file| {
+                    file.state = TransferState::Failed;
+                    file.error = Some(e.to_string());
+                });
+                progress.messages.push((
+                    false,
+                    format!(
+                        "Failed to stage {} on {}: {}",
+                        transfer.cert_type, transfer.host, e
+                    ),
+                ));
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Failures that will not go away by trying again.
+fn is_transient(error: &io::Error) -> bool {
+    !matches!(
+        error.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::InvalidData
+            | io::ErrorKind::Unsupported
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::{reload::ReloadAction, MockTransport};
+    use std::{fs, path::PathBuf};
+    use uuid::Uuid;
+
+    const CA: &str = "/etc/kubernetes/pki/ca.crt";
+    const APISERVER: &str = "/etc/kubernetes/pki/apiserver.crt";
+
+    /// Local files to distribute, removed again on drop.
+    struct Sources(PathBuf);
+
+    impl Sources {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("starquill-test-{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn transfer(&self, host: &str, remote: &str, contents: &str) -> Transfer {
+            let source = self.0.join(Uuid::new_v4().to_string());
+            fs::write(&source, contents).unwrap();
+            Transfer {
+                cert_type: Path::new(remote)
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string(),
+                host: host.to_string(),
+                source: source.to_string_lossy().to_string(),
+                remote: remote.to_string(),
+                permissions: FilePermissions::new("root", "root", 0o644),
+            }
+        }
+    }
+
+    impl Drop for Sources {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Runs a job one host at a time and waits for its result.
+    fn distribute(
+        transport: &Arc<MockTransport>,
+        transfers: Vec<Transfer>,
+        reload: ReloadSettings,
+        gate: Option<PreflightGate>,
+    ) -> DistributionProgress {
+        let settings = DistributionSettings {
+            parallelism: 1,
+            retry_backoff_ms: 1,
+            ..Default::default()
+        };
+        let mut job = DistributionJob::start(transport.clone(), transfers, settings, reload, gate);
+        loop {
+            if let Some(result) = job.take_result() {
+                return result;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn states(host: &HostProgress) -> Vec<TransferState> {
+        host.files.iter().map(|file| file.state).collect()
+    }
+
+    #[test]
+    fn committed_hosts_keep_a_backup_of_the_replaced_files() {
+        let transport = Arc::new(MockTransport::new());
+        let sources = Sources::new();
+        transport.write("10.0.0.1", CA, "old ca");
+
+        let result = distribute(
+            &transport,
+            vec![
+                sources.transfer("10.0.0.1", CA, "new ca"),
+                sources.transfer("10.0.0.1", APISERVER, "new apiserver"),
+            ],
+            ReloadSettings::default(),
+            None,
+        );
+
+        let host = &result.hosts[0];
+        assert_eq!(host.state, HostState::Committed);
+        assert_eq!(states(host), [TransferState::Placed; 2]);
+        assert_eq!(transport.read("10.0.0.1", CA).as_deref(), Some("new ca"));
+        // Staged files were all renamed into place
+        assert_eq!(
+            transport.list("10.0.0.1", "/etc/kubernetes/pki").unwrap(),
+            ["apiserver.crt", "ca.crt"]
+        );
+
+        let settings = DistributionSettings::default();
+        let backups =
+            transaction::list_backups(transport.as_ref(), "10.0.0.1", &settings.backup_dir)
+                .unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].generation, result.generation);
+        let existed: Vec<bool> = backups[0].entries.iter().map(|e| e.existed).collect();
+        assert_eq!(existed, [true, false]);
+    }
+
+    #[test]
+    fn a_failed_swap_restores_the_replaced_files() {
+        let transport = Arc::new(MockTransport::new());
+        let sources = Sources::new();
+        transport.write("10.0.0.1", CA, "old ca");
+        // The CA is swapped in before the apiserver certificate fails
+        transport.fail_next_write("10.0.0.1", APISERVER);
+
+        let result = distribute(
+            &transport,
+            vec![
+                sources.transfer("10.0.0.1", CA, "new ca"),
+                sources.transfer("10.0.0.1", APISERVER, "new apiserver"),
+            ],
+            ReloadSettings::default(),
+            None,
+        );
+
+        let host = &result.hosts[0];
+        assert_eq!(host.state, HostState::RolledBack);
+        assert_eq!(states(host), [TransferState::RolledBack; 2]);
+        assert_eq!(transport.read("10.0.0.1", CA).as_deref(), Some("old ca"));
+        assert_eq!(transport.read("10.0.0.1", APISERVER), None);
+        assert_eq!(
+            transport.list("10.0.0.1", "/etc/kubernetes/pki").unwrap(),
+            ["ca.crt"]
+        );
+    }
+
+    #[test]
+    fn unreachable_hosts_are_aborted_without_holding_up_others() {
+        let transport = Arc::new(MockTransport::new());
+        let sources = Sources::new();
+        transport.set_unreachable("10.0.0.1");
+
+        let result = distribute(
+            &transport,
+            vec![
+                sources.transfer("10.0.0.1", CA, "new ca"),
+                sources.transfer("10.0.0.2", CA, "new ca"),
+            ],
+            ReloadSettings::default(),
+            None,
+        );
+
+        assert_eq!(result.hosts[0].state, HostState::Aborted);
+        assert_eq!(result.hosts[0].files[0].attempts, 3);
+        assert_eq!(result.hosts[1].state, HostState::Committed);
+        assert_eq!(transport.read("10.0.0.2", CA).as_deref(), Some("new ca"));
+    }
+
+    #[test]
+    fn a_failed_preflight_check_aborts_every_host_untouched() {
+        let transport = Arc::new(MockTransport::new());
+        let sources = Sources::new();
+        transport.write("10.0.0.1", CA, "old ca");
+        // The first node is ready, the second cannot be reached
+        transport.respond("10.0.0.1", "true", "");
+        transport.respond("10.0.0.1", "sudo -n true", "");
+        transport.respond(
+            "10.0.0.1",
+            "date -u +%s",
+            &Utc::now().timestamp().to_string(),
+        );
+        transport.respond(
+            "10.0.0.1",
+            "kubelet --version 2>/dev/null || kubeadm version -o short 2>/dev/null || true",
+            "Kubernetes v1.31.2\n",
+        );
+        transport.set_unreachable("10.0.0.2");
+        let node = |host: &str| PreflightNode {
+            host: host.to_string(),
+            directories: Vec::new(),
+            services: Vec::new(),
+        };
+        let gate = PreflightGate {
+            nodes: vec![node("10.0.0.1"), node("10.0.0.2")],
+            settings: PreflightSettings::default(),
+        };
+
+        let result = distribute(
+            &transport,
+            vec![
+                sources.transfer("10.0.0.1", CA, "new ca"),
+                sources.transfer("10.0.0.2", CA, "new ca"),
+            ],
+            ReloadSettings::default(),
+            Some(gate),
+        );
+
+        assert_eq!(result.preflight.len(), 2);
+        assert_eq!(result.preflight[0].status, CheckStatus::Pass);
+        assert_eq!(result.preflight[1].status, CheckStatus::Fail);
+        for host in &result.hosts {
+            assert_eq!(host.state, HostState::Aborted);
+            assert_eq!(
+                host.error.as_deref(),
+                Some("preflight checks failed on 10.0.0.2")
+            );
+            assert_eq!(states(host), [TransferState::Skipped]);
+        }
+        assert_eq!(transport.read("10.0.0.1", CA).as_deref(), Some("old ca"));
+    }
+
+    #[test]
+    fn a_failed_reload_skips_the_hooks_not_started_yet() {
+        let transport = Arc::new(MockTransport::new());
+        let sources = Sources::new();
+        let restart = |unit: &str| ReloadHook {
+            component: unit.to_string(),
+            paths: Vec::new(),
+            action: ReloadAction::RestartUnit {
+                unit: unit.to_string(),
+            },
+            health: None,
+            timeout_secs: 1,
+        };
+        let reload = ReloadSettings {
+            hooks: vec![restart("kubelet"), restart("containerd")],
+        };
+        // Only the second host can restart the kubelet
+        transport.respond("10.0.0.2", "sudo systemctl restart kubelet", "");
+
+        let result = distribute(
+            &transport,
+            vec![
+                sources.transfer("10.0.0.1", CA, "new ca"),
+                sources.transfer("10.0.0.2", CA, "new ca"),
+            ],
+            reload,
+            None,
+        );
+
+        let reloads = |host: &HostProgress| -> Vec<ReloadState> {
+            host.reloads.iter().map(|outcome| outcome.state).collect()
+        };
+        assert_eq!(result.hosts[0].state, HostState::Unhealthy);
+        assert_eq!(
+            reloads(&result.hosts[0]),
+            [ReloadState::Failed, ReloadState::Skipped]
+        );
+        // The committed files stay in place on both hosts
+        assert_eq!(result.hosts[1].state, HostState::Committed);
+        assert_eq!(
+            reloads(&result.hosts[1]),
+            [ReloadState::Skipped, ReloadState::Skipped]
+        );
+        assert_eq!(result.count(TransferState::Placed), 2);
+    }
+}
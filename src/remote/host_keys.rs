@@ -0,0 +1,341 @@
+// src/remote/host_keys.rs
+//
+// SSH host keys of the cluster's nodes. A key is pinned either in
+// cluster_config.json (`host_keys.pinned`) or in the cluster's known-hosts
+// store, which records the keys the user confirmed on first use. A node that
+// presents any other key is refused; the key it presented is kept so it can
+// be confirmed or re-pinned.
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+};
+use utoipa::ToSchema;
+
+/// `host_keys` in cluster_config.json.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HostKeySettings {
+    /// Known-hosts store of this cluster, written when a key is confirmed
+    /// or re-pinned.
+    #[serde(default = "default_known_hosts_file")]
+    pub known_hosts_file: String,
+    /// `SHA256:` fingerprints per host. They take precedence over the store
+    /// and can only be changed here.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pinned: BTreeMap<String, String>,
+}
+
+fn default_known_hosts_file() -> String {
+    "known_hosts.json".to_string()
+}
+
+impl Default for HostKeySettings {
+    fn default() -> Self {
+        Self {
+            known_hosts_file: default_known_hosts_file(),
+            pinned: BTreeMap::new(),
+        }
+    }
+}
+
+impl HostKeySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.known_hosts_file.is_empty() {
+            return Err("host_keys.known_hosts_file must not be empty".into());
+        }
+        for (host, fingerprint) in &self.pinned {
+            if !is_fingerprint(fingerprint) {
+                return Err(format!(
+                    "host_keys.pinned: '{}' for {} is not a SHA256: fingerprint",
+                    fingerprint, host
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_fingerprint(fingerprint: &str) -> bool {
+    fingerprint.strip_prefix("SHA256:").is_some_and(|hash| {
+        !hash.is_empty()
+            && hash
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+    })
+}
+
+/// A host key as `ssh-keygen -lf` shows it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct HostKey {
+    /// Algorithm, e.g. `ssh-ed25519`.
+    pub key_type: String,
+    /// `SHA256:` followed by the unpadded base64 hash of the key.
+    pub fingerprint: String,
+    /// When the key was presented or confirmed.
+    pub seen: String,
+}
+
+impl HostKey {
+    pub fn new(key_type: &str, fingerprint: String) -> Self {
+        Self {
+            key_type: key_type.to_string(),
+            fingerprint,
+            seen: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Result of comparing a presented key with the pinned one.
+pub enum HostKeyCheck {
+    Trusted,
+    /// Nothing is pinned for the host yet.
+    Unknown,
+    /// The host presented a different key than `expected`.
+    Changed {
+        expected: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HostKeyState {
+    /// Pinned in cluster_config.json.
+    Pinned,
+    /// Confirmed on first use or re-pinned, kept in the known-hosts store.
+    Trusted,
+    /// Never confirmed; connections are refused until it is.
+    Unknown,
+    /// The host presented a key other than the pinned one.
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HostKeyStatus {
+    pub host: String,
+    pub state: HostKeyState,
+    /// Pinned fingerprint, if any.
+    pub expected: Option<String>,
+    /// Key the host presented when it was last refused.
+    pub presented: Option<HostKey>,
+}
+
+#[derive(Default)]
+struct Store {
+    /// Contents of the known-hosts file, read on first use.
+    trusted: Option<BTreeMap<String, HostKey>>,
+    /// Keys that were refused, by host.
+    refused: HashMap<String, HostKey>,
+}
+
+type StoreRegistry = Mutex<HashMap<String, Arc<KnownHosts>>>;
+
+pub struct KnownHosts {
+    path: PathBuf,
+    pinned: Mutex<BTreeMap<String, String>>,
+    store: Mutex<Store>,
+}
+
+impl KnownHosts {
+    /// The process-wide store for the known-hosts file of `settings`, so the
+    /// SSH pool, the TUI and the API see the same refused keys. Its pinned
+    /// keys are replaced by those of `settings`, so a changed configuration
+    /// takes effect.
+    pub fn shared(settings: &HostKeySettings) -> Arc<Self> {
+        static STORES: OnceLock<StoreRegistry> = OnceLock::new();
+
+        let known_hosts = lock(STORES.get_or_init(Default::default))
+            .entry(settings.known_hosts_file.clone())
+            .or_insert_with(|| {
+                let path = shellexpand::tilde(&settings.known_hosts_file).to_string();
+                Arc::new(Self {
+                    path: PathBuf::from(path),
+                    pinned: Mutex::default(),
+                    store: Mutex::default(),
+                })
+            })
+            .clone();
+        *lock(&known_hosts.pinned) = settings.pinned.clone();
+        known_hosts
+    }
+
+    /// Compares the key `host` presented with the one pinned for it. Keys
+    /// that do not match are remembered until they are confirmed.
+    pub fn check(&self, host: &str, presented: &HostKey) -> io::Result<HostKeyCheck> {
+        let mut store = lock(&self.store);
+        let pinned = lock(&self.pinned).get(host).cloned();
+        let expected = match pinned {
+            Some(fingerprint) => Some(fingerprint),
+            None => self
+                .trusted(&mut store)?
+                .get(host)
+                .map(|key| key.fingerprint.clone()),
+        };
+
+        let check = match expected {
+            Some(expected) if expected == presented.fingerprint => {
+                store.refused.remove(host);
+                return Ok(HostKeyCheck::Trusted);
+            }
+            Some(expected) => HostKeyCheck::Changed { expected },
+            None => HostKeyCheck::Unknown,
+        };
+        store.refused.insert(host.to_string(), presented.clone());
+        Ok(check)
+    }
+
+    /// Pins the key `host` presented when it was last refused and saves the
+    /// store. `fingerprint`, confirmed by the operator, must be the one
+    /// presented, so a key that changed again in the meantime is not pinned
+    /// unseen.
+    pub fn trust(&self, host: &str, fingerprint: &str) -> io::Result<HostKey> {
+        if lock(&self.pinned).contains_key(host) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "The host key of {} is pinned in cluster_config.json; \
+                     change host_keys.pinned there",
+                    host
+                ),
+            ));
+        }
+
+        let mut store = lock(&self.store);
+        let key = store.refused.get(host).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} has not presented a key that needs confirming", host),
+            )
+        })?;
+        if fingerprint != key.fingerprint {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} presented {}, not {}",
+                    host, key.fingerprint, fingerprint
+                ),
+            ));
+        }
+
+        let mut trusted = self.trusted(&mut store)?.clone();
+        trusted.insert(host.to_string(), key.clone());
+        let contents = serde_json::to_string_pretty(&trusted)?;
+        fs::write(&self.path, contents)?;
+        store.trusted = Some(trusted);
+        store.refused.remove(host);
+        Ok(key)
+    }
+
+    /// Hosts whose key was refused because it was never confirmed.
+    pub fn unconfirmed(&self) -> io::Result<Vec<(String, HostKey)>> {
+        let mut store = lock(&self.store);
+        let refused: Vec<(String, HostKey)> = store
+            .refused
+            .iter()
+            .map(|(host, key)| (host.clone(), key.clone()))
+            .collect();
+        let trusted = self.trusted(&mut store)?;
+        let pinned = lock(&self.pinned);
+        let mut unconfirmed: Vec<(String, HostKey)> = refused
+            .into_iter()
+            .filter(|(host, _)| !pinned.contains_key(host) && !trusted.contains_key(host))
+            .collect();
+        unconfirmed.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(unconfirmed)
+    }
+
+    /// State of every host in `hosts` and of every host with a stored key.
+    pub fn statuses(&self, hosts: &[String]) -> io::Result<Vec<HostKeyStatus>> {
+        let mut store = lock(&self.store);
+        let refused = store.refused.clone();
+        let trusted = self.trusted(&mut store)?;
+        let pinned = lock(&self.pinned);
+
+        let mut names: Vec<&String> = hosts
+            .iter()
+            .chain(pinned.keys())
+            .chain(trusted.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        Ok(names
+            .into_iter()
+            .map(|host| {
+                let expected = pinned
+                    .get(host)
+                    .or_else(|| trusted.get(host).map(|key| &key.fingerprint))
+                    .cloned();
+                let presented = refused.get(host).cloned();
+                let state = match (&expected, &presented) {
+                    (Some(_), Some(_)) => HostKeyState::Changed,
+                    (None, _) => HostKeyState::Unknown,
+                    (Some(_), None) if pinned.contains_key(host) => HostKeyState::Pinned,
+                    (Some(_), None) => HostKeyState::Trusted,
+                };
+                HostKeyStatus {
+                    host: host.clone(),
+                    state,
+                    expected,
+                    presented,
+                }
+            })
+            .collect())
+    }
+
+    fn trusted<'a>(&self, store: &'a mut Store) -> io::Result<&'a BTreeMap<String, HostKey>> {
+        if store.trusted.is_none() {
+            let trusted = match fs::read_to_string(&self.path) {
+                Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Failed to parse {}: {}", self.path.display(), e),
+                    )
+                })?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+                Err(e) => return Err(e),
+            };
+            store.trusted = Some(trusted);
+        }
+        Ok(store.trusted.get_or_insert_with(BTreeMap::new))
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_stores_follow_the_latest_pinned_keys() {
+        let settings = |fingerprint: &str| HostKeySettings {
+            known_hosts_file: std::env::temp_dir()
+                .join("starquill-test-known-hosts.json")
+                .to_string_lossy()
+                .to_string(),
+            pinned: BTreeMap::from([("10.0.0.1".to_string(), fingerprint.to_string())]),
+        };
+        let key = |fingerprint: &str| HostKey::new("ssh-ed25519", fingerprint.to_string());
+
+        let known_hosts = KnownHosts::shared(&settings("SHA256:old"));
+        assert!(matches!(
+            known_hosts.check("10.0.0.1", &key("SHA256:old")).unwrap(),
+            HostKeyCheck::Trusted
+        ));
+
+        let known_hosts = KnownHosts::shared(&settings("SHA256:new"));
+        assert!(matches!(
+            known_hosts.check("10.0.0.1", &key("SHA256:new")).unwrap(),
+            HostKeyCheck::Trusted
+        ));
+        assert!(matches!(
+            known_hosts.check("10.0.0.1", &key("SHA256:old")).unwrap(),
+            HostKeyCheck::Changed { .. }
+        ));
+    }
+}
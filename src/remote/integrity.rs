@@ -0,0 +1,48 @@
+// src/remote/integrity.rs
+//
+// Compares local files with their copies on the hosts by SHA-256. Used as the
+// post-check of every copy and by the standalone audit, which only reads.
+use super::{sha256_hex, Transport};
+use serde::Serialize;
+use std::{fs, io, path::Path};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct IntegrityCheck {
+    /// SHA-256 of the local file.
+    pub expected: String,
+    /// SHA-256 found on the host, `None` when the file is missing.
+    pub actual: Option<String>,
+}
+
+impl IntegrityCheck {
+    pub fn matches(&self) -> bool {
+        self.actual.as_deref() == Some(self.expected.as_str())
+    }
+
+    /// Error describing a failed check of `remote` on `host`.
+    pub fn mismatch(&self, host: &str, remote: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "checksum mismatch for {} on {}: expected {}, found {}",
+                remote,
+                host,
+                self.expected,
+                self.actual.as_deref().unwrap_or("no file")
+            ),
+        )
+    }
+}
+
+/// Hashes `local` and the file at `remote` on `host`.
+pub fn compare_checksums(
+    transport: &dyn Transport,
+    host: &str,
+    local: &Path,
+    remote: &str,
+) -> io::Result<IntegrityCheck> {
+    let expected = sha256_hex(&fs::read(local)?);
+    let actual = transport.checksum(host, remote)?;
+    Ok(IntegrityCheck { expected, actual })
+}
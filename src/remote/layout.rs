@@ -0,0 +1,439 @@
+// src/remote/layout.rs
+//
+// Where each tracked file is installed on a node. A layout is an ordered list
+// of rules matching the local path of a file, optionally only on nodes of
+// some roles; the first rule that matches gives the remote path. Rules from
+// cluster_config.json are tried before those of the chosen profile, and files
+// no rule matches go to `<remote_dir>/<file name>`. Distribution, the audit,
+// remote verification and trust discovery all resolve paths here.
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Role of a node, as far as the layout is concerned.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    ControlPlane,
+    /// An etcd member that is not the control plane.
+    Etcd,
+    Worker,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutProfile {
+    /// `/etc/kubernetes/pki` with `etcd/` and `front-proxy-*`, kubelet
+    /// certificates in `/var/lib/kubelet/pki` and kubeconfigs in
+    /// `/etc/kubernetes`.
+    #[default]
+    Kubeadm,
+    /// Kubernetes the Hard Way: `/var/lib/kubernetes` on the control plane,
+    /// `/var/lib/kubelet` and `/var/lib/kube-proxy` on the workers and
+    /// `/etc/etcd` for etcd.
+    HardWay,
+    /// Only the rules from the config.
+    Custom,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LayoutRule {
+    /// Glob over the local path, e.g. `certs/etcd/*/server.crt`. `*` stays
+    /// within one directory, `**` spans any number.
+    pub source: String,
+    /// Remote path, with `{remote_dir}`, `{file}` (file name) and `{stem}`
+    /// (file name without extension) filled in.
+    pub target: String,
+    /// Roles the rule applies to; empty applies it on every node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<NodeRole>,
+}
+
+/// `layout` in cluster_config.json.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LayoutSettings {
+    #[serde(default)]
+    pub profile: LayoutProfile,
+    /// Rules tried before those of the profile.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<LayoutRule>,
+}
+
+type Rule = (&'static str, &'static str, &'static [NodeRole]);
+
+const KUBEADM: &[Rule] = &[
+    (
+        "kubeconfig/*/kubelet.conf",
+        "/etc/kubernetes/kubelet.conf",
+        &[],
+    ),
+    ("kubeconfig/*.conf", "/etc/kubernetes/{file}", &[]),
+    ("certs/nodes/*/kubelet.*", "/var/lib/kubelet/pki/{file}", &[]),
+    (
+        "certs/nodes/*/kubelet-client.*",
+        "/var/lib/kubelet/pki/{file}",
+        &[],
+    ),
+    ("certs/etcd/**", "{remote_dir}/etcd/{file}", &[]),
+    ("certs/etcd-ca/ca.key", "{remote_dir}/etcd/ca.key", &[]),
+    // kubeadm's names for the API server's own certificates
+    (
+        "certs/kube-apiserver/kube-apiserver.crt",
+        "{remote_dir}/apiserver.crt",
+        &[],
+    ),
+    (
+        "certs/kube-apiserver/kube-apiserver.key",
+        "{remote_dir}/apiserver.key",
+        &[],
+    ),
+    (
+        "certs/kube-apiserver-kubelet-client/kube-apiserver-kubelet-client.crt",
+        "{remote_dir}/apiserver-kubelet-client.crt",
+        &[],
+    ),
+    (
+        "certs/kube-apiserver-kubelet-client/kube-apiserver-kubelet-client.key",
+        "{remote_dir}/apiserver-kubelet-client.key",
+        &[],
+    ),
+    (
+        "certs/front-proxy-ca/*",
+        "{remote_dir}/front-proxy-{file}",
+        &[],
+    ),
+    // kubeadm's ca.crt is the Kubernetes CA
+    ("certs/root-ca/ca.crt", "{remote_dir}/root-ca.crt", &[]),
+];
+
+const HARD_WAY: &[Rule] = &[
+    ("certs/etcd/**", "/etc/etcd/{file}", &[]),
+    ("certs/nodes/*/kubelet.*", "/var/lib/kubelet/{file}", &[]),
+    ("certs/nodes/*/kubelet-client.*", "/var/lib/kubelet/{file}", &[]),
+    (
+        "kubeconfig/*/kubelet.conf",
+        "/var/lib/kubelet/kubeconfig",
+        &[],
+    ),
+    (
+        "kubeconfig/kube-proxy.conf",
+        "/var/lib/kube-proxy/kubeconfig",
+        &[],
+    ),
+    ("certs/kube-proxy/*", "/var/lib/kube-proxy/{file}", &[]),
+    (
+        "certs/front-proxy-ca/*",
+        "/var/lib/kubernetes/front-proxy-{file}",
+        &[],
+    ),
+    (
+        "certs/root-ca/ca.crt",
+        "/var/lib/kubernetes/root-ca.crt",
+        &[],
+    ),
+    (
+        "kubeconfig/*.conf",
+        "/var/lib/kubernetes/{stem}.kubeconfig",
+        &[NodeRole::ControlPlane],
+    ),
+    (
+        "**",
+        "/var/lib/kubernetes/{file}",
+        &[NodeRole::ControlPlane],
+    ),
+    ("**", "/var/lib/kubelet/{file}", &[NodeRole::Worker]),
+];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+impl LayoutSettings {
+    /// Targets end up in shell commands on the hosts, so only plain paths
+    /// are accepted.
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            Pattern::new(&rule.source)
+                .map_err(|e| format!("layout.rules: invalid source '{}': {}", rule.source, e))?;
+            let target = fill(&rule.target, "/etc/kubernetes/pki", "file.crt");
+            let valid = target.starts_with('/')
+                && target
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "/._-".contains(c));
+            if !valid {
+                return Err(format!(
+                    "layout.rules: '{}' is not a plain absolute path",
+                    rule.target
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fill(target: &str, remote_dir: &str, file: &str) -> String {
+    let stem = Path::new(file)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file);
+    target
+        .replace("{remote_dir}", remote_dir.trim_end_matches('/'))
+        .replace("{file}", file)
+        .replace("{stem}", stem)
+}
+
+/// The layout of one cluster, resolving local files to remote paths.
+#[derive(Debug, Clone)]
+pub struct RemoteLayout {
+    rules: Vec<(Pattern, LayoutRule)>,
+    remote_dir: String,
+    control_plane: String,
+    etcd_members: Vec<String>,
+}
+
+impl RemoteLayout {
+    pub fn new(
+        settings: &LayoutSettings,
+        remote_dir: &str,
+        control_plane: &str,
+        etcd_members: Vec<String>,
+    ) -> Self {
+        let profile: &[Rule] = match settings.profile {
+            LayoutProfile::Kubeadm => KUBEADM,
+            LayoutProfile::HardWay => HARD_WAY,
+            LayoutProfile::Custom => &[],
+        };
+        let builtin = profile.iter().map(|(source, target, roles)| LayoutRule {
+            source: source.to_string(),
+            target: target.to_string(),
+            roles: roles.to_vec(),
+        });
+        // Sources were checked by validate()
+        let rules = settings
+            .rules
+            .iter()
+            .cloned()
+            .chain(builtin)
+            .filter_map(|rule| Some((Pattern::new(&rule.source).ok()?, rule)))
+            .collect();
+
+        Self {
+            rules,
+            remote_dir: remote_dir.to_string(),
+            control_plane: control_plane.to_string(),
+            etcd_members,
+        }
+    }
+
+    pub fn role(&self, host: &str) -> NodeRole {
+        if host == self.control_plane {
+            NodeRole::ControlPlane
+        } else if self.etcd_members.iter().any(|member| member == host) {
+            NodeRole::Etcd
+        } else {
+            NodeRole::Worker
+        }
+    }
+
+    /// The local path of a tracked file. Files generated at the top level,
+    /// like the encryption config, stay there; anything else given relative
+    /// to `certs/` is moved below it.
+    pub fn source(&self, cert_path: &str) -> String {
+        let top_level = !cert_path.contains('/') && Path::new(cert_path).extension().is_some();
+        if cert_path.starts_with("certs/") || cert_path.starts_with("kubeconfig/") || top_level {
+            cert_path.to_string()
+        } else {
+            format!("certs/{}", cert_path)
+        }
+    }
+
+    /// Where the file at `source` is installed on `host`.
+    pub fn target(&self, source: &str, host: &str) -> String {
+        let role = self.role(host);
+        let file = Path::new(source)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(source);
+        let template = self
+            .rules
+            .iter()
+            .find(|(pattern, rule)| {
+                (rule.roles.is_empty() || rule.roles.contains(&role))
+                    && pattern.matches_with(source, MATCH_OPTIONS)
+            })
+            .map_or("{remote_dir}/{file}", |(_, rule)| rule.target.as_str());
+        fill(template, &self.remote_dir, file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTROL_PLANE: &str = "10.0.0.1";
+    const ETCD: &str = "10.0.0.2";
+    const WORKER: &str = "10.0.0.3";
+
+    fn layout(settings: &LayoutSettings) -> RemoteLayout {
+        RemoteLayout::new(
+            settings,
+            "/etc/kubernetes/pki",
+            CONTROL_PLANE,
+            vec![ETCD.to_string()],
+        )
+    }
+
+    fn profile(profile: LayoutProfile) -> RemoteLayout {
+        layout(&LayoutSettings {
+            profile,
+            rules: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn roles_follow_the_cluster_config() {
+        let layout = profile(LayoutProfile::Kubeadm);
+        assert_eq!(layout.role(CONTROL_PLANE), NodeRole::ControlPlane);
+        assert_eq!(layout.role(ETCD), NodeRole::Etcd);
+        assert_eq!(layout.role(WORKER), NodeRole::Worker);
+    }
+
+    #[test]
+    fn kubeadm_uses_kubeadms_names() {
+        let layout = profile(LayoutProfile::Kubeadm);
+        let target = |source: &str| layout.target(source, CONTROL_PLANE);
+
+        assert_eq!(
+            target("certs/kubernetes-ca/ca.crt"),
+            "/etc/kubernetes/pki/ca.crt"
+        );
+        assert_eq!(
+            target("certs/kube-apiserver/kube-apiserver.crt"),
+            "/etc/kubernetes/pki/apiserver.crt"
+        );
+        assert_eq!(
+            target("certs/front-proxy-ca/ca.key"),
+            "/etc/kubernetes/pki/front-proxy-ca.key"
+        );
+        assert_eq!(
+            target("certs/etcd/10.0.0.2/server.crt"),
+            "/etc/kubernetes/pki/etcd/server.crt"
+        );
+        assert_eq!(
+            target("certs/root-ca/ca.crt"),
+            "/etc/kubernetes/pki/root-ca.crt"
+        );
+        assert_eq!(
+            target("certs/nodes/worker-1/kubelet-client.key"),
+            "/var/lib/kubelet/pki/kubelet-client.key"
+        );
+        assert_eq!(
+            target("kubeconfig/worker-1/kubelet.conf"),
+            "/etc/kubernetes/kubelet.conf"
+        );
+        assert_eq!(
+            target("kubeconfig/admin.conf"),
+            "/etc/kubernetes/admin.conf"
+        );
+    }
+
+    #[test]
+    fn hard_way_places_files_by_role() {
+        let layout = profile(LayoutProfile::HardWay);
+
+        assert_eq!(
+            layout.target("certs/kube-apiserver/kube-apiserver.crt", CONTROL_PLANE),
+            "/var/lib/kubernetes/kube-apiserver.crt"
+        );
+        assert_eq!(
+            layout.target("certs/kubernetes-ca/ca.crt", WORKER),
+            "/var/lib/kubelet/ca.crt"
+        );
+        assert_eq!(
+            layout.target("kubeconfig/admin.conf", CONTROL_PLANE),
+            "/var/lib/kubernetes/admin.kubeconfig"
+        );
+        assert_eq!(
+            layout.target("kubeconfig/kube-proxy.conf", WORKER),
+            "/var/lib/kube-proxy/kubeconfig"
+        );
+        assert_eq!(
+            layout.target("certs/etcd/10.0.0.2/server.key", ETCD),
+            "/etc/etcd/server.key"
+        );
+        // No profile rule covers other files on etcd members
+        assert_eq!(
+            layout.target("certs/kubernetes-ca/ca.crt", ETCD),
+            "/etc/kubernetes/pki/ca.crt"
+        );
+    }
+
+    #[test]
+    fn config_rules_come_before_the_profile() {
+        let layout = layout(&LayoutSettings {
+            profile: LayoutProfile::Kubeadm,
+            rules: vec![LayoutRule {
+                source: "certs/kubernetes-ca/*".to_string(),
+                target: "/srv/pki/{stem}-kubernetes.{file}".to_string(),
+                roles: vec![NodeRole::Worker],
+            }],
+        });
+
+        assert_eq!(
+            layout.target("certs/kubernetes-ca/ca.crt", WORKER),
+            "/srv/pki/ca-kubernetes.ca.crt"
+        );
+        assert_eq!(
+            layout.target("certs/kubernetes-ca/ca.crt", CONTROL_PLANE),
+            "/etc/kubernetes/pki/ca.crt"
+        );
+    }
+
+    #[test]
+    fn custom_layouts_only_use_the_config_rules() {
+        let layout = profile(LayoutProfile::Custom);
+        assert_eq!(
+            layout.target("certs/kube-apiserver/kube-apiserver.crt", CONTROL_PLANE),
+            "/etc/kubernetes/pki/kube-apiserver.crt"
+        );
+    }
+
+    #[test]
+    fn sources_are_resolved_below_certs() {
+        let layout = profile(LayoutProfile::Kubeadm);
+        assert_eq!(
+            layout.source("kubernetes-ca/ca.crt"),
+            "certs/kubernetes-ca/ca.crt"
+        );
+        assert_eq!(
+            layout.source("certs/kubernetes-ca/ca.crt"),
+            "certs/kubernetes-ca/ca.crt"
+        );
+        assert_eq!(
+            layout.source("kubeconfig/admin.conf"),
+            "kubeconfig/admin.conf"
+        );
+        assert_eq!(
+            layout.source("encryption-config.yaml"),
+            "encryption-config.yaml"
+        );
+    }
+
+    #[test]
+    fn validation_rejects_targets_unsafe_in_commands() {
+        let settings = |target: &str| LayoutSettings {
+            profile: LayoutProfile::Custom,
+            rules: vec![LayoutRule {
+                source: "certs/**".to_string(),
+                target: target.to_string(),
+                roles: Vec::new(),
+            }],
+        };
+
+        assert!(settings("{remote_dir}/{file}").validate().is_ok());
+        assert!(settings("relative/{file}").validate().is_err());
+        assert!(settings("/etc/$(reboot)/{file}").validate().is_err());
+    }
+}
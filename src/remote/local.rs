@@ -0,0 +1,142 @@
+// src/remote/local.rs
+//
+// Transport that treats `<root>/<host>` as the root filesystem of each host.
+// Files are written as the user running Starquill, so only modes are applied;
+// owners are reported by the names in the host tree's etc/passwd and
+// etc/group.
+use super::{sha256_hex, write_private, FilePermissions, RemoteFile, Transport};
+use std::{
+    fs, io,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+pub struct LocalRootTransport {
+    root: PathBuf,
+}
+
+impl LocalRootTransport {
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: PathBuf::from(shellexpand::tilde(root).to_string()),
+        }
+    }
+
+    /// Maps `remote` on `host` to a path below `<root>/<host>`. Parent
+    /// components are rejected so nothing is written outside the tree.
+    fn host_path(&self, host: &str, remote: &str) -> io::Result<PathBuf> {
+        let relative = Path::new(remote.trim_start_matches('/'));
+        if host.is_empty()
+            || host.contains('/')
+            || relative
+                .components()
+                .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid path {} on {}", remote, host),
+            ));
+        }
+        Ok(self.root.join(host).join(relative))
+    }
+
+    /// Name of `id` in the passwd-style `database` of `host`, if it has one.
+    fn name_of(&self, host: &str, database: &str, id: u32) -> Option<String> {
+        let contents = fs::read_to_string(self.host_path(host, database).ok()?).ok()?;
+        contents.lines().find_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            (fields.nth(1)?.parse::<u32>().ok()? == id).then(|| name.to_string())
+        })
+    }
+}
+
+impl Transport for LocalRootTransport {
+    fn put(
+        &self,
+        host: &str,
+        local: &Path,
+        remote: &str,
+        permissions: &FilePermissions,
+    ) -> io::Result<()> {
+        let target = self.host_path(host, remote)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(local, &target)?;
+        fs::set_permissions(&target, fs::Permissions::from_mode(permissions.mode))
+    }
+
+    fn get(&self, host: &str, remote: &str, local: &Path) -> io::Result<()> {
+        write_private(local, &fs::read(self.host_path(host, remote)?)?)
+    }
+
+    /// Only `hostname` is understood; it reads `etc/hostname` of the host
+    /// tree, as a node booted from it would.
+    fn exec(&self, host: &str, command: &str) -> io::Result<String> {
+        match command.trim() {
+            "hostname" => fs::read_to_string(self.host_path(host, "/etc/hostname")?),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Cannot run '{}' against a local root", command),
+            )),
+        }
+    }
+
+    fn stat(&self, host: &str, remote: &str) -> io::Result<Option<RemoteFile>> {
+        match fs::metadata(self.host_path(host, remote)?) {
+            Ok(metadata) => Ok(Some(RemoteFile {
+                size: metadata.len(),
+                mode: metadata.mode() & 0o7777,
+                owner: self.name_of(host, "/etc/passwd", metadata.uid()),
+                group: self.name_of(host, "/etc/group", metadata.gid()),
+            })),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn checksum(&self, host: &str, remote: &str) -> io::Result<Option<String>> {
+        match fs::read(self.host_path(host, remote)?) {
+            Ok(contents) => Ok(Some(sha256_hex(&contents))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn copy(&self, host: &str, from: &str, to: &str) -> io::Result<()> {
+        let target = self.host_path(host, to)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // fs::copy carries the permission bits over
+        fs::copy(self.host_path(host, from)?, target)?;
+        Ok(())
+    }
+
+    fn rename(&self, host: &str, from: &str, to: &str) -> io::Result<()> {
+        let target = self.host_path(host, to)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(self.host_path(host, from)?, target)
+    }
+
+    fn remove(&self, host: &str, remote: &str) -> io::Result<()> {
+        match fs::remove_file(self.host_path(host, remote)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn list(&self, host: &str, remote: &str) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(self.host_path(host, remote)?) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        entries
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect()
+    }
+}
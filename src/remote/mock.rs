@@ -0,0 +1,241 @@
+// src/remote/mock.rs
+//
+// In-memory transport. Files put on a host can be read back, commands
+// answer with scripted output, and hosts can be marked unreachable.
+use super::{sha256_hex, write_private, FilePermissions, RemoteFile, Transport};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+};
+
+struct MockFile {
+    contents: Vec<u8>,
+    permissions: FilePermissions,
+}
+
+#[derive(Default)]
+struct MockState {
+    files: HashMap<(String, String), MockFile>,
+    responses: HashMap<(String, String), String>,
+    unreachable: HashSet<String>,
+    failing_writes: HashSet<(String, String)>,
+}
+
+#[derive(Default)]
+pub struct MockTransport {
+    state: Mutex<MockState>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide mock, so what one component puts another can read.
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<MockTransport>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    /// Answers `command` on `host` with `output`.
+    pub fn respond(&self, host: &str, command: &str, output: &str) {
+        self.state()
+            .responses
+            .insert((host.to_string(), command.to_string()), output.to_string());
+    }
+
+    /// Makes every operation against `host` fail as if it were down.
+    pub fn set_unreachable(&self, host: &str) {
+        self.state().unreachable.insert(host.to_string());
+    }
+
+    /// Makes the next write of `remote` on `host` fail, as a read-only
+    /// mount would.
+    #[cfg(test)]
+    pub fn fail_next_write(&self, host: &str, remote: &str) {
+        self.state()
+            .failing_writes
+            .insert((host.to_string(), remote.to_string()));
+    }
+
+    /// Puts `contents` at `remote` on `host` as a file owned by root.
+    #[cfg(test)]
+    pub fn write(&self, host: &str, remote: &str, contents: &str) {
+        self.state().files.insert(
+            (host.to_string(), remote.to_string()),
+            MockFile {
+                contents: contents.as_bytes().to_vec(),
+                permissions: FilePermissions::new("root", "root", 0o600),
+            },
+        );
+    }
+
+    /// The contents of `remote` on `host`, if it exists.
+    #[cfg(test)]
+    pub fn read(&self, host: &str, remote: &str) -> Option<String> {
+        self.state()
+            .files
+            .get(&(host.to_string(), remote.to_string()))
+            .map(|file| String::from_utf8_lossy(&file.contents).into_owned())
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn reachable_state(&self, host: &str) -> io::Result<MutexGuard<'_, MockState>> {
+        let state = self.state();
+        if state.unreachable.contains(host) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("Host {} unreachable", host),
+            ));
+        }
+        Ok(state)
+    }
+}
+
+impl Transport for MockTransport {
+    fn put(
+        &self,
+        host: &str,
+        local: &Path,
+        remote: &str,
+        permissions: &FilePermissions,
+    ) -> io::Result<()> {
+        let contents = fs::read(local)?;
+        let mut state = self.reachable_state(host)?;
+        state.check_write(host, remote)?;
+        state.files.insert(
+            (host.to_string(), remote.to_string()),
+            MockFile {
+                contents,
+                permissions: permissions.clone(),
+            },
+        );
+        Ok(())
+    }
+
+    fn get(&self, host: &str, remote: &str, local: &Path) -> io::Result<()> {
+        let contents = self.reachable_state(host)?.file_contents(host, remote)?;
+        write_private(local, &contents)
+    }
+
+    fn exec(&self, host: &str, command: &str) -> io::Result<String> {
+        let state = self.reachable_state(host)?;
+        let key = (host.to_string(), command.to_string());
+        state.responses.get(&key).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("No scripted output for '{}'", command),
+            )
+        })
+    }
+
+    fn stat(&self, host: &str, remote: &str) -> io::Result<Option<RemoteFile>> {
+        Ok(self
+            .reachable_state(host)?
+            .files
+            .get(&(host.to_string(), remote.to_string()))
+            .map(|file| RemoteFile {
+                size: file.contents.len() as u64,
+                mode: file.permissions.mode,
+                owner: Some(file.permissions.owner.clone()),
+                group: Some(file.permissions.group.clone()),
+            }))
+    }
+
+    fn checksum(&self, host: &str, remote: &str) -> io::Result<Option<String>> {
+        Ok(self
+            .reachable_state(host)?
+            .files
+            .get(&(host.to_string(), remote.to_string()))
+            .map(|file| sha256_hex(&file.contents)))
+    }
+
+    fn copy(&self, host: &str, from: &str, to: &str) -> io::Result<()> {
+        let mut state = self.reachable_state(host)?;
+        state.check_write(host, to)?;
+        let contents = state.file_contents(host, from)?;
+        let permissions = state.files[&(host.to_string(), from.to_string())]
+            .permissions
+            .clone();
+        state.files.insert(
+            (host.to_string(), to.to_string()),
+            MockFile {
+                contents,
+                permissions,
+            },
+        );
+        Ok(())
+    }
+
+    fn rename(&self, host: &str, from: &str, to: &str) -> io::Result<()> {
+        let mut state = self.reachable_state(host)?;
+        state.check_write(host, to)?;
+        let file = state
+            .files
+            .remove(&(host.to_string(), from.to_string()))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} not found on {}", from, host),
+                )
+            })?;
+        state.files.insert((host.to_string(), to.to_string()), file);
+        Ok(())
+    }
+
+    fn remove(&self, host: &str, remote: &str) -> io::Result<()> {
+        self.reachable_state(host)?
+            .files
+            .remove(&(host.to_string(), remote.to_string()));
+        Ok(())
+    }
+
+    fn list(&self, host: &str, remote: &str) -> io::Result<Vec<String>> {
+        let prefix = format!("{}/", remote.trim_end_matches('/'));
+        let mut names: Vec<String> = self
+            .reachable_state(host)?
+            .files
+            .keys()
+            .filter(|(file_host, _)| file_host == host)
+            .filter_map(|(_, path)| path.strip_prefix(&prefix))
+            .filter_map(|rest| rest.split('/').next())
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+}
+
+impl MockState {
+    /// Fails a write that was set to fail, once.
+    fn check_write(&mut self, host: &str, remote: &str) -> io::Result<()> {
+        if self
+            .failing_writes
+            .remove(&(host.to_string(), remote.to_string()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Cannot write {} on {}", remote, host),
+            ));
+        }
+        Ok(())
+    }
+
+    fn file_contents(&self, host: &str, remote: &str) -> io::Result<Vec<u8>> {
+        self.files
+            .get(&(host.to_string(), remote.to_string()))
+            .map(|file| file.contents.clone())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} not found on {}", remote, host),
+                )
+            })
+    }
+}
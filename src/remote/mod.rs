@@ -0,0 +1,177 @@
+// src/remote/mod.rs
+//
+// How certificates reach the nodes. Distribution, remote verification and
+// node discovery only talk to a `Transport`, so the same flows run against
+// real hosts over SSH, against a directory tree on disk, or in memory.
+// Preflight checks tell beforehand whether an SSH host is ready for them.
+mod distribute;
+mod host_keys;
+mod integrity;
+mod layout;
+mod local;
+mod mock;
+mod policy;
+mod preflight;
+mod reload;
+mod ssh;
+mod ssh_settings;
+mod transaction;
+
+pub use distribute::{
+    DistributionJob, DistributionProgress, DistributionSettings, FileProgress, HostProgress,
+    HostState, PreflightGate, Transfer, TransferState,
+};
+pub use host_keys::{HostKey, HostKeySettings, HostKeyState, HostKeyStatus, KnownHosts};
+pub use integrity::{compare_checksums, IntegrityCheck};
+pub use layout::{LayoutSettings, RemoteLayout};
+pub use local::LocalRootTransport;
+pub use mock::MockTransport;
+pub use policy::{FilePermissions, PermissionPolicy};
+pub use preflight::{
+    preflight_hosts, CheckStatus, HostPreflight, PreflightCheck, PreflightNode, PreflightSettings,
+};
+pub use reload::{ReloadOutcome, ReloadSettings, ReloadState};
+pub use ssh::SshPool;
+pub use ssh_settings::{SshOptions, SshSettings};
+pub use transaction::{list_backups, restore_generation, BackupEntry, BackupManifest};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt},
+    path::Path,
+    sync::Arc,
+};
+
+/// Size, permission bits and ownership of a file on a host. Owner and
+/// group are `None` when the transport cannot resolve their names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteFile {
+    pub size: u64,
+    pub mode: u32,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+}
+
+pub trait Transport: Send + Sync {
+    /// Installs `local` at the absolute path `remote` on `host`, creating
+    /// parent directories and applying `permissions`.
+    fn put(
+        &self,
+        host: &str,
+        local: &Path,
+        remote: &str,
+        permissions: &FilePermissions,
+    ) -> io::Result<()>;
+
+    /// Copies the file at `remote` on `host` into `local`.
+    fn get(&self, host: &str, remote: &str, local: &Path) -> io::Result<()>;
+
+    /// Runs a shell command on `host` and returns its standard output.
+    fn exec(&self, host: &str, command: &str) -> io::Result<String>;
+
+    /// Metadata of `remote` on `host`, or `None` when it does not exist.
+    fn stat(&self, host: &str, remote: &str) -> io::Result<Option<RemoteFile>>;
+
+    /// Hex SHA-256 of `remote` on `host`, or `None` when it does not exist.
+    fn checksum(&self, host: &str, remote: &str) -> io::Result<Option<String>>;
+
+    /// Copies `from` to `to` on `host`, keeping mode and ownership.
+    fn copy(&self, host: &str, from: &str, to: &str) -> io::Result<()>;
+
+    /// Renames `from` over `to` on `host`. Atomic when both are on the same
+    /// filesystem.
+    fn rename(&self, host: &str, from: &str, to: &str) -> io::Result<()>;
+
+    /// Removes the file `remote` on `host`; a missing file is not an error.
+    fn remove(&self, host: &str, remote: &str) -> io::Result<()>;
+
+    /// Names of the entries of directory `remote` on `host`, empty when it
+    /// does not exist.
+    fn list(&self, host: &str, remote: &str) -> io::Result<Vec<String>>;
+}
+
+/// `transport` in cluster_config.json.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransportSettings {
+    /// Native SSH with `remote_user`, `ssh_key_path` and the `ssh` settings.
+    #[default]
+    Ssh,
+    /// Every host is the directory `<root>/<host>`; remote paths are
+    /// resolved inside it. Useful for staging a PKI tree for image baking.
+    LocalRoot { root: String },
+    /// In-memory hosts shared by the whole process, for dry runs and CI.
+    /// `hostnames` answers `hostname` per host; `unreachable` hosts fail
+    /// every operation.
+    Mock {
+        #[serde(default)]
+        hostnames: BTreeMap<String, String>,
+        #[serde(default)]
+        unreachable: Vec<String>,
+    },
+}
+
+impl TransportSettings {
+    pub fn open(&self, ssh: &SshOptions) -> Arc<dyn Transport> {
+        match self {
+            Self::Ssh => SshPool::shared(ssh),
+            Self::LocalRoot { root } => Arc::new(LocalRootTransport::new(root)),
+            Self::Mock {
+                hostnames,
+                unreachable,
+            } => {
+                let mock = MockTransport::shared();
+                for (host, hostname) in hostnames {
+                    mock.respond(host, "hostname", hostname);
+                }
+                for host in unreachable {
+                    mock.set_unreachable(host);
+                }
+                mock
+            }
+        }
+    }
+
+    pub fn is_ssh(&self) -> bool {
+        matches!(self, Self::Ssh)
+    }
+}
+
+/// Writes a file fetched from or adopted off a node. These may hold private
+/// keys, so they are created readable by their owner only.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // The mode only applies to new files
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+/// Creates `path` and its missing parents accessible to their owner only,
+/// tightening `path` itself if it already existed.
+pub(crate) fn create_private_dir(path: &Path) -> io::Result<()> {
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o700))
+}
+
+/// Quotes `arg` as a single word for a POSIX shell.
+pub(crate) fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+pub(crate) fn sha256_hex(contents: &[u8]) -> String {
+    openssl::sha::sha256(contents)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,293 @@
+// src/remote/policy.rs
+//
+// Ownership and permissions of distributed files. Every file gets the
+// settings of its class (private key, certificate, kubeconfig or other
+// secret), files in the etcd directory are handed to the etcd user, and
+// individual certificate types can be overridden.
+use super::RemoteFile;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::BTreeMap, path::Path};
+
+/// Owner, group and mode a file is installed with.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FilePermissions {
+    #[serde(default = "default_owner")]
+    pub owner: String,
+    #[serde(default = "default_owner")]
+    pub group: String,
+    /// Octal string such as "0644".
+    #[serde(
+        serialize_with = "serialize_mode",
+        deserialize_with = "deserialize_mode"
+    )]
+    pub mode: u32,
+}
+
+fn default_owner() -> String {
+    "root".to_string()
+}
+
+fn serialize_mode<S: Serializer>(mode: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:04o}", mode))
+}
+
+fn deserialize_mode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let mode = String::deserialize(deserializer)?;
+    u32::from_str_radix(&mode, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid file mode '{}'", mode)))
+}
+
+impl FilePermissions {
+    pub fn new(owner: &str, group: &str, mode: u32) -> Self {
+        Self {
+            owner: owner.to_string(),
+            group: group.to_string(),
+            mode,
+        }
+    }
+
+    /// How `file` differs from these permissions. Owner and group are only
+    /// compared when the transport could resolve them.
+    pub fn differences(&self, file: &RemoteFile) -> Vec<String> {
+        let mut differences = Vec::new();
+        if file.mode != self.mode {
+            differences.push(format!(
+                "mode {:04o}, expected {:04o}",
+                file.mode, self.mode
+            ));
+        }
+        if let Some(owner) = file.owner.as_ref().filter(|owner| **owner != self.owner) {
+            differences.push(format!("owner {}, expected {}", owner, self.owner));
+        }
+        if let Some(group) = file.group.as_ref().filter(|group| **group != self.group) {
+            differences.push(format!("group {}, expected {}", group, self.group));
+        }
+        differences
+    }
+}
+
+/// Owner and group without a mode, for rules that keep the mode of the
+/// file's class.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Ownership {
+    pub owner: String,
+    pub group: String,
+}
+
+/// `permissions` in cluster_config.json.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PermissionPolicy {
+    #[serde(default = "default_private_key")]
+    pub private_key: FilePermissions,
+    #[serde(default = "default_certificate")]
+    pub certificate: FilePermissions,
+    #[serde(default = "default_secret")]
+    pub kubeconfig: FilePermissions,
+    /// Encryption config and anything else that is not a key, certificate
+    /// or kubeconfig.
+    #[serde(default = "default_secret")]
+    pub other: FilePermissions,
+    /// Owner of files in the etcd directory, so etcd can run unprivileged.
+    #[serde(default = "default_etcd")]
+    pub etcd: Ownership,
+    /// Settings for single certificate types, taking precedence over all
+    /// of the above.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, FilePermissions>,
+}
+
+fn default_private_key() -> FilePermissions {
+    FilePermissions::new("root", "root", 0o600)
+}
+
+fn default_certificate() -> FilePermissions {
+    FilePermissions::new("root", "root", 0o644)
+}
+
+fn default_secret() -> FilePermissions {
+    FilePermissions::new("root", "root", 0o600)
+}
+
+fn default_etcd() -> Ownership {
+    Ownership {
+        owner: "etcd".to_string(),
+        group: "etcd".to_string(),
+    }
+}
+
+impl Default for PermissionPolicy {
+    fn default() -> Self {
+        Self {
+            private_key: default_private_key(),
+            certificate: default_certificate(),
+            kubeconfig: default_secret(),
+            other: default_secret(),
+            etcd: default_etcd(),
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+impl PermissionPolicy {
+    /// Owner and group names end up in shell commands on the hosts, so only
+    /// plain user names are accepted.
+    pub fn validate(&self) -> Result<(), String> {
+        let classes = [
+            ("private_key", &self.private_key),
+            ("certificate", &self.certificate),
+            ("kubeconfig", &self.kubeconfig),
+            ("other", &self.other),
+        ];
+        let overrides = self.overrides.iter().map(|(k, v)| (k.as_str(), v));
+        for (name, permissions) in classes.into_iter().chain(overrides) {
+            for user in [&permissions.owner, &permissions.group] {
+                validate_user(user).map_err(|e| format!("permissions.{}: {}", name, e))?;
+            }
+        }
+        validate_user(&self.etcd.owner).map_err(|e| format!("permissions.etcd: {}", e))?;
+        validate_user(&self.etcd.group).map_err(|e| format!("permissions.etcd: {}", e))
+    }
+
+    /// Permissions of the file of `cert_type` installed at `remote`.
+    pub fn for_file(&self, cert_type: &str, remote: &str) -> FilePermissions {
+        if let Some(permissions) = self.overrides.get(cert_type) {
+            return permissions.clone();
+        }
+
+        let path = Path::new(remote);
+        let mut permissions = match path.extension().and_then(|e| e.to_str()) {
+            Some("key") => self.private_key.clone(),
+            Some("crt") | Some("pem") | Some("pub") => self.certificate.clone(),
+            Some("conf") | Some("kubeconfig") => self.kubeconfig.clone(),
+            _ => self.other.clone(),
+        };
+        let in_etcd_dir = path
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|dir| dir == "etcd");
+        if in_etcd_dir {
+            permissions.owner = self.etcd.owner.clone();
+            permissions.group = self.etcd.group.clone();
+        }
+        permissions
+    }
+}
+
+fn validate_user(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid user or group name '{}'", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::{MockTransport, Transport};
+    use std::fs;
+
+    #[test]
+    fn files_get_the_permissions_of_their_class() {
+        let policy = PermissionPolicy::default();
+        let mode = |remote: &str| policy.for_file("any", remote).mode;
+
+        assert_eq!(mode("/etc/kubernetes/pki/apiserver.key"), 0o600);
+        assert_eq!(mode("/etc/kubernetes/pki/apiserver.crt"), 0o644);
+        assert_eq!(mode("/etc/kubernetes/pki/sa.pub"), 0o644);
+        assert_eq!(mode("/etc/kubernetes/admin.conf"), 0o600);
+        assert_eq!(mode("/etc/kubernetes/encryption-config.yaml"), 0o600);
+    }
+
+    #[test]
+    fn etcd_files_belong_to_the_etcd_user() {
+        let policy = PermissionPolicy::default();
+
+        let key = policy.for_file("etcd-server", "/etc/kubernetes/pki/etcd/server.key");
+        assert_eq!(key, FilePermissions::new("etcd", "etcd", 0o600));
+        let apiserver = policy.for_file(
+            "apiserver-etcd-client",
+            "/etc/kubernetes/pki/apiserver-etcd-client.key",
+        );
+        assert_eq!(apiserver, FilePermissions::new("root", "root", 0o600));
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let mut policy = PermissionPolicy::default();
+        policy.overrides.insert(
+            "etcd-server".to_string(),
+            FilePermissions::new("root", "etcd", 0o640),
+        );
+
+        let key = policy.for_file("etcd-server", "/etc/kubernetes/pki/etcd/server.key");
+        assert_eq!(key, FilePermissions::new("root", "etcd", 0o640));
+    }
+
+    #[test]
+    fn modes_are_octal_strings() {
+        let permissions: FilePermissions =
+            serde_json::from_str(r#"{"owner": "root", "group": "root", "mode": "0640"}"#).unwrap();
+        assert_eq!(permissions.mode, 0o640);
+        assert_eq!(serde_json::to_value(&permissions).unwrap()["mode"], "0640");
+
+        for invalid in ["0800", "17777", "rw-r-----"] {
+            let json = format!(r#"{{"mode": "{}"}}"#, invalid);
+            assert!(serde_json::from_str::<FilePermissions>(&json).is_err());
+        }
+    }
+
+    #[test]
+    fn validation_rejects_names_unsafe_in_commands() {
+        let mut policy = PermissionPolicy::default();
+        assert!(policy.validate().is_ok());
+
+        policy.etcd.owner = "etcd; rm -rf /".to_string();
+        assert!(policy.validate().is_err());
+
+        let mut policy = PermissionPolicy::default();
+        policy.overrides.insert(
+            "admin".to_string(),
+            FilePermissions::new("-oroot", "root", 0o600),
+        );
+        assert!(policy
+            .validate()
+            .unwrap_err()
+            .starts_with("permissions.admin"));
+    }
+
+    #[test]
+    fn differences_list_what_a_placed_file_got_wrong() {
+        let transport = MockTransport::new();
+        let local = std::env::temp_dir().join(format!("starquill-test-{}", uuid::Uuid::new_v4()));
+        fs::write(&local, "key").unwrap();
+        let remote = "/etc/kubernetes/pki/etcd/server.key";
+        transport
+            .put(
+                "10.0.0.1",
+                &local,
+                remote,
+                &FilePermissions::new("root", "root", 0o644),
+            )
+            .unwrap();
+        fs::remove_file(&local).unwrap();
+
+        let expected = PermissionPolicy::default().for_file("etcd-server", remote);
+        let placed = transport.stat("10.0.0.1", remote).unwrap().unwrap();
+        assert_eq!(
+            expected.differences(&placed),
+            [
+                "mode 0644, expected 0600",
+                "owner root, expected etcd",
+                "group root, expected etcd",
+            ]
+        );
+    }
+}
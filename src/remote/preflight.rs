@@ -0,0 +1,526 @@
+// src/remote/preflight.rs
+//
+// Checks a node is ready to receive certificates: it answers over SSH in
+// reasonable time, runs sudo without a password, can write and has room in
+// the directories files are installed to, and its clock agrees with ours.
+// Certificates are valid from the moment they are issued, so a node whose
+// clock runs behind rejects them until it catches up. The services a node
+// should run and its Kubernetes version are reported along the way.
+use super::Transport;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use utoipa::ToSchema;
+
+/// Skew beyond this many seconds is reported even below the limit; `date`
+/// only resolves whole seconds.
+const CLOCK_SKEW_WARN_SECS: f64 = 2.0;
+
+/// `preflight` in cluster_config.json.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PreflightSettings {
+    /// Round trips slower than this are reported as a warning.
+    #[serde(default = "default_max_latency_ms")]
+    pub max_latency_ms: u64,
+    /// Free space required below every target directory.
+    #[serde(default = "default_min_free_mb")]
+    pub min_free_mb: u64,
+    /// Clock difference to the local host a node fails at.
+    #[serde(default = "default_max_clock_skew_secs")]
+    pub max_clock_skew_secs: u64,
+    /// Refuse to distribute while a receiving node fails a check.
+    #[serde(default = "default_gate")]
+    pub gate: bool,
+}
+
+fn default_max_latency_ms() -> u64 {
+    1000
+}
+
+fn default_min_free_mb() -> u64 {
+    100
+}
+
+fn default_max_clock_skew_secs() -> u64 {
+    30
+}
+
+fn default_gate() -> bool {
+    true
+}
+
+impl Default for PreflightSettings {
+    fn default() -> Self {
+        Self {
+            max_latency_ms: default_max_latency_ms(),
+            min_free_mb: default_min_free_mb(),
+            max_clock_skew_secs: default_max_clock_skew_secs(),
+            gate: default_gate(),
+        }
+    }
+}
+
+impl PreflightSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_latency_ms == 0 {
+            return Err("preflight.max_latency_ms must be at least 1".into());
+        }
+        if self.max_clock_skew_secs == 0 {
+            return Err("preflight.max_clock_skew_secs must be at least 1".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PreflightCheck {
+    /// `ssh`, `sudo`, `directories`, `disk`, `clock`, `services` or
+    /// `kubernetes`.
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HostPreflight {
+    pub host: String,
+    /// The worst status of any check.
+    pub status: CheckStatus,
+    pub checks: Vec<PreflightCheck>,
+    pub checked: String,
+}
+
+/// What a node is expected to provide.
+#[derive(Debug, Clone)]
+pub struct PreflightNode {
+    pub host: String,
+    /// Directories files are installed or backed up to. They need not exist
+    /// yet; their nearest existing parent is checked instead.
+    pub directories: Vec<String>,
+    /// systemd units the node should run. A unit kubeadm runs as a static
+    /// pod instead counts when its manifest exists.
+    pub services: Vec<String>,
+}
+
+/// Runs every check on one node. Once the node cannot be reached the
+/// remaining checks are left out.
+pub fn preflight_host(
+    transport: &dyn Transport,
+    node: &PreflightNode,
+    settings: &PreflightSettings,
+) -> HostPreflight {
+    let host = node.host.as_str();
+    let mut checks = Vec::new();
+
+    let started = Instant::now();
+    match transport.exec(host, "true") {
+        Ok(_) => {
+            let latency = started.elapsed().as_millis();
+            let status = if latency > u128::from(settings.max_latency_ms) {
+                CheckStatus::Warn
+            } else {
+                CheckStatus::Pass
+            };
+            checks.push(check("ssh", status, format!("{} ms round trip", latency)));
+        }
+        Err(e) => {
+            checks.push(check("ssh", CheckStatus::Fail, e.to_string()));
+            return HostPreflight::new(host, checks);
+        }
+    }
+
+    checks.push(match transport.exec(host, "sudo -n true") {
+        Ok(_) => check("sudo", CheckStatus::Pass, "passwordless".to_string()),
+        Err(e) => check(
+            "sudo",
+            CheckStatus::Fail,
+            format!("sudo needs a password or is denied: {}", e),
+        ),
+    });
+
+    if !node.directories.is_empty() {
+        checks.push(check_directories(transport, node));
+        checks.push(check_disk(transport, node, settings));
+    }
+    checks.push(check_clock(transport, host, settings));
+    if !node.services.is_empty() {
+        checks.push(check_services(transport, node));
+    }
+    checks.push(check_version(transport, host));
+
+    HostPreflight::new(host, checks)
+}
+
+/// Runs [`preflight_host`] on every node in parallel, in the order given.
+pub fn preflight_hosts(
+    transport: &dyn Transport,
+    nodes: &[PreflightNode],
+    settings: &PreflightSettings,
+) -> Vec<HostPreflight> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = nodes
+            .iter()
+            .map(|node| scope.spawn(move || preflight_host(transport, node, settings)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("preflight check panicked"))
+            .collect()
+    })
+}
+
+impl HostPreflight {
+    fn new(host: &str, checks: Vec<PreflightCheck>) -> Self {
+        Self {
+            host: host.to_string(),
+            status: checks
+                .iter()
+                .map(|check| check.status)
+                .max()
+                .unwrap_or(CheckStatus::Pass),
+            checks,
+            checked: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+fn check(name: &str, status: CheckStatus, detail: String) -> PreflightCheck {
+    PreflightCheck {
+        name: name.to_string(),
+        status,
+        detail,
+    }
+}
+
+/// Shell loop binding `$p` to the nearest existing parent of each
+/// directory `$d`. Directories come from the validated remote layout and
+/// backup settings, so they are plain paths.
+fn for_each_existing(directories: &[String], body: &str) -> String {
+    format!(
+        "for d in {}; do p=$d; while [ ! -e \"$p\" ]; do p=$(dirname \"$p\"); done; {}; done",
+        directories.join(" "),
+        body
+    )
+}
+
+fn check_directories(transport: &dyn Transport, node: &PreflightNode) -> PreflightCheck {
+    let command = for_each_existing(
+        &node.directories,
+        "sudo -n test -d \"$p\" -a -w \"$p\" || echo \"$d\"",
+    );
+    match transport.exec(&node.host, &command) {
+        Ok(output) => {
+            let denied: Vec<&str> = output
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect();
+            if denied.is_empty() {
+                check(
+                    "directories",
+                    CheckStatus::Pass,
+                    format!("{} directories writable", node.directories.len()),
+                )
+            } else {
+                check(
+                    "directories",
+                    CheckStatus::Fail,
+                    format!("not writable: {}", denied.join(", ")),
+                )
+            }
+        }
+        Err(e) => check("directories", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+fn check_disk(
+    transport: &dyn Transport,
+    node: &PreflightNode,
+    settings: &PreflightSettings,
+) -> PreflightCheck {
+    let command = for_each_existing(&node.directories, "df -Pk \"$p\" | tail -n 1");
+    match transport.exec(&node.host, &command) {
+        Ok(output) => free_space(&output, settings.min_free_mb),
+        Err(e) => check("disk", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Judges the `df -Pk` lines of the target directories by the filesystem
+/// with the least room.
+fn free_space(output: &str, min_free_mb: u64) -> PreflightCheck {
+    // Filesystem, 1024-blocks, Used, Available, Capacity, Mounted on
+    let lowest = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let available = fields.get(3)?.parse::<u64>().ok()?;
+            Some((available / 1024, fields.get(5).copied().unwrap_or("?")))
+        })
+        .min_by_key(|(available, _)| *available);
+    match lowest {
+        Some((free, mount)) if free < min_free_mb => check(
+            "disk",
+            CheckStatus::Fail,
+            format!("{} MB free on {}, {} MB required", free, mount, min_free_mb),
+        ),
+        Some((free, mount)) => check(
+            "disk",
+            CheckStatus::Pass,
+            format!("{} MB free on {}", free, mount),
+        ),
+        None => check(
+            "disk",
+            CheckStatus::Warn,
+            "could not read free space".to_string(),
+        ),
+    }
+}
+
+fn check_clock(
+    transport: &dyn Transport,
+    host: &str,
+    settings: &PreflightSettings,
+) -> PreflightCheck {
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs_f64())
+            .unwrap_or_default()
+    };
+    let before = now();
+    let output = transport.exec(host, "date -u +%s");
+    // The node read its clock somewhere during the round trip
+    let local = (before + now()) / 2.0;
+
+    let remote = match output {
+        Ok(output) => match output.trim().parse::<f64>() {
+            Ok(remote) => remote,
+            Err(_) => {
+                return check(
+                    "clock",
+                    CheckStatus::Warn,
+                    format!("unexpected output of date: {}", output.trim()),
+                )
+            }
+        },
+        Err(e) => return check("clock", CheckStatus::Fail, e.to_string()),
+    };
+
+    // Whole seconds on the node, so half a second either way is noise
+    clock_skew(remote + 0.5 - local, settings)
+}
+
+/// Judges the clock of a node `skew` seconds ahead of ours.
+fn clock_skew(skew: f64, settings: &PreflightSettings) -> PreflightCheck {
+    let detail = if skew.abs() < 1.0 {
+        "in sync".to_string()
+    } else if skew < 0.0 {
+        format!(
+            "{:.0}s behind; it rejects new certificates until it catches up",
+            -skew
+        )
+    } else {
+        format!("{:.0}s ahead", skew)
+    };
+    let status = if skew.abs() > settings.max_clock_skew_secs as f64 {
+        CheckStatus::Fail
+    } else if skew.abs() > CLOCK_SKEW_WARN_SECS {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Pass
+    };
+    check("clock", status, detail)
+}
+
+fn check_services(transport: &dyn Transport, node: &PreflightNode) -> PreflightCheck {
+    let command = format!(
+        "for u in {}; do printf '%s %s %s' $u \
+         \"$(systemctl show -p LoadState --value $u.service 2>/dev/null)\" \
+         \"$(systemctl show -p ActiveState --value $u.service 2>/dev/null)\"; \
+         [ -f /etc/kubernetes/manifests/$u.yaml ] && printf ' static-pod'; echo; done",
+        node.services.join(" ")
+    );
+    let output = match transport.exec(&node.host, &command) {
+        Ok(output) => output,
+        Err(e) => return check("services", CheckStatus::Warn, e.to_string()),
+    };
+
+    let mut status = CheckStatus::Pass;
+    let mut states = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(unit) = fields.first() else {
+            continue;
+        };
+        let state = match (fields.get(1), fields.get(2)) {
+            _ if fields.contains(&"static-pod") => "static pod",
+            (Some(&"loaded"), Some(&"active")) => "active",
+            (Some(&"loaded"), Some(state)) => {
+                status = status.max(CheckStatus::Warn);
+                state
+            }
+            _ => {
+                status = status.max(CheckStatus::Warn);
+                "not installed"
+            }
+        };
+        states.push(format!("{} {}", unit, state));
+    }
+    check("services", status, states.join(", "))
+}
+
+fn check_version(transport: &dyn Transport, host: &str) -> PreflightCheck {
+    let command = "kubelet --version 2>/dev/null || kubeadm version -o short 2>/dev/null || true";
+    let version = transport.exec(host, command).ok().and_then(|output| {
+        output
+            .split_whitespace()
+            .find(|word| {
+                word.starts_with('v') && word[1..].starts_with(|c: char| c.is_ascii_digit())
+            })
+            .map(str::to_string)
+    });
+    match version {
+        Some(version) => check("kubernetes", CheckStatus::Pass, version),
+        None => check(
+            "kubernetes",
+            CheckStatus::Warn,
+            "no kubelet or kubeadm found".to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::MockTransport;
+
+    const HOST: &str = "10.0.0.1";
+
+    fn node() -> PreflightNode {
+        PreflightNode {
+            host: HOST.to_string(),
+            directories: Vec::new(),
+            services: Vec::new(),
+        }
+    }
+
+    /// Answers the checks of [`node`] like a reachable node with kubelet
+    /// v1.31 and a clock in sync.
+    fn script(transport: &MockTransport) {
+        transport.respond(HOST, "true", "");
+        transport.respond(HOST, "date -u +%s", &Utc::now().timestamp().to_string());
+        transport.respond(
+            HOST,
+            "kubelet --version 2>/dev/null || kubeadm version -o short 2>/dev/null || true",
+            "Kubernetes v1.31.2\n",
+        );
+    }
+
+    fn statuses(report: &HostPreflight) -> Vec<(&str, CheckStatus)> {
+        report
+            .checks
+            .iter()
+            .map(|check| (check.name.as_str(), check.status))
+            .collect()
+    }
+
+    #[test]
+    fn ready_nodes_pass() {
+        let transport = MockTransport::new();
+        script(&transport);
+        transport.respond(HOST, "sudo -n true", "");
+
+        let report = preflight_host(&transport, &node(), &PreflightSettings::default());
+        assert_eq!(report.status, CheckStatus::Pass);
+        assert_eq!(
+            statuses(&report),
+            [
+                ("ssh", CheckStatus::Pass),
+                ("sudo", CheckStatus::Pass),
+                ("clock", CheckStatus::Pass),
+                ("kubernetes", CheckStatus::Pass),
+            ]
+        );
+        assert_eq!(report.checks[3].detail, "v1.31.2");
+    }
+
+    #[test]
+    fn nodes_without_passwordless_sudo_fail() {
+        let transport = MockTransport::new();
+        script(&transport);
+
+        let report = preflight_host(&transport, &node(), &PreflightSettings::default());
+        assert_eq!(report.status, CheckStatus::Fail);
+        assert_eq!(report.checks[1].status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn unreachable_nodes_only_report_ssh() {
+        let transport = MockTransport::new();
+        transport.set_unreachable(HOST);
+
+        let report = preflight_host(&transport, &node(), &PreflightSettings::default());
+        assert_eq!(report.status, CheckStatus::Fail);
+        assert_eq!(statuses(&report), [("ssh", CheckStatus::Fail)]);
+    }
+
+    #[test]
+    fn the_fullest_filesystem_must_have_the_minimum_free() {
+        let df = |available_kb: u64| {
+            format!(
+                "/dev/sda1 20480000 1024 2048000 1% /\n\
+                 /dev/sda2 20480000 1024 {} 99% /var\n",
+                available_kb
+            )
+        };
+
+        let low = free_space(&df(50 * 1024), 100);
+        assert_eq!(low.status, CheckStatus::Fail);
+        assert_eq!(low.detail, "50 MB free on /var, 100 MB required");
+        assert_eq!(free_space(&df(100 * 1024), 100).status, CheckStatus::Pass);
+        assert_eq!(
+            free_space("df: /etc: No such file", 100).status,
+            CheckStatus::Warn
+        );
+    }
+
+    #[test]
+    fn clock_skew_warns_before_it_fails() {
+        let settings = PreflightSettings::default();
+        let status = |skew: f64| clock_skew(skew, &settings).status;
+
+        assert_eq!(clock_skew(0.4, &settings).detail, "in sync");
+        assert_eq!(status(2.0), CheckStatus::Pass);
+        assert_eq!(status(10.0), CheckStatus::Warn);
+        assert_eq!(status(30.0), CheckStatus::Warn);
+        assert_eq!(status(31.0), CheckStatus::Fail);
+
+        let behind = clock_skew(-45.0, &settings);
+        assert_eq!(behind.status, CheckStatus::Fail);
+        assert_eq!(
+            behind.detail,
+            "45s behind; it rejects new certificates until it catches up"
+        );
+    }
+}
@@ -0,0 +1,445 @@
+// src/remote/reload.rs
+//
+// Makes components pick up the files a distribution replaced. A hook runs on
+// every host where a file below one of its paths was committed: it restarts a
+// systemd unit, cycles a static pod manifest or runs a command, then waits for
+// its health gate. Hosts reload independently, and once a hook fails no
+// hook that has not started yet runs on any host.
+use super::{Transfer, Transport};
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+use utoipa::ToSchema;
+
+/// How often a health gate is polled until it passes or times out.
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `reload` in cluster_config.json.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReloadSettings {
+    /// Hooks in the order they run on a host.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<ReloadHook>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReloadHook {
+    /// Name shown in the log, e.g. `kubelet` or `etcd`.
+    pub component: String,
+    /// Remote directories or files whose replacement triggers the hook.
+    /// Empty triggers it on every host that received files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    pub action: ReloadAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<HealthGate>,
+    /// How long the health gate may take to pass.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    120
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReloadAction {
+    /// `systemctl restart <unit>`.
+    RestartUnit { unit: String },
+    /// Moves a static pod manifest out of the kubelet's manifest directory
+    /// for `pause_secs` and back, so the pod is recreated. Touching the file
+    /// is not enough: the kubelet only compares manifest contents.
+    TouchManifest {
+        manifest: String,
+        #[serde(default = "default_pause_secs")]
+        pause_secs: u64,
+    },
+    /// Runs as the SSH user; prefix it with sudo where needed.
+    Command { command: String },
+}
+
+fn default_pause_secs() -> u64 {
+    20
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HealthGate {
+    /// The kubelet's `/healthz` on localhost.
+    Kubelet {
+        #[serde(default = "default_kubelet_port")]
+        port: u16,
+    },
+    /// kube-apiserver's `/readyz` on localhost.
+    Apiserver {
+        #[serde(default = "default_apiserver_port")]
+        port: u16,
+    },
+    /// etcd's `/health`, queried with the healthcheck client certificate.
+    Etcd {
+        #[serde(default = "default_etcd_endpoint")]
+        endpoint: String,
+        #[serde(default = "default_etcd_cacert")]
+        cacert: String,
+        #[serde(default = "default_etcd_cert")]
+        cert: String,
+        #[serde(default = "default_etcd_key")]
+        key: String,
+    },
+    /// Passes once the command exits with status 0.
+    Command { command: String },
+}
+
+fn default_kubelet_port() -> u16 {
+    10248
+}
+
+fn default_apiserver_port() -> u16 {
+    6443
+}
+
+fn default_etcd_endpoint() -> String {
+    "https://127.0.0.1:2379".to_string()
+}
+
+fn default_etcd_cacert() -> String {
+    "/etc/kubernetes/pki/etcd/ca.crt".to_string()
+}
+
+fn default_etcd_cert() -> String {
+    "/etc/kubernetes/pki/etcd/healthcheck-client.crt".to_string()
+}
+
+fn default_etcd_key() -> String {
+    "/etc/kubernetes/pki/etcd/healthcheck-client.key".to_string()
+}
+
+impl ReloadSettings {
+    /// Unit names, paths and endpoints end up in shell commands on the
+    /// hosts, so only plain values are accepted. Custom commands are taken
+    /// as they are.
+    pub fn validate(&self) -> Result<(), String> {
+        for hook in &self.hooks {
+            let name = format!("reload hook '{}'", hook.component);
+            if hook.component.is_empty() {
+                return Err("reload: a hook has no component".into());
+            }
+            if hook.timeout_secs == 0 {
+                return Err(format!("{}: timeout_secs must be at least 1", name));
+            }
+            for path in &hook.paths {
+                validate_path(path).map_err(|e| format!("{}: {}", name, e))?;
+            }
+            match &hook.action {
+                ReloadAction::RestartUnit { unit } => {
+                    let valid = !unit.is_empty()
+                        && !unit.starts_with('-')
+                        && unit
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || "@._:-".contains(c));
+                    if !valid {
+                        return Err(format!("{}: invalid unit name '{}'", name, unit));
+                    }
+                }
+                ReloadAction::TouchManifest { manifest, .. } => {
+                    validate_path(manifest).map_err(|e| format!("{}: {}", name, e))?;
+                }
+                ReloadAction::Command { command } => {
+                    if command.trim().is_empty() {
+                        return Err(format!("{}: the command is empty", name));
+                    }
+                }
+            }
+            match &hook.health {
+                Some(HealthGate::Etcd {
+                    endpoint,
+                    cacert,
+                    cert,
+                    key,
+                }) => {
+                    let valid_endpoint = endpoint.starts_with("https://")
+                        && endpoint[8..]
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || ".:-[]".contains(c));
+                    if !valid_endpoint {
+                        return Err(format!("{}: invalid etcd endpoint '{}'", name, endpoint));
+                    }
+                    for path in [cacert, cert, key] {
+                        validate_path(path).map_err(|e| format!("{}: {}", name, e))?;
+                    }
+                }
+                Some(HealthGate::Command { command }) if command.trim().is_empty() => {
+                    return Err(format!("{}: the health command is empty", name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_path(path: &str) -> Result<(), String> {
+    let valid = path.starts_with('/')
+        && path
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a plain absolute path", path))
+    }
+}
+
+impl ReloadHook {
+    /// The transfers that replaced a file this hook watches.
+    pub fn triggers<'a>(&self, transfers: &'a [Transfer]) -> Vec<&'a Transfer> {
+        transfers
+            .iter()
+            .filter(|transfer| {
+                self.paths.is_empty()
+                    || self.paths.iter().any(|path| {
+                        Path::new(&transfer.remote).starts_with(path.trim_end_matches('/'))
+                    })
+            })
+            .collect()
+    }
+
+    fn action_command(&self) -> String {
+        match &self.action {
+            ReloadAction::RestartUnit { unit } => format!("sudo systemctl restart {}", unit),
+            ReloadAction::TouchManifest {
+                manifest,
+                pause_secs,
+            } => {
+                let parked = format!(
+                    "/tmp/starquill-{}",
+                    Path::new(manifest)
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .unwrap_or("manifest")
+                );
+                // The manifest goes back even if the pause is interrupted
+                format!(
+                    "sudo sh -c 'mv {m} {p} && {{ sleep {s}; mv {p} {m}; }}'",
+                    m = manifest,
+                    p = parked,
+                    s = pause_secs
+                )
+            }
+            ReloadAction::Command { command } => command.clone(),
+        }
+    }
+
+    /// Runs the action on `host` and waits for the health gate.
+    pub fn run(&self, transport: &dyn Transport, host: &str) -> io::Result<()> {
+        transport
+            .exec(host, &self.action_command())
+            .map_err(|e| io::Error::new(e.kind(), format!("reload failed: {}", e)))?;
+        match &self.health {
+            Some(gate) => gate.wait(transport, host, Duration::from_secs(self.timeout_secs)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl HealthGate {
+    fn command(&self) -> String {
+        match self {
+            Self::Kubelet { port } => format!("curl -sf http://127.0.0.1:{}/healthz", port),
+            // Only readiness matters here, not which CA serves it
+            Self::Apiserver { port } => format!("curl -sfk https://127.0.0.1:{}/readyz", port),
+            Self::Etcd {
+                endpoint,
+                cacert,
+                cert,
+                key,
+            } => format!(
+                "sudo curl -sf --cacert {} --cert {} --key {} {}/health",
+                cacert, cert, key, endpoint
+            ),
+            Self::Command { command } => command.clone(),
+        }
+    }
+
+    fn passes(&self, output: &str) -> bool {
+        match self {
+            Self::Etcd { .. } => output.replace(' ', "").contains("\"health\":\"true\""),
+            _ => true,
+        }
+    }
+
+    /// Polls the gate on `host` until it passes or `timeout` has elapsed.
+    pub fn wait(&self, transport: &dyn Transport, host: &str, timeout: Duration) -> io::Result<()> {
+        let command = self.command();
+        let started = Instant::now();
+        loop {
+            let error = match transport.exec(host, &command) {
+                Ok(output) if self.passes(&output) => return Ok(()),
+                Ok(output) => format!("unhealthy: {}", output.trim()),
+                Err(e) => e.to_string(),
+            };
+            if started.elapsed() + HEALTH_POLL_INTERVAL > timeout {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "health check did not pass within {}s: {}",
+                        timeout.as_secs(),
+                        error
+                    ),
+                ));
+            }
+            thread::sleep(HEALTH_POLL_INTERVAL);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadState {
+    /// Reloaded, and the health gate passed if there is one.
+    Healthy,
+    /// The action itself failed.
+    Failed,
+    /// Reloaded, but the health gate did not pass in time.
+    Unhealthy,
+    /// Not run because an earlier hook or host failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReloadOutcome {
+    pub component: String,
+    pub state: ReloadState,
+    /// Certificates whose files triggered the hook.
+    pub cert_types: Vec<String>,
+    pub elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::{FilePermissions, MockTransport};
+
+    fn hook(paths: &[&str], action: ReloadAction, health: Option<HealthGate>) -> ReloadHook {
+        ReloadHook {
+            component: "kubelet".to_string(),
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+            action,
+            health,
+            timeout_secs: 1,
+        }
+    }
+
+    fn transfer(remote: &str) -> Transfer {
+        Transfer {
+            cert_type: "kubelet".to_string(),
+            host: "10.0.0.1".to_string(),
+            source: String::new(),
+            remote: remote.to_string(),
+            permissions: FilePermissions::new("root", "root", 0o600),
+        }
+    }
+
+    #[test]
+    fn hooks_trigger_on_files_below_their_paths() {
+        let transfers = [
+            transfer("/var/lib/kubelet/pki/kubelet.crt"),
+            transfer("/var/lib/kubelet-other/kubelet.crt"),
+            transfer("/etc/kubernetes/pki/ca.crt"),
+        ];
+        let restart = ReloadAction::RestartUnit {
+            unit: "kubelet".to_string(),
+        };
+
+        let kubelet = hook(&["/var/lib/kubelet/"], restart.clone(), None);
+        let triggered: Vec<&str> = kubelet
+            .triggers(&transfers)
+            .iter()
+            .map(|transfer| transfer.remote.as_str())
+            .collect();
+        assert_eq!(triggered, ["/var/lib/kubelet/pki/kubelet.crt"]);
+
+        let everything = hook(&[], restart, None);
+        assert_eq!(everything.triggers(&transfers).len(), 3);
+    }
+
+    #[test]
+    fn validation_rejects_values_unsafe_in_commands() {
+        let settings = |hook| ReloadSettings { hooks: vec![hook] };
+        let restart = |unit: &str| ReloadAction::RestartUnit {
+            unit: unit.to_string(),
+        };
+
+        assert!(
+            settings(hook(&["/etc/kubernetes/pki"], restart("kubelet"), None))
+                .validate()
+                .is_ok()
+        );
+        assert!(settings(hook(&[], restart("kubelet; reboot"), None))
+            .validate()
+            .is_err());
+        assert!(
+            settings(hook(&["etc/kubernetes"], restart("kubelet"), None))
+                .validate()
+                .is_err()
+        );
+        let etcd = HealthGate::Etcd {
+            endpoint: "https://127.0.0.1:2379/$(reboot)".to_string(),
+            cacert: default_etcd_cacert(),
+            cert: default_etcd_cert(),
+            key: default_etcd_key(),
+        };
+        assert!(settings(hook(&[], restart("etcd"), Some(etcd)))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn hooks_run_their_action_and_wait_for_the_gate() {
+        let transport = MockTransport::new();
+        let kubelet = hook(
+            &[],
+            ReloadAction::RestartUnit {
+                unit: "kubelet".to_string(),
+            },
+            Some(HealthGate::Kubelet {
+                port: default_kubelet_port(),
+            }),
+        );
+
+        // Not restarted yet, so the action has no scripted output
+        let error = kubelet.run(&transport, "10.0.0.1").unwrap_err();
+        assert!(error.to_string().starts_with("reload failed"));
+
+        transport.respond("10.0.0.1", "sudo systemctl restart kubelet", "");
+        let error = kubelet.run(&transport, "10.0.0.1").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+
+        transport.respond("10.0.0.1", "curl -sf http://127.0.0.1:10248/healthz", "ok");
+        kubelet.run(&transport, "10.0.0.1").unwrap();
+    }
+
+    #[test]
+    fn etcd_passes_only_when_it_reports_healthy() {
+        let transport = MockTransport::new();
+        let gate = HealthGate::Etcd {
+            endpoint: default_etcd_endpoint(),
+            cacert: default_etcd_cacert(),
+            cert: default_etcd_cert(),
+            key: default_etcd_key(),
+        };
+        let timeout = Duration::from_secs(1);
+
+        transport.respond("10.0.0.1", &gate.command(), r#"{"health":"false"}"#);
+        assert!(gate.wait(&transport, "10.0.0.1", timeout).is_err());
+        transport.respond("10.0.0.1", &gate.command(), r#"{"health": "true"}"#);
+        assert!(gate.wait(&transport, "10.0.0.1", timeout).is_ok());
+    }
+}
@@ -0,0 +1,800 @@
+// src/remote/ssh.rs
+//
+// Pooled SSH sessions on top of libssh2. One authenticated session per host
+// is kept open and shared by command execution and SFTP transfers, so talking
+// to a node repeatedly costs a single handshake. Hosts behind jump hosts are
+// reached through direct-tcpip channels, each hop relayed by its own thread.
+use super::{
+    host_keys::{HostKey, HostKeyCheck, KnownHosts},
+    shell_quote,
+    ssh_settings::{Auth, Endpoint, SshOptions},
+    write_private, FilePermissions, RemoteFile, Transport,
+};
+use base64::{engine::general_purpose, Engine as _};
+use rustix::{
+    event::{self, PollFd, PollFlags},
+    io::Errno,
+};
+use ssh2::{BlockDirections, Channel, HashType, HostKeyType, OpenFlags, OpenType, Session};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    os::unix::{
+        io::{AsFd, AsRawFd, OwnedFd},
+        net::UnixStream,
+    },
+    path::Path,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const SESSION_TIMEOUT_MS: u32 = 60_000;
+/// How long a wait for a socket lasts before the waiter checks again.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum SshError {
+    Unreachable {
+        host: String,
+        source: io::Error,
+    },
+    Handshake {
+        host: String,
+        source: ssh2::Error,
+    },
+    /// The host presented a key nobody has confirmed yet.
+    HostKeyUnknown {
+        host: String,
+        key: HostKey,
+    },
+    /// The host presented a key other than the pinned one.
+    HostKeyChanged {
+        host: String,
+        expected: String,
+        found: HostKey,
+    },
+    HostKeyStore {
+        host: String,
+        source: io::Error,
+    },
+    AuthFailed {
+        host: String,
+        user: String,
+    },
+    SudoDenied {
+        host: String,
+        message: String,
+    },
+    CommandFailed {
+        host: String,
+        command: String,
+        status: i32,
+        stderr: String,
+    },
+    Transfer {
+        host: String,
+        path: String,
+        source: io::Error,
+    },
+    Session {
+        host: String,
+        source: ssh2::Error,
+    },
+}
+
+impl std::fmt::Display for SshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unreachable { host, source } => {
+                write!(f, "Host {} unreachable: {}", host, source)
+            }
+            Self::Handshake { host, source } => {
+                write!(f, "SSH handshake with {} failed: {}", host, source)
+            }
+            Self::HostKeyUnknown { host, key } => write!(
+                f,
+                "Host key of {} is not trusted yet ({} {}); confirm it at startup, \
+                 through the API or in host_keys.pinned",
+                host, key.key_type, key.fingerprint
+            ),
+            Self::HostKeyChanged {
+                host,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Host key of {} has changed, refusing to connect:\n  \
+                 - pinned    {}\n  \
+                 + presented {} {}\n\
+                 Re-pin the key only if the change is expected",
+                host, expected, found.key_type, found.fingerprint
+            ),
+            Self::HostKeyStore { host, source } => {
+                write!(f, "Cannot check the host key of {}: {}", host, source)
+            }
+            Self::AuthFailed { host, user } => {
+                write!(f, "SSH authentication as {} on {} failed", user, host)
+            }
+            Self::SudoDenied { host, message } => write!(f, "sudo denied on {}: {}", host, message),
+            Self::CommandFailed {
+                host,
+                command,
+                status,
+                stderr,
+            } => write!(
+                f,
+                "Command '{}' on {} exited with {}: {}",
+                command, host, status, stderr
+            ),
+            Self::Transfer { host, path, source } => {
+                write!(f, "Transfer of {} on {} failed: {}", path, host, source)
+            }
+            Self::Session { host, source } => {
+                write!(f, "SSH session to {} failed: {}", host, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SshError {}
+
+impl SshError {
+    /// Errors that mean the pooled session itself is no longer usable.
+    fn is_connection_error(&self) -> bool {
+        matches!(self, Self::Session { .. })
+    }
+}
+
+impl From<SshError> for io::Error {
+    fn from(error: SshError) -> Self {
+        let kind = match &error {
+            SshError::Unreachable { .. } => io::ErrorKind::NotConnected,
+            SshError::AuthFailed { .. }
+            | SshError::SudoDenied { .. }
+            | SshError::HostKeyUnknown { .. }
+            | SshError::HostKeyChanged { .. }
+            | SshError::HostKeyStore { .. } => io::ErrorKind::PermissionDenied,
+            SshError::Transfer { source, .. } => source.kind(),
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// An authenticated session and a handle on the socket it runs over, which
+/// is what non-blocking reads wait on.
+struct Connection {
+    session: Session,
+    socket: OwnedFd,
+}
+
+type SessionSlot = Arc<Mutex<Option<Connection>>>;
+type PoolRegistry = Mutex<Vec<(SshOptions, Arc<SshPool>)>>;
+
+/// Authenticated SSH sessions for one set of connection settings, keyed by
+/// host. Operations on the same host are serialized; different hosts run
+/// independently. A session that breaks is reopened once per operation.
+/// Sessions are only opened to hosts presenting their pinned key.
+pub struct SshPool {
+    options: SshOptions,
+    known_hosts: Arc<KnownHosts>,
+    sessions: Mutex<HashMap<String, SessionSlot>>,
+}
+
+impl SshPool {
+    pub fn new(options: &SshOptions) -> Self {
+        Self {
+            options: options.clone(),
+            known_hosts: KnownHosts::shared(&options.host_keys),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The process-wide pool for `options`, so every component talking to a
+    /// host reuses the same session.
+    pub fn shared(options: &SshOptions) -> Arc<Self> {
+        static POOLS: OnceLock<PoolRegistry> = OnceLock::new();
+
+        let mut pools = POOLS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some((_, pool)) = pools.iter().find(|(existing, _)| existing == options) {
+            return Arc::clone(pool);
+        }
+        let pool = Arc::new(Self::new(options));
+        pools.push((options.clone(), Arc::clone(&pool)));
+        pool
+    }
+
+    /// Runs `command` on `host` and returns its standard output.
+    pub fn exec(&self, host: &str, command: &str) -> Result<String, SshError> {
+        self.with_session(host, |connection| {
+            let session = &connection.session;
+            let session_error = |source| SshError::Session {
+                host: host.to_string(),
+                source,
+            };
+
+            let mut channel = session.channel_session().map_err(session_error)?;
+            channel.exec(command).map_err(session_error)?;
+
+            // A command filling the stderr window while stdout is read to
+            // its end would never finish, so both are read as data arrives
+            session.set_blocking(false);
+            let output = read_output(connection, &mut channel);
+            session.set_blocking(true);
+            let (stdout, stderr) = output.map_err(|source| SshError::Transfer {
+                host: host.to_string(),
+                path: command.to_string(),
+                source,
+            })?;
+            channel.wait_close().map_err(session_error)?;
+            let status = channel.exit_status().map_err(session_error)?;
+
+            if status == 0 {
+                Ok(stdout)
+            } else if is_sudo_denial(&stderr) {
+                Err(SshError::SudoDenied {
+                    host: host.to_string(),
+                    message: stderr.trim().to_string(),
+                })
+            } else {
+                Err(SshError::CommandFailed {
+                    host: host.to_string(),
+                    command: command.to_string(),
+                    status,
+                    stderr: stderr.trim().to_string(),
+                })
+            }
+        })
+    }
+
+    /// Uploads `local` to `remote` over SFTP, creating it with `mode`.
+    pub fn upload(
+        &self,
+        host: &str,
+        local: &Path,
+        remote: &str,
+        mode: i32,
+    ) -> Result<(), SshError> {
+        let transfer_error = |path: &str, source| SshError::Transfer {
+            host: host.to_string(),
+            path: path.to_string(),
+            source,
+        };
+        let contents = fs::read(local).map_err(|e| transfer_error(&local.to_string_lossy(), e))?;
+
+        self.with_session(host, |connection| {
+            let sftp = connection
+                .session
+                .sftp()
+                .map_err(|source| SshError::Session {
+                    host: host.to_string(),
+                    source,
+                })?;
+            let mut file = sftp
+                .open_mode(
+                    Path::new(remote),
+                    OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+                    mode,
+                    OpenType::File,
+                )
+                .map_err(|e| transfer_error(remote, e.into()))?;
+            file.write_all(&contents)
+                .map_err(|e| transfer_error(remote, e))
+        })
+    }
+
+    /// Downloads `remote` from `host` into `local` over SFTP.
+    pub fn download(&self, host: &str, remote: &str, local: &Path) -> Result<(), SshError> {
+        let transfer_error = |path: &str, source| SshError::Transfer {
+            host: host.to_string(),
+            path: path.to_string(),
+            source,
+        };
+
+        let contents = self.with_session(host, |connection| {
+            let sftp = connection
+                .session
+                .sftp()
+                .map_err(|source| SshError::Session {
+                    host: host.to_string(),
+                    source,
+                })?;
+            let mut file = sftp
+                .open(Path::new(remote))
+                .map_err(|e| transfer_error(remote, e.into()))?;
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)
+                .map_err(|e| transfer_error(remote, e))?;
+            Ok(contents)
+        })?;
+
+        write_private(local, &contents).map_err(|e| transfer_error(&local.to_string_lossy(), e))
+    }
+
+    /// Whether `host` accepts our key and can run a command. A failed check
+    /// drops any pooled session to the host.
+    pub fn check(&self, host: &str) -> bool {
+        let reachable = self.exec(host, "true").is_ok();
+        if !reachable {
+            self.disconnect(host);
+        }
+        reachable
+    }
+
+    pub fn disconnect(&self, host: &str) {
+        let slot = self
+            .sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(host);
+        if let Some(slot) = slot {
+            if let Some(connection) = slot.lock().unwrap_or_else(PoisonError::into_inner).take() {
+                let _ = connection.session.disconnect(None, "closing", None);
+            }
+        }
+    }
+
+    fn with_session<T>(
+        &self,
+        host: &str,
+        operation: impl Fn(&Connection) -> Result<T, SshError>,
+    ) -> Result<T, SshError> {
+        let slot = self
+            .sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(host.to_string())
+            .or_default()
+            .clone();
+        let mut connection = slot.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut reused = connection.is_some();
+        loop {
+            let current = match connection.as_ref() {
+                Some(current) => current,
+                None => connection.insert(self.connect(host)?),
+            };
+            match operation(current) {
+                // The host may have dropped an idle session; reconnect once
+                Err(e) if reused && e.is_connection_error() => {
+                    *connection = None;
+                    reused = false;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Logs in to `host`, through its jump hosts if it has any.
+    fn connect(&self, host: &str) -> Result<Connection, SshError> {
+        let (jumps, target) = self.options.route(host);
+        let mut hops = jumps.iter().chain([&target]);
+        // The chain always ends with the target itself
+        let first = hops.next().unwrap_or(&target);
+
+        let unreachable = |source| SshError::Unreachable {
+            host: first.host.clone(),
+            source,
+        };
+        let address = (first.host.as_str(), first.port)
+            .to_socket_addrs()
+            .map_err(unreachable)?
+            .next()
+            .ok_or_else(|| {
+                unreachable(io::Error::new(io::ErrorKind::NotFound, "no address found"))
+            })?;
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(unreachable)?;
+        let mut socket = stream.as_fd().try_clone_to_owned().map_err(unreachable)?;
+
+        let mut session = self.log_in(first, stream)?;
+        for hop in hops {
+            let stream = tunnel(session, socket, hop)?;
+            socket =
+                stream
+                    .as_fd()
+                    .try_clone_to_owned()
+                    .map_err(|source| SshError::Unreachable {
+                        host: hop.host.clone(),
+                        source,
+                    })?;
+            session = self.log_in(hop, stream)?;
+        }
+        Ok(Connection { session, socket })
+    }
+
+    /// Runs the SSH handshake with `endpoint` over `stream`, checks its host
+    /// key and authenticates.
+    fn log_in<S: 'static + AsRawFd>(
+        &self,
+        endpoint: &Endpoint,
+        stream: S,
+    ) -> Result<Session, SshError> {
+        let handshake = |source| SshError::Handshake {
+            host: endpoint.host.clone(),
+            source,
+        };
+
+        let mut session = Session::new().map_err(handshake)?;
+        session.set_tcp_stream(stream);
+        session.set_timeout(SESSION_TIMEOUT_MS);
+        session.handshake().map_err(handshake)?;
+        self.check_host_key(&endpoint.host, &session)?;
+
+        let authenticated = match &endpoint.auth {
+            Auth::Agent => session.userauth_agent(&endpoint.user),
+            Auth::Key {
+                path,
+                passphrase_env,
+            } => {
+                let passphrase = passphrase_env
+                    .as_ref()
+                    .and_then(|name| std::env::var(name).ok());
+                session.userauth_pubkey_file(&endpoint.user, None, path, passphrase.as_deref())
+            }
+        };
+        if authenticated.is_err() || !session.authenticated() {
+            return Err(SshError::AuthFailed {
+                host: endpoint.host.clone(),
+                user: endpoint.user.clone(),
+            });
+        }
+
+        Ok(session)
+    }
+
+    /// Refuses hosts whose key is not the pinned one, before any credential
+    /// is sent to them.
+    fn check_host_key(&self, host: &str, session: &Session) -> Result<(), SshError> {
+        let key_type = match session.host_key() {
+            Some((_, HostKeyType::Rsa)) => "ssh-rsa",
+            Some((_, HostKeyType::Dss)) => "ssh-dss",
+            Some((_, HostKeyType::Ecdsa256)) => "ecdsa-sha2-nistp256",
+            Some((_, HostKeyType::Ecdsa384)) => "ecdsa-sha2-nistp384",
+            Some((_, HostKeyType::Ecdsa521)) => "ecdsa-sha2-nistp521",
+            Some((_, HostKeyType::Ed25519)) => "ssh-ed25519",
+            _ => "unknown",
+        };
+        let hash =
+            session
+                .host_key_hash(HashType::Sha256)
+                .ok_or_else(|| SshError::HostKeyStore {
+                    host: host.to_string(),
+                    source: io::Error::new(io::ErrorKind::InvalidData, "no host key presented"),
+                })?;
+        let key = HostKey::new(
+            key_type,
+            format!("SHA256:{}", general_purpose::STANDARD_NO_PAD.encode(hash)),
+        );
+
+        let check =
+            self.known_hosts
+                .check(host, &key)
+                .map_err(|source| SshError::HostKeyStore {
+                    host: host.to_string(),
+                    source,
+                })?;
+        match check {
+            HostKeyCheck::Trusted => Ok(()),
+            HostKeyCheck::Unknown => Err(SshError::HostKeyUnknown {
+                host: host.to_string(),
+                key,
+            }),
+            HostKeyCheck::Changed { expected } => Err(SshError::HostKeyChanged {
+                host: host.to_string(),
+                expected,
+                found: key,
+            }),
+        }
+    }
+}
+
+/// Reads stdout and stderr of `channel` to their end, from whichever has
+/// data, on a session in non-blocking mode. Fails once the command has been
+/// silent for the session timeout.
+fn read_output(connection: &Connection, channel: &mut Channel) -> io::Result<(String, String)> {
+    let timeout = Duration::from_millis(SESSION_TIMEOUT_MS.into());
+    // Stream 0 is stdout, stream 1 (EXTENDED_DATA_STDERR) stderr
+    let mut output = [Vec::new(), Vec::new()];
+    let mut buffer = [0u8; 32 * 1024];
+    let mut last_read = Instant::now();
+    loop {
+        let mut idle = true;
+        for (stream_id, contents) in (0..).zip(output.iter_mut()) {
+            match channel.stream(stream_id).read(&mut buffer) {
+                Ok(read) => {
+                    contents.extend_from_slice(&buffer[..read]);
+                    idle &= read == 0;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !idle {
+            last_read = Instant::now();
+        } else if channel.eof() {
+            break;
+        } else if last_read.elapsed() >= timeout {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "no output from the command within the session timeout",
+            ));
+        } else {
+            let events = session_events(&connection.session);
+            wait_for(&mut [PollFd::new(&connection.socket, events)])?;
+        }
+    }
+
+    let [stdout, stderr] = output;
+    let text =
+        |bytes| String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+    Ok((text(stdout)?, text(stderr)?))
+}
+
+/// What `session` needs from its socket before a blocked call can proceed.
+fn session_events(session: &Session) -> PollFlags {
+    match session.block_directions() {
+        BlockDirections::Outbound => PollFlags::OUT,
+        BlockDirections::Both => PollFlags::IN | PollFlags::OUT,
+        BlockDirections::Inbound | BlockDirections::None => PollFlags::IN,
+    }
+}
+
+/// Waits until one of `sockets` is ready or `POLL_INTERVAL` has passed.
+fn wait_for(sockets: &mut [PollFd<'_>]) -> io::Result<()> {
+    match event::poll(sockets, POLL_INTERVAL.as_millis() as i32) {
+        Ok(_) => Ok(()),
+        Err(e) if e == Errno::INTR => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Opens a channel from `session` to `hop` and relays it to one end of a
+/// socket pair on a thread that owns the session. The other end carries the
+/// SSH connection to `hop`; closing it tears the relay down. `socket` is the
+/// one `session` runs over.
+fn tunnel(session: Session, socket: OwnedFd, hop: &Endpoint) -> Result<UnixStream, SshError> {
+    let unreachable = |source| SshError::Unreachable {
+        host: hop.host.clone(),
+        source,
+    };
+
+    let channel = session
+        .channel_direct_tcpip(&hop.host, hop.port, None)
+        .map_err(|e| unreachable(e.into()))?;
+    let (stream, relay_end) = UnixStream::pair().map_err(unreachable)?;
+    relay_end.set_nonblocking(true).map_err(unreachable)?;
+    session.set_blocking(false);
+
+    thread::spawn(move || relay(session, socket, channel, relay_end));
+    Ok(stream)
+}
+
+/// Copies between `channel` and `stream` until either side closes, waiting
+/// on both sockets while neither has data. The session of the hop is held
+/// until then.
+fn relay(session: Session, socket: OwnedFd, mut channel: Channel, mut stream: UnixStream) {
+    let wait_for_session =
+        |_: &Channel| wait_for(&mut [PollFd::new(&socket, session_events(&session))]);
+    let wait_for_stream =
+        |stream: &UnixStream| wait_for(&mut [PollFd::new(stream, PollFlags::OUT)]);
+
+    let mut buffer = [0u8; 32 * 1024];
+    loop {
+        let mut idle = true;
+
+        match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => {
+                if write_fully(&mut channel, &buffer[..read], wait_for_session).is_err() {
+                    break;
+                }
+                idle = false;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        match channel.read(&mut buffer) {
+            Ok(0) if channel.eof() => break,
+            Ok(0) => {}
+            Ok(read) => {
+                if write_fully(&mut stream, &buffer[..read], wait_for_stream).is_err() {
+                    break;
+                }
+                idle = false;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        if idle {
+            let mut sockets = [
+                PollFd::new(&stream, PollFlags::IN),
+                PollFd::new(&socket, session_events(&session)),
+            ];
+            if wait_for(&mut sockets).is_err() {
+                break;
+            }
+        }
+    }
+    let _ = channel.close();
+}
+
+/// `write_all` for non-blocking writers. `wait` blocks until `writer` can
+/// take more.
+fn write_fully<W: Write>(
+    writer: &mut W,
+    mut data: &[u8],
+    wait: impl Fn(&W) -> io::Result<()>,
+) -> io::Result<()> {
+    while !data.is_empty() {
+        match writer.write(data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => data = &data[written..],
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => wait(writer)?,
+            Err(e) => return Err(e),
+        }
+    }
+    writer.flush().or_else(|e| match e.kind() {
+        io::ErrorKind::WouldBlock => Ok(()),
+        _ => Err(e),
+    })
+}
+
+/// Files are staged in /tmp as the remote user and moved into place with
+/// sudo, so the target directories stay root-owned. Every path and name put
+/// into a command is shell-quoted.
+impl Transport for SshPool {
+    fn put(
+        &self,
+        host: &str,
+        local: &Path,
+        remote: &str,
+        permissions: &FilePermissions,
+    ) -> io::Result<()> {
+        let temp_file = format!("/tmp/cert_{}", Uuid::new_v4());
+        self.upload(host, local, &temp_file, 0o600)?;
+
+        let command = format!(
+            "sudo mkdir -p \"$(dirname {target})\" && \
+             sudo mv {temp} {target} && \
+             sudo chown {owner} {target} && \
+             sudo chmod {mode:o} {target}",
+            target = shell_quote(remote),
+            temp = shell_quote(&temp_file),
+            owner = shell_quote(&format!("{}:{}", permissions.owner, permissions.group)),
+            mode = permissions.mode,
+        );
+        if let Err(e) = SshPool::exec(self, host, &command) {
+            let _ = SshPool::exec(self, host, &format!("rm -f {}", shell_quote(&temp_file)));
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn get(&self, host: &str, remote: &str, local: &Path) -> io::Result<()> {
+        match self.download(host, remote, local) {
+            // Private keys are only readable by root, which SFTP cannot do
+            Err(SshError::Transfer { path, source, .. })
+                if path == remote && source.kind() != io::ErrorKind::NotFound =>
+            {
+                let command = format!("sudo base64 -w0 {}", shell_quote(remote));
+                let encoded = SshPool::exec(self, host, &command)?;
+                let contents = general_purpose::STANDARD
+                    .decode(encoded.trim())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                write_private(local, &contents)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    fn exec(&self, host: &str, command: &str) -> io::Result<String> {
+        Ok(SshPool::exec(self, host, command)?)
+    }
+
+    fn stat(&self, host: &str, remote: &str) -> io::Result<Option<RemoteFile>> {
+        let output = SshPool::exec(
+            self,
+            host,
+            &format!(
+                "if sudo test -e {path}; then sudo stat -c '%s %a %U %G' {path}; fi",
+                path = shell_quote(remote)
+            ),
+        )?;
+
+        let fields: Vec<&str> = output.split_whitespace().collect();
+        let parse_error = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Unexpected stat output for {} on {}: {}",
+                    remote, host, output
+                ),
+            )
+        };
+        // stat prints UNKNOWN for ids without a name
+        let name = |field: &str| Some(field.to_string()).filter(|name| name != "UNKNOWN");
+        match fields.as_slice() {
+            [] => Ok(None),
+            [size, mode, owner, group] => Ok(Some(RemoteFile {
+                size: size.parse().map_err(|_| parse_error())?,
+                mode: u32::from_str_radix(mode, 8).map_err(|_| parse_error())?,
+                owner: name(owner),
+                group: name(group),
+            })),
+            _ => Err(parse_error()),
+        }
+    }
+
+    fn checksum(&self, host: &str, remote: &str) -> io::Result<Option<String>> {
+        let output = SshPool::exec(
+            self,
+            host,
+            &format!(
+                "if sudo test -e {path}; then sudo sha256sum {path}; fi",
+                path = shell_quote(remote)
+            ),
+        )?;
+        Ok(output.split_whitespace().next().map(str::to_string))
+    }
+
+    fn copy(&self, host: &str, from: &str, to: &str) -> io::Result<()> {
+        let command = format!(
+            "sudo mkdir -p \"$(dirname {to})\" && sudo cp -p {from} {to}",
+            from = shell_quote(from),
+            to = shell_quote(to)
+        );
+        SshPool::exec(self, host, &command)?;
+        Ok(())
+    }
+
+    fn rename(&self, host: &str, from: &str, to: &str) -> io::Result<()> {
+        let command = format!(
+            "sudo mkdir -p \"$(dirname {to})\" && sudo mv -f {from} {to}",
+            from = shell_quote(from),
+            to = shell_quote(to)
+        );
+        SshPool::exec(self, host, &command)?;
+        Ok(())
+    }
+
+    fn remove(&self, host: &str, remote: &str) -> io::Result<()> {
+        SshPool::exec(self, host, &format!("sudo rm -f {}", shell_quote(remote)))?;
+        Ok(())
+    }
+
+    fn list(&self, host: &str, remote: &str) -> io::Result<Vec<String>> {
+        let output = SshPool::exec(
+            self,
+            host,
+            &format!(
+                "if sudo test -d {path}; then sudo ls -1A {path}; fi",
+                path = shell_quote(remote)
+            ),
+        )?;
+        Ok(output.lines().map(str::to_string).collect())
+    }
+}
+
+fn is_sudo_denial(stderr: &str) -> bool {
+    stderr.contains("sudo:")
+        && [
+            "password is required",
+            "terminal is required",
+            "not in the sudoers",
+            "not allowed to",
+            "incorrect password",
+        ]
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
@@ -0,0 +1,200 @@
+// src/remote/ssh_settings.rs
+//
+// How each host is reached over SSH: its user, port and credentials, and
+// the jump hosts in front of it. Cluster-wide defaults come from
+// `remote_user`, `ssh_key_path` and the `ssh` block; `ssh.nodes` overrides
+// them per host.
+use super::HostKeySettings;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Login settings of one machine. Unset fields fall back to the
+/// cluster-wide settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Login {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_path: Option<String>,
+    /// Authenticate with the identities of the running ssh-agent instead of
+    /// a key file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<bool>,
+    /// Environment variable holding the passphrase of an encrypted key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase_env: Option<String>,
+}
+
+/// One hop of a ProxyJump chain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JumpHost {
+    pub host: String,
+    #[serde(flatten)]
+    pub login: Login,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NodeSsh {
+    #[serde(flatten)]
+    pub login: Login,
+    /// Replaces `ssh.proxy_jump` for this node; empty connects directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_jump: Option<Vec<JumpHost>>,
+}
+
+/// `ssh` in cluster_config.json.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SshSettings {
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub agent: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase_env: Option<String>,
+    /// Jump hosts every node is reached through, first hop first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proxy_jump: Vec<JumpHost>,
+    /// Settings per node address.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub nodes: BTreeMap<String, NodeSsh>,
+}
+
+fn default_port() -> u16 {
+    22
+}
+
+impl Default for SshSettings {
+    fn default() -> Self {
+        Self {
+            port: default_port(),
+            agent: false,
+            passphrase_env: None,
+            proxy_jump: Vec::new(),
+            nodes: BTreeMap::new(),
+        }
+    }
+}
+
+impl SshSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let jumps = self.proxy_jump.iter().chain(
+            self.nodes
+                .values()
+                .filter_map(|node| node.proxy_jump.as_ref())
+                .flatten(),
+        );
+        for jump in jumps {
+            if jump.host.is_empty() {
+                return Err("ssh.proxy_jump: a jump host has no host".into());
+            }
+            validate_login(&format!("ssh.proxy_jump {}", jump.host), &jump.login)?;
+        }
+        for (host, node) in &self.nodes {
+            validate_login(&format!("ssh.nodes.{}", host), &node.login)?;
+        }
+        if self.port == 0 {
+            return Err("ssh.port must not be 0".into());
+        }
+        Ok(())
+    }
+
+    /// Whether `host` is reached through at least one jump host.
+    pub fn uses_jump_hosts(&self, host: &str) -> bool {
+        match self
+            .nodes
+            .get(host)
+            .and_then(|node| node.proxy_jump.as_ref())
+        {
+            Some(jumps) => !jumps.is_empty(),
+            None => !self.proxy_jump.is_empty(),
+        }
+    }
+
+    /// Moves the settings of a node given by name to its resolved address.
+    pub fn rename_node(&mut self, name: &str, address: &str) {
+        if let Some(node) = self.nodes.remove(name) {
+            self.nodes.entry(address.to_string()).or_insert(node);
+        }
+    }
+}
+
+fn validate_login(name: &str, login: &Login) -> Result<(), String> {
+    if login.port == Some(0) {
+        return Err(format!("{}: port must not be 0", name));
+    }
+    if login.user.as_deref() == Some("") {
+        return Err(format!("{}: user must not be empty", name));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Auth {
+    Agent,
+    Key {
+        path: PathBuf,
+        passphrase_env: Option<String>,
+    },
+}
+
+/// A resolved SSH login.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub auth: Auth,
+}
+
+/// Everything the SSH pool needs to reach the cluster's hosts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshOptions {
+    pub remote_user: String,
+    pub ssh_key_path: String,
+    pub ssh: SshSettings,
+    pub host_keys: HostKeySettings,
+}
+
+impl SshOptions {
+    /// The jump hosts to pass, first hop first, and the login of `host`.
+    pub fn route(&self, host: &str) -> (Vec<Endpoint>, Endpoint) {
+        let node = self.ssh.nodes.get(host);
+        let jumps = node
+            .and_then(|node| node.proxy_jump.as_ref())
+            .unwrap_or(&self.ssh.proxy_jump)
+            .iter()
+            .map(|jump| self.endpoint(&jump.host, &jump.login))
+            .collect();
+        let target = match node {
+            Some(node) => self.endpoint(host, &node.login),
+            None => self.endpoint(host, &Login::default()),
+        };
+        (jumps, target)
+    }
+
+    fn endpoint(&self, host: &str, login: &Login) -> Endpoint {
+        let auth = if login.agent.unwrap_or(self.ssh.agent) {
+            Auth::Agent
+        } else {
+            let path = login.key_path.as_deref().unwrap_or(&self.ssh_key_path);
+            Auth::Key {
+                path: PathBuf::from(shellexpand::tilde(path).to_string()),
+                passphrase_env: login
+                    .passphrase_env
+                    .clone()
+                    .or_else(|| self.ssh.passphrase_env.clone()),
+            }
+        };
+        Endpoint {
+            host: host.to_string(),
+            port: login.port.unwrap_or(self.ssh.port),
+            user: login
+                .user
+                .clone()
+                .unwrap_or_else(|| self.remote_user.clone()),
+            auth,
+        }
+    }
+}
@@ -0,0 +1,273 @@
+// src/remote/transaction.rs
+//
+// Backups and restores of the files a distribution replaces. Before the
+// staged files of a host are swapped in, the files they replace are copied to
+// `<backup_dir>/<generation>/files/...` next to a manifest recording which
+// targets existed. Restoring a generation puts every target back the way it
+// was before that distribution, removing files it had created.
+use super::{FilePermissions, Transport};
+use serde::{Deserialize, Serialize};
+use std::{fs, io};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Generations are named after the UTC time the distribution started, down
+/// to the millisecond, so they sort chronologically.
+pub const GENERATION_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackupEntry {
+    /// Absolute path of the replaced file on the host.
+    pub target: String,
+    /// Whether the target existed before; restoring removes it if not.
+    pub existed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackupManifest {
+    pub host: String,
+    pub generation: String,
+    pub created: String,
+    pub entries: Vec<BackupEntry>,
+}
+
+/// Where a file is staged before the swap. Staging next to the target keeps
+/// both on one filesystem, which is what makes the final rename atomic.
+pub fn staging_path(target: &str, generation: &str, index: usize) -> String {
+    format!("{}.starquill-{}.{}", target, generation, index)
+}
+
+fn generation_dir(backup_dir: &str, generation: &str) -> String {
+    format!("{}/{}", backup_dir.trim_end_matches('/'), generation)
+}
+
+fn backup_path(backup_dir: &str, generation: &str, target: &str) -> String {
+    format!("{}/files{}", generation_dir(backup_dir, generation), target)
+}
+
+/// Copies the current version of every target on `host` into the backup of
+/// `generation` and writes its manifest. A generation that already has a
+/// backup on the host is refused rather than overwritten.
+pub fn back_up(
+    transport: &dyn Transport,
+    host: &str,
+    backup_dir: &str,
+    generation: &str,
+    targets: &[String],
+) -> io::Result<BackupManifest> {
+    if !transport
+        .list(host, &generation_dir(backup_dir, generation))?
+        .is_empty()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Backup {} already exists on {}", generation, host),
+        ));
+    }
+
+    let mut entries: Vec<BackupEntry> = Vec::new();
+    for target in targets {
+        if entries.iter().any(|entry| &entry.target == target) {
+            continue;
+        }
+        let existed = transport.stat(host, target)?.is_some();
+        if existed {
+            transport.copy(host, target, &backup_path(backup_dir, generation, target))?;
+        }
+        entries.push(BackupEntry {
+            target: target.clone(),
+            existed,
+        });
+    }
+
+    let manifest = BackupManifest {
+        host: host.to_string(),
+        generation: generation.to_string(),
+        created: chrono::Utc::now().to_rfc3339(),
+        entries,
+    };
+    let contents = serde_json::to_vec_pretty(&manifest)?;
+    let local = std::env::temp_dir().join(format!("starquill-manifest-{}", Uuid::new_v4()));
+    fs::write(&local, contents)?;
+    let result = transport.put(
+        host,
+        &local,
+        &format!(
+            "{}/{}",
+            generation_dir(backup_dir, generation),
+            MANIFEST_FILE
+        ),
+        &FilePermissions::new("root", "root", 0o644),
+    );
+    let _ = fs::remove_file(&local);
+    result.map(|_| manifest)
+}
+
+/// Puts every target of `manifest` back as it was when the backup was
+/// taken. Each file is copied next to its target and renamed over it, so a
+/// restore that fails midway never leaves a partially written file.
+pub fn restore(
+    transport: &dyn Transport,
+    backup_dir: &str,
+    manifest: &BackupManifest,
+) -> io::Result<()> {
+    let host = &manifest.host;
+    for entry in &manifest.entries {
+        if entry.existed {
+            let staged = format!("{}.starquill-restore", entry.target);
+            transport.copy(
+                host,
+                &backup_path(backup_dir, &manifest.generation, &entry.target),
+                &staged,
+            )?;
+            transport.rename(host, &staged, &entry.target)?;
+        } else {
+            transport.remove(host, &entry.target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Backups kept on `host`, oldest first.
+pub fn list_backups(
+    transport: &dyn Transport,
+    host: &str,
+    backup_dir: &str,
+) -> io::Result<Vec<BackupManifest>> {
+    let mut generations = transport.list(host, backup_dir)?;
+    generations.sort();
+
+    let mut manifests = Vec::new();
+    for generation in generations {
+        match read_manifest(transport, host, backup_dir, &generation) {
+            Ok(manifest) => manifests.push(manifest),
+            // A directory without a manifest is a backup that never finished
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(manifests)
+}
+
+/// Restores the backup of `generation` on `host`.
+pub fn restore_generation(
+    transport: &dyn Transport,
+    host: &str,
+    backup_dir: &str,
+    generation: &str,
+) -> io::Result<BackupManifest> {
+    let manifest = read_manifest(transport, host, backup_dir, generation)?;
+    restore(transport, backup_dir, &manifest)?;
+    Ok(manifest)
+}
+
+fn read_manifest(
+    transport: &dyn Transport,
+    host: &str,
+    backup_dir: &str,
+    generation: &str,
+) -> io::Result<BackupManifest> {
+    if generation.is_empty() || generation.contains('/') || generation.starts_with('.') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid backup generation '{}'", generation),
+        ));
+    }
+
+    let remote = format!(
+        "{}/{}",
+        generation_dir(backup_dir, generation),
+        MANIFEST_FILE
+    );
+    if transport.stat(host, &remote)?.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No backup {} on {}", generation, host),
+        ));
+    }
+
+    let local = std::env::temp_dir().join(format!("starquill-manifest-{}", Uuid::new_v4()));
+    let result = transport
+        .get(host, &remote, &local)
+        .and_then(|_| fs::read(&local));
+    let _ = fs::remove_file(&local);
+    let manifest: BackupManifest = serde_json::from_slice(&result?)?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::MockTransport;
+
+    const HOST: &str = "10.0.0.1";
+    const BACKUP_DIR: &str = "/var/lib/starquill/backups";
+
+    #[test]
+    fn restore_puts_back_replaced_and_removes_created_files() {
+        let transport = MockTransport::new();
+        transport.write(HOST, "/etc/kubernetes/pki/ca.crt", "old ca");
+        let targets = vec![
+            "/etc/kubernetes/pki/ca.crt".to_string(),
+            "/etc/kubernetes/pki/apiserver.crt".to_string(),
+        ];
+
+        let generation = "20261017T083000.123Z";
+        back_up(&transport, HOST, BACKUP_DIR, generation, &targets).unwrap();
+        transport.write(HOST, "/etc/kubernetes/pki/ca.crt", "new ca");
+        transport.write(HOST, "/etc/kubernetes/pki/apiserver.crt", "new apiserver");
+
+        let backups = list_backups(&transport, HOST, BACKUP_DIR).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].generation, generation);
+
+        restore_generation(&transport, HOST, BACKUP_DIR, generation).unwrap();
+        assert_eq!(
+            transport
+                .read(HOST, "/etc/kubernetes/pki/ca.crt")
+                .as_deref(),
+            Some("old ca")
+        );
+        assert_eq!(
+            transport.read(HOST, "/etc/kubernetes/pki/apiserver.crt"),
+            None
+        );
+    }
+
+    #[test]
+    fn existing_generations_are_not_overwritten() {
+        let transport = MockTransport::new();
+        transport.write(HOST, "/etc/kubernetes/pki/ca.crt", "old ca");
+        let targets = vec!["/etc/kubernetes/pki/ca.crt".to_string()];
+        let generation = "20261017T083000.123Z";
+        back_up(&transport, HOST, BACKUP_DIR, generation, &targets).unwrap();
+
+        transport.write(HOST, "/etc/kubernetes/pki/ca.crt", "new ca");
+        let error = back_up(&transport, HOST, BACKUP_DIR, generation, &targets).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+        restore_generation(&transport, HOST, BACKUP_DIR, generation).unwrap();
+        assert_eq!(
+            transport
+                .read(HOST, "/etc/kubernetes/pki/ca.crt")
+                .as_deref(),
+            Some("old ca")
+        );
+    }
+
+    #[test]
+    fn generation_names_sort_chronologically() {
+        let name = |s: &str| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .unwrap()
+                .format(GENERATION_FORMAT)
+                .to_string()
+        };
+        let earlier = name("2026-10-17T08:30:00.123Z");
+        let later = name("2026-10-17T08:30:00.456Z");
+        assert_eq!(earlier, "20261017T083000.123Z");
+        assert!(earlier < later);
+    }
+}
@@ -0,0 +1,681 @@
+// src/state.rs
+//
+// The state database, `starquill.db` in the working directory. It holds the
+// tracker's current view of every file Starquill manages and the history
+// behind it: every certificate issued or adopted, what each distribution,
+// audit and reload found on the hosts, and the per-file results of remote
+// verification. History is only appended to, so a regenerated certificate
+// keeps the record of its predecessors. The schema is versioned through
+// SQLite's user_version and migrated when the database is opened.
+use crate::cert::verification::HostVerification;
+use crate::types::{CertTracker, CertificateStatus, HostEvent};
+use chrono::{DateTime, Utc};
+use openssl::{hash::MessageDigest, pkey::Id, x509::X509};
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+use std::{fs, io, path::Path, time::Duration};
+use utoipa::ToSchema;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+pub const DEFAULT_PATH: &str = "starquill.db";
+
+/// The tracker file the database replaces. It is imported while the
+/// database tracks nothing yet, and left in place.
+pub const LEGACY_STATUS_PATH: &str = "certificate_status.json";
+
+/// How long a statement waits for a lock held by another connection, such
+/// as the web server's or a concurrent command-line run.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Schema changes, applied in order. user_version counts those a database
+/// has; append new entries rather than editing applied ones.
+const MIGRATIONS: &[&str] = &[SCHEMA_V1, SCHEMA_V2];
+
+const SCHEMA_V1: &str = "CREATE TABLE tracked_files (
+        cert_type TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        hosts TEXT NOT NULL,
+        generated INTEGER NOT NULL,
+        distributed INTEGER,
+        verified INTEGER,
+        last_verified INTEGER,
+        checksums TEXT NOT NULL,
+        reloads TEXT NOT NULL
+    );
+    CREATE TABLE certificates (
+        id INTEGER PRIMARY KEY,
+        fingerprint TEXT NOT NULL,
+        serial TEXT NOT NULL,
+        subject TEXT NOT NULL,
+        issuer TEXT NOT NULL,
+        profile TEXT,
+        not_before INTEGER NOT NULL,
+        not_after INTEGER NOT NULL,
+        key_algorithm TEXT NOT NULL,
+        cert_path TEXT NOT NULL,
+        key_path TEXT,
+        recorded INTEGER NOT NULL,
+        UNIQUE (fingerprint, cert_path)
+    );
+    CREATE TABLE host_events (
+        id INTEGER PRIMARY KEY,
+        cert_type TEXT NOT NULL,
+        host TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        sha256 TEXT,
+        matches INTEGER,
+        component TEXT,
+        state TEXT,
+        error TEXT,
+        at INTEGER NOT NULL
+    );
+    CREATE INDEX host_events_by_host ON host_events (host, at);
+    CREATE TABLE verification_results (
+        id INTEGER PRIMARY KEY,
+        host TEXT NOT NULL,
+        cert_type TEXT NOT NULL,
+        remote TEXT NOT NULL,
+        valid INTEGER NOT NULL,
+        problems TEXT NOT NULL,
+        checked INTEGER NOT NULL,
+        UNIQUE (host, remote, checked)
+    );";
+
+/// Distribution events record the file and the outcome of its host.
+const SCHEMA_V2: &str = "ALTER TABLE host_events ADD COLUMN remote TEXT;
+    ALTER TABLE host_events ADD COLUMN host_state TEXT;";
+
+/// A certificate as it was issued or adopted.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CertificateRecord {
+    /// SHA-256 of the DER encoding.
+    pub fingerprint: String,
+    pub serial: String,
+    pub subject: String,
+    pub issuer: String,
+    /// The profile it was issued from; `None` for adopted certificates.
+    pub profile: Option<String>,
+    pub not_before: String,
+    pub not_after: String,
+    /// As named in `key_algorithms`, e.g. `ecdsa-p256`.
+    pub key_algorithm: String,
+    pub cert_path: String,
+    pub key_path: Option<String>,
+    pub recorded: String,
+}
+
+impl CertificateRecord {
+    /// Reads the certificate at `cert_path`.
+    pub fn read(
+        cert_path: &str,
+        key_path: Option<&str>,
+        profile: Option<&str>,
+    ) -> io::Result<Self> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        let cert = X509::from_pem(&fs::read(cert_path)?).map_err(|e| invalid(e.to_string()))?;
+        let der = cert.to_der().map_err(|e| invalid(e.to_string()))?;
+        let (_, parsed) = X509Certificate::from_der(&der).map_err(|e| invalid(e.to_string()))?;
+
+        let fingerprint = cert
+            .digest(MessageDigest::sha256())
+            .map(|digest| hex::encode(&*digest))
+            .map_err(|e| invalid(e.to_string()))?;
+        let serial = cert
+            .serial_number()
+            .to_bn()
+            .and_then(|serial| serial.to_hex_str().map(|hex| hex.to_string()))
+            .map_err(|e| invalid(e.to_string()))?;
+        let key_algorithm = match cert.public_key() {
+            Ok(key) => match key.id() {
+                Id::RSA => format!("rsa-{}", key.bits()),
+                Id::EC if key.bits() > 256 => "ecdsa-p384".to_string(),
+                Id::EC => "ecdsa-p256".to_string(),
+                Id::ED25519 => "ed25519".to_string(),
+                _ => "unknown".to_string(),
+            },
+            Err(_) => "unknown".to_string(),
+        };
+
+        Ok(Self {
+            fingerprint,
+            serial,
+            subject: parsed.subject().to_string(),
+            issuer: parsed.issuer().to_string(),
+            profile: profile.map(str::to_string),
+            not_before: rfc3339(parsed.validity().not_before.timestamp()),
+            not_after: rfc3339(parsed.validity().not_after.timestamp()),
+            key_algorithm,
+            cert_path: cert_path.to_string(),
+            key_path: key_path.map(str::to_string),
+            recorded: Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// A checksum comparison, distribution or reload on a host.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HostEventRecord {
+    pub cert_type: String,
+    pub host: String,
+    /// `checksum`, `distribution` or `reload`.
+    pub kind: String,
+    /// The distributed file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// State of the reload or the distributed file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// How the transaction of the host finished, for distributions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub at: String,
+}
+
+/// One file of a remote verification.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct VerificationRecord {
+    pub host: String,
+    pub cert_type: String,
+    pub remote: String,
+    pub valid: bool,
+    pub problems: Vec<String>,
+    pub checked: String,
+}
+
+/// The recorded history, newest first.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StateHistory {
+    pub certificates: Vec<CertificateRecord>,
+    pub events: Vec<HostEventRecord>,
+    pub verifications: Vec<VerificationRecord>,
+}
+
+pub struct StateStore {
+    conn: Connection,
+}
+
+impl StateStore {
+    /// Opens the database at `path`, creating it and applying pending
+    /// migrations.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut conn = Connection::open(path).map_err(to_io)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(to_io)?;
+        // Readers no longer block the writer and the writer no longer
+        // blocks readers; the mode is stored in the database file
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(to_io)?;
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(to_io)?;
+        if version > MIGRATIONS.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} has schema version {}, newer than this build's {}",
+                    path.display(),
+                    version,
+                    MIGRATIONS.len()
+                ),
+            ));
+        }
+
+        for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction().map_err(to_io)?;
+            tx.execute_batch(migration).map_err(to_io)?;
+            tx.pragma_update(None, "user_version", applied + 1)
+                .map_err(to_io)?;
+            tx.commit().map_err(to_io)?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Whether any file is tracked yet.
+    pub fn is_empty(&self) -> io::Result<bool> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM tracked_files", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|count| count == 0)
+            .map_err(to_io)
+    }
+
+    /// Imports the tracker file at `legacy`, along with the `certificates`
+    /// it tracks, while nothing is tracked yet. Returns the imported tracker,
+    /// or `None` when there was nothing to import.
+    pub fn import_legacy(
+        &mut self,
+        legacy: &Path,
+        certificates: impl FnOnce(&CertTracker) -> Vec<CertificateRecord>,
+    ) -> io::Result<Option<CertTracker>> {
+        if !self.is_empty()? || !legacy.exists() {
+            return Ok(None);
+        }
+        let tracker: CertTracker = serde_json::from_str(&fs::read_to_string(legacy)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.save(&tracker, &certificates(&tracker), &[])?;
+        Ok(Some(tracker))
+    }
+
+    pub fn load_tracker(&self) -> io::Result<CertTracker> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT cert_type, path, hosts, generated, distributed, verified,
+                        last_verified, checksums, reloads
+                 FROM tracked_files ORDER BY position",
+            )
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(CertificateStatus {
+                    cert_type: row.get(0)?,
+                    path: row.get(1)?,
+                    hosts: json_column(row, 2)?,
+                    generated: timestamp(row.get(3)?),
+                    distributed: row.get::<_, Option<i64>>(4)?.map(timestamp),
+                    verified: row.get(5)?,
+                    last_verified: row.get::<_, Option<i64>>(6)?.map(timestamp),
+                    checksums: json_column(row, 7)?,
+                    reloads: json_column(row, 8)?,
+                })
+            })
+            .map_err(to_io)?;
+
+        let mut tracker = CertTracker::new();
+        for cert in rows {
+            tracker.certificates.push(cert.map_err(to_io)?);
+        }
+        Ok(tracker)
+    }
+
+    /// Replaces the tracked view with `tracker` and appends its pending host
+    /// events, `certificates` and `verification` to the history, all in one
+    /// transaction. Certificates and verification results already recorded
+    /// are skipped; an adopted certificate issued later gains its profile.
+    pub fn save(
+        &mut self,
+        tracker: &CertTracker,
+        certificates: &[CertificateRecord],
+        verification: &[HostVerification],
+    ) -> io::Result<()> {
+        let tx = self.conn.transaction().map_err(to_io)?;
+
+        tx.execute("DELETE FROM tracked_files", []).map_err(to_io)?;
+        for (position, cert) in tracker.certificates.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO tracked_files (cert_type, position, path, hosts, generated,
+                     distributed, verified, last_verified, checksums, reloads)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    cert.cert_type,
+                    position as i64,
+                    cert.path,
+                    to_json(&cert.hosts)?,
+                    cert.generated.timestamp(),
+                    cert.distributed.map(|time| time.timestamp()),
+                    cert.verified,
+                    cert.last_verified.map(|time| time.timestamp()),
+                    to_json(&cert.checksums)?,
+                    to_json(&cert.reloads)?,
+                ],
+            )
+            .map_err(to_io)?;
+        }
+
+        for (cert_type, host, event) in &tracker.events {
+            match event {
+                HostEvent::Checksum(checksum) => tx.execute(
+                    "INSERT INTO host_events (cert_type, host, kind, sha256, matches, at)
+                     VALUES (?1, ?2, 'checksum', ?3, ?4, ?5)",
+                    params![
+                        cert_type,
+                        host,
+                        checksum.sha256,
+                        checksum.matches,
+                        checksum.checked.timestamp()
+                    ],
+                ),
+                HostEvent::Distribution(distribution) => tx.execute(
+                    "INSERT INTO host_events
+                         (cert_type, host, kind, remote, state, host_state, error, at)
+                     VALUES (?1, ?2, 'distribution', ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        cert_type,
+                        host,
+                        distribution.remote,
+                        variant_name(distribution.state),
+                        variant_name(distribution.host_state),
+                        distribution.error,
+                        distribution.distributed.timestamp()
+                    ],
+                ),
+                HostEvent::Reload(reload) => tx.execute(
+                    "INSERT INTO host_events (cert_type, host, kind, component, state, error, at)
+                     VALUES (?1, ?2, 'reload', ?3, ?4, ?5, ?6)",
+                    params![
+                        cert_type,
+                        host,
+                        reload.component,
+                        variant_name(reload.state),
+                        reload.error,
+                        reload.reloaded.timestamp()
+                    ],
+                ),
+            }
+            .map_err(to_io)?;
+        }
+
+        for record in certificates {
+            tx.execute(
+                "INSERT INTO certificates (fingerprint, serial, subject, issuer, profile,
+                     not_before, not_after, key_algorithm, cert_path, key_path, recorded)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                 ON CONFLICT (fingerprint, cert_path) DO UPDATE SET
+                     profile = COALESCE(certificates.profile, excluded.profile),
+                     key_path = COALESCE(certificates.key_path, excluded.key_path)",
+                params![
+                    record.fingerprint,
+                    record.serial,
+                    record.subject,
+                    record.issuer,
+                    record.profile,
+                    seconds(&record.not_before),
+                    seconds(&record.not_after),
+                    record.key_algorithm,
+                    record.cert_path,
+                    record.key_path,
+                    seconds(&record.recorded),
+                ],
+            )
+            .map_err(to_io)?;
+        }
+
+        for host in verification {
+            for file in &host.files {
+                tx.execute(
+                    "INSERT OR IGNORE INTO verification_results
+                         (host, cert_type, remote, valid, problems, checked)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        host.host,
+                        file.cert_type,
+                        file.remote,
+                        file.valid(),
+                        to_json(&file.problems)?,
+                        seconds(&host.checked),
+                    ],
+                )
+                .map_err(to_io)?;
+            }
+        }
+
+        tx.commit().map_err(to_io)
+    }
+
+    /// The last `limit` entries of each history, newest first.
+    pub fn history(&self, limit: usize) -> io::Result<StateHistory> {
+        let limit = limit as i64;
+
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT fingerprint, serial, subject, issuer, profile, not_before, not_after,
+                        key_algorithm, cert_path, key_path, recorded
+                 FROM certificates ORDER BY recorded DESC, id DESC LIMIT ?1",
+            )
+            .map_err(to_io)?;
+        let certificates = statement
+            .query_map([limit], |row| {
+                Ok(CertificateRecord {
+                    fingerprint: row.get(0)?,
+                    serial: row.get(1)?,
+                    subject: row.get(2)?,
+                    issuer: row.get(3)?,
+                    profile: row.get(4)?,
+                    not_before: rfc3339(row.get(5)?),
+                    not_after: rfc3339(row.get(6)?),
+                    key_algorithm: row.get(7)?,
+                    cert_path: row.get(8)?,
+                    key_path: row.get(9)?,
+                    recorded: rfc3339(row.get(10)?),
+                })
+            })
+            .map_err(to_io)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_io)?;
+
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT cert_type, host, kind, remote, sha256, matches, component, state,
+                     host_state, error, at
+                 FROM host_events ORDER BY at DESC, id DESC LIMIT ?1",
+            )
+            .map_err(to_io)?;
+        let events = statement
+            .query_map([limit], |row| {
+                Ok(HostEventRecord {
+                    cert_type: row.get(0)?,
+                    host: row.get(1)?,
+                    kind: row.get(2)?,
+                    remote: row.get(3)?,
+                    sha256: row.get(4)?,
+                    matches: row.get(5)?,
+                    component: row.get(6)?,
+                    state: row.get(7)?,
+                    host_state: row.get(8)?,
+                    error: row.get(9)?,
+                    at: rfc3339(row.get(10)?),
+                })
+            })
+            .map_err(to_io)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_io)?;
+
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT host, cert_type, remote, valid, problems, checked
+                 FROM verification_results ORDER BY checked DESC, id DESC LIMIT ?1",
+            )
+            .map_err(to_io)?;
+        let verifications = statement
+            .query_map([limit], |row| {
+                Ok(VerificationRecord {
+                    host: row.get(0)?,
+                    cert_type: row.get(1)?,
+                    remote: row.get(2)?,
+                    valid: row.get(3)?,
+                    problems: json_column(row, 4)?,
+                    checked: rfc3339(row.get(5)?),
+                })
+            })
+            .map_err(to_io)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_io)?;
+
+        Ok(StateHistory {
+            certificates,
+            events,
+            verifications,
+        })
+    }
+}
+
+fn to_io(error: rusqlite::Error) -> io::Error {
+    io::Error::other(format!("State database error: {}", error))
+}
+
+fn to_json<T: Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The serialized name of a unit enum variant, e.g. `rolled_back`.
+fn variant_name<T: Serialize>(value: T) -> Option<String> {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+}
+
+fn json_column<T: serde::de::DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(index)?;
+    serde_json::from_str(&text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn timestamp(seconds: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(seconds, 0).unwrap_or_default()
+}
+
+fn rfc3339(seconds: i64) -> String {
+    timestamp(seconds).to_rfc3339()
+}
+
+fn seconds(rfc3339: &str) -> i64 {
+    DateTime::parse_from_rfc3339(rfc3339)
+        .map(|time| time.timestamp())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    /// A directory for a database and tracker file, removed again on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("starquill-test-{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const LEGACY: &str = r#"{"certificates": [{
+        "cert_type": "apiserver",
+        "generated": 1700000000,
+        "distributed": 1700000600,
+        "path": "certs/apiserver/apiserver.crt",
+        "hosts": ["10.0.0.1"],
+        "verified": true,
+        "last_verified": null
+    }]}"#;
+
+    fn user_version(store: &StateStore) -> usize {
+        store
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn version_1_databases_are_migrated_keeping_their_history() {
+        let dir = TempDir::new();
+        let path = dir.path("starquill.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute(
+            "INSERT INTO host_events (cert_type, host, kind, sha256, matches, at)
+             VALUES ('apiserver', '10.0.0.1', 'checksum', 'abc', 1, 1700000000)",
+            [],
+        )
+        .unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        drop(conn);
+
+        let store = StateStore::open(&path).unwrap();
+        assert_eq!(user_version(&store), MIGRATIONS.len());
+        let events = store.history(10).unwrap().events;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sha256.as_deref(), Some("abc"));
+        assert_eq!(events[0].remote, None);
+        assert_eq!(events[0].host_state, None);
+        drop(store);
+
+        // Opening a current database applies nothing
+        let store = StateStore::open(&path).unwrap();
+        assert_eq!(user_version(&store), MIGRATIONS.len());
+        assert_eq!(store.history(10).unwrap().events.len(), 1);
+    }
+
+    #[test]
+    fn databases_of_newer_builds_are_refused() {
+        let dir = TempDir::new();
+        let path = dir.path("starquill.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        drop(conn);
+
+        let error = StateStore::open(&path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn the_legacy_tracker_is_imported_once() {
+        let dir = TempDir::new();
+        let legacy = dir.path(LEGACY_STATUS_PATH);
+        let mut store = StateStore::open(&dir.path("starquill.db")).unwrap();
+
+        // Nothing to import yet
+        assert!(store
+            .import_legacy(&legacy, |_| Vec::new())
+            .unwrap()
+            .is_none());
+
+        fs::write(&legacy, LEGACY).unwrap();
+        let imported = store.import_legacy(&legacy, |_| Vec::new()).unwrap();
+        assert_eq!(imported.unwrap().certificates.len(), 1);
+        let tracker = store.load_tracker().unwrap();
+        let cert = &tracker.certificates[0];
+        assert_eq!(cert.cert_type, "apiserver");
+        assert_eq!(cert.hosts, ["10.0.0.1"]);
+        assert_eq!(cert.distributed.unwrap().timestamp(), 1700000600);
+        assert_eq!(cert.verified, Some(true));
+        // The file is left in place
+        assert!(legacy.exists());
+
+        // Once the database tracks files, the file is ignored
+        fs::write(&legacy, r#"{"certificates": []}"#).unwrap();
+        assert!(store
+            .import_legacy(&legacy, |_| Vec::new())
+            .unwrap()
+            .is_none());
+        assert_eq!(store.load_tracker().unwrap().certificates.len(), 1);
+    }
+
+    #[test]
+    fn unreadable_legacy_trackers_are_not_imported() {
+        let dir = TempDir::new();
+        let legacy = dir.path(LEGACY_STATUS_PATH);
+        fs::write(&legacy, "{").unwrap();
+        let mut store = StateStore::open(&dir.path("starquill.db")).unwrap();
+
+        let error = store.import_legacy(&legacy, |_| Vec::new()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(store.is_empty().unwrap());
+    }
+}
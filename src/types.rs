@@ -4,6 +4,12 @@ use clap::Parser;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::cert::revocation::{RevocationEntry, RevocationReason};
+use crate::remote::{
+    BackupManifest, HostState, IntegrityCheck, ReloadOutcome, ReloadState, TransferState,
+};
+use std::collections::BTreeMap;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
@@ -21,6 +27,8 @@ pub enum AppMode {
     Normal,
     EditConfig,
     Confirmation,
+    Revoke,
+    Restore,
 }
 
 #[derive(Clone)]
@@ -37,6 +45,62 @@ pub enum ConfirmationCallback {
     RootCA,
     AutomateAll, // Add other confirmation types as needed
     VerifyChains,
+    Revoke {
+        ca: String,
+        serial: String,
+        reason: RevocationReason,
+    },
+    Restore {
+        host: String,
+        generation: String,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RevokeStep {
+    SelectCertificate,
+    SelectReason,
+}
+
+/// State of the revocation picker: first a certificate, then a reason.
+#[derive(Clone)]
+pub struct RevocationPicker {
+    pub candidates: Vec<(String, RevocationEntry)>,
+    pub selected: usize,
+    pub reason: usize,
+    pub step: RevokeStep,
+}
+
+impl RevocationPicker {
+    pub fn new(candidates: Vec<(String, RevocationEntry)>) -> Self {
+        Self {
+            candidates,
+            selected: 0,
+            reason: 0,
+            step: RevokeStep::SelectCertificate,
+        }
+    }
+
+    pub fn selected_reason(&self) -> RevocationReason {
+        RevocationReason::ALL[self.reason]
+    }
+}
+
+/// State of the backup picker: one entry per host and generation, newest
+/// first.
+#[derive(Clone)]
+pub struct RestorePicker {
+    pub candidates: Vec<BackupManifest>,
+    pub selected: usize,
+}
+
+impl RestorePicker {
+    pub fn new(candidates: Vec<BackupManifest>) -> Self {
+        Self {
+            candidates,
+            selected: 0,
+        }
+    }
 }
 
 pub enum ScrollDirection {
@@ -60,17 +124,67 @@ pub struct CertificateStatus {
     pub verified: Option<bool>,
     #[serde(with = "chrono::serde::ts_seconds_option")]
     pub last_verified: Option<DateTime<Utc>>,
+    /// What the last post-copy check or audit found on each host.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<String, HostChecksum>,
+    /// Reload hooks this certificate triggered on each host.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reloads: BTreeMap<String, Vec<HostReload>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HostChecksum {
+    /// SHA-256 of the file on the host, `None` when it was missing.
+    pub sha256: Option<String>,
+    pub matches: bool,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub checked: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HostReload {
+    pub component: String,
+    pub state: ReloadState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub reloaded: DateTime<Utc>,
+}
+
+/// Where one file of a distribution ended up and how its host's
+/// transaction finished.
+#[derive(Clone)]
+pub struct HostDistribution {
+    pub remote: String,
+    pub state: TransferState,
+    pub host_state: HostState,
+    pub error: Option<String>,
+    pub distributed: DateTime<Utc>,
+}
+
+/// What a check, distribution or reload found on a host, kept for the state
+/// database's history until the tracker is next saved.
+#[derive(Clone)]
+pub enum HostEvent {
+    Checksum(HostChecksum),
+    Distribution(HostDistribution),
+    Reload(HostReload),
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CertTracker {
     pub certificates: Vec<CertificateStatus>,
+    /// Host events since the last save, oldest first, with the certificate
+    /// and host they happened to.
+    #[serde(skip)]
+    pub events: Vec<(String, String, HostEvent)>,
 }
 
 impl CertTracker {
     pub fn new() -> Self {
         Self {
             certificates: Vec::new(),
+            events: Vec::new(),
         }
     }
 
@@ -85,6 +199,8 @@ impl CertTracker {
             existing.distributed = None;
             existing.path = path.to_string();
             existing.hosts = hosts;
+            existing.checksums.clear();
+            existing.reloads.clear();
         } else {
             self.certificates.push(CertificateStatus {
                 cert_type: cert_type.to_string(),
@@ -94,6 +210,8 @@ impl CertTracker {
                 hosts,
                 verified: None,
                 last_verified: None,
+                checksums: BTreeMap::new(),
+                reloads: BTreeMap::new(),
             });
         }
     }
@@ -107,6 +225,81 @@ impl CertTracker {
         }
     }
 
+    /// Records the checksum comparison of a certificate on `host`. A file
+    /// that is missing or differs fails the certificate and queues it for
+    /// distribution again.
+    pub fn record_checksum(&mut self, cert_type: &str, host: &str, check: &IntegrityCheck) {
+        if let Some(cert) = self
+            .certificates
+            .iter_mut()
+            .find(|c| c.cert_type == cert_type)
+        {
+            let checksum = HostChecksum {
+                sha256: check.actual.clone(),
+                matches: check.matches(),
+                checked: Utc::now(),
+            };
+            cert.checksums.insert(host.to_string(), checksum.clone());
+            self.events.push((
+                cert_type.to_string(),
+                host.to_string(),
+                HostEvent::Checksum(checksum),
+            ));
+            if !check.matches() {
+                cert.verified = Some(false);
+                cert.distributed = None;
+            }
+        }
+    }
+
+    /// Records the outcome of a reload hook the certificate triggered on
+    /// `host`, replacing the previous outcome of the same component.
+    pub fn record_reload(&mut self, cert_type: &str, host: &str, outcome: &ReloadOutcome) {
+        if let Some(cert) = self
+            .certificates
+            .iter_mut()
+            .find(|c| c.cert_type == cert_type)
+        {
+            let reload = HostReload {
+                component: outcome.component.clone(),
+                state: outcome.state,
+                error: outcome.error.clone(),
+                reloaded: Utc::now(),
+            };
+            let reloads = cert.reloads.entry(host.to_string()).or_default();
+            reloads.retain(|old| old.component != outcome.component);
+            reloads.push(reload.clone());
+            self.events.push((
+                cert_type.to_string(),
+                host.to_string(),
+                HostEvent::Reload(reload),
+            ));
+        }
+    }
+
+    /// Records the outcome of one file of a distribution to `host`. Unlike
+    /// checksums and reloads these only go to the history, so files of
+    /// untracked types are recorded too.
+    pub fn record_distribution(&mut self, cert_type: &str, host: &str, outcome: HostDistribution) {
+        self.events.push((
+            cert_type.to_string(),
+            host.to_string(),
+            HostEvent::Distribution(outcome),
+        ));
+    }
+
+    /// Queues a certificate for distribution again, e.g. after an older
+    /// copy was restored on one of its hosts.
+    pub fn mark_undistributed(&mut self, cert_type: &str) {
+        if let Some(cert) = self
+            .certificates
+            .iter_mut()
+            .find(|c| c.cert_type == cert_type)
+        {
+            cert.distributed = None;
+        }
+    }
+
     pub fn mark_distributed(&mut self, cert_type: &str) {
         if let Some(cert) = self
             .certificates
@@ -117,10 +310,13 @@ impl CertTracker {
         }
     }
 
+    /// Certificates waiting to be copied to their hosts. Entries without
+    /// hosts, such as the admin credential, stay on this machine.
     pub fn get_undistributed(&self) -> Vec<&CertificateStatus> {
         self.certificates
             .iter()
             .filter(|cert| cert.distributed.is_none())
+            .filter(|cert| !cert.hosts.is_empty())
             .filter(|cert|!cert.cert_type.contains("root-ca"))
             .collect()
     }
@@ -9,6 +9,7 @@ use ratatui::{
 };
 
 use super::LoadingState;
+use crate::remote::HostKey;
 
 pub fn render_loading(f: &mut Frame, state: &LoadingState) {
     let size = f.area();
@@ -111,3 +112,42 @@ pub fn render_ssh_status(
     area
 }
 
+
+// Trust-on-first-use prompt for a node whose host key is not pinned yet
+pub fn render_host_key_prompt(f: &mut Frame, host: &str, key: &HostKey) {
+    let size = f.area();
+
+    let width = (size.width as f32 * 0.8) as u16;
+    let height = 9;
+    let x = (size.width - width) / 2;
+    let y = size.height.saturating_sub(height) / 2;
+
+    let area = Rect::new(x, y, width, height.min(size.height));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Unknown SSH Host Key ");
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Host:        "),
+            Span::styled(host, Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(vec![Span::raw("Key type:    "), Span::raw(&key.key_type)]),
+        Line::from(vec![
+            Span::raw("Fingerprint: "),
+            Span::styled(&key.fingerprint, Style::default().fg(Color::Yellow)),
+        ]),
+        Line::from(""),
+        Line::from("Compare the fingerprint with `ssh-keygen -lf` on the node before trusting it."),
+        Line::from(vec![
+            Span::styled("y", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::raw(" trust and pin   "),
+            Span::styled("n", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+            Span::raw(" refuse"),
+        ]),
+    ];
+
+    let text = Paragraph::new(lines).block(block);
+    f.render_widget(text, area);
+}
@@ -41,6 +41,7 @@ impl LoadingState {
                 ("Initializing web server...".to_string(), StepStatus::Pending),
                 ("Verifying SSH connections...".to_string(), StepStatus::Pending),
                 ("Initializing certificate manager...".to_string(), StepStatus::Pending),
+                ("Running node preflight checks...".to_string(), StepStatus::Pending),
             ],
             current_step: 0,
         }
@@ -1,6 +1,8 @@
 use super::styles::*;
 use crate::app::CertManager;
-use crate::types::{ActiveSection, AppMode};
+use crate::cert::revocation::RevocationReason;
+use crate::remote::CheckStatus;
+use crate::types::{ActiveSection, AppMode, RevokeStep};
 use crate::utils::constants::BACKGROUND_ART;
 use ratatui::layout::Margin;
 use ratatui::widgets::{Scrollbar, ScrollbarOrientation, ScrollbarState};
@@ -21,7 +23,7 @@ pub fn render_all(f: &mut Frame, cert_manager: &CertManager) {
         .margin(1)
         .constraints([
             Constraint::Length(3),      // Title
-            Constraint::Length(7),      // Status/Config
+            Constraint::Length(8),      // Status/Config
             Constraint::Percentage(40), // Menu + Cert Status section
             Constraint::Percentage(40), // Logs + Trust Info section
             Constraint::Length(3),      // Help
@@ -65,6 +67,14 @@ pub fn render_all(f: &mut Frame, cert_manager: &CertManager) {
     if cert_manager.mode == AppMode::Confirmation {
         render_confirmation_dialog(f, f.area(), cert_manager);
     }
+
+    if cert_manager.mode == AppMode::Revoke {
+        render_revocation_picker(f, f.area(), cert_manager);
+    }
+
+    if cert_manager.mode == AppMode::Restore {
+        render_restore_picker(f, f.area(), cert_manager);
+    }
 }
 
 fn render_title(f: &mut Frame, area: Rect) {
@@ -463,6 +473,14 @@ pub fn render_help(f: &mut Frame, area: Rect, mode: &AppMode) {
             Span::styled("Esc", Style::default().fg(Color::Red)),
             Span::raw(": Cancel"),
         ],
+        AppMode::Revoke | AppMode::Restore => vec![
+            Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
+            Span::raw(": Navigate | "),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::raw(": Select | "),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::raw(": Back"),
+        ],
         AppMode::Normal => vec![
             Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
             Span::raw(": Navigate | "),
@@ -542,6 +560,146 @@ pub fn render_confirmation_dialog(f: &mut Frame, area: Rect, cert_manager: &Cert
     }
 }
 
+pub fn render_revocation_picker(f: &mut Frame, area: Rect, cert_manager: &CertManager) {
+    if let Some(picker) = &cert_manager.revocation_picker {
+        let width = area.width.min(80);
+        let height = area.height.min(16);
+        let dialog_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let (title, items, selected): (&str, Vec<ListItem>, usize) = match picker.step {
+            RevokeStep::SelectCertificate => (
+                "Revoke Certificate",
+                picker
+                    .candidates
+                    .iter()
+                    .map(|(ca, entry)| {
+                        ListItem::new(Line::from(vec![
+                            Span::styled(
+                                format!("{:<32}", entry.name()),
+                                Style::default().fg(Color::White),
+                            ),
+                            Span::styled(
+                                format!(" {} ", ca),
+                                Style::default().fg(Color::Cyan),
+                            ),
+                            Span::styled(
+                                format!("expires {}", entry.expires.format("%Y-%m-%d")),
+                                Style::default().fg(Color::Gray),
+                            ),
+                        ]))
+                    })
+                    .collect(),
+                picker.selected,
+            ),
+            RevokeStep::SelectReason => (
+                "Revocation Reason",
+                RevocationReason::ALL
+                    .iter()
+                    .map(|reason| ListItem::new(reason.to_string()))
+                    .collect(),
+                picker.reason,
+            ),
+        };
+
+        // Keep the selection in view
+        let visible = dialog_area.height.saturating_sub(2) as usize;
+        let offset = selected.saturating_sub(visible.saturating_sub(1));
+        let items: Vec<ListItem> = items
+            .into_iter()
+            .enumerate()
+            .skip(offset)
+            .map(|(i, item)| {
+                if i == selected {
+                    item.style(
+                        Style::default()
+                            .bg(Color::Cyan)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    item
+                }
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .style(Style::default().bg(Color::Black)),
+        );
+
+        f.render_widget(Clear, dialog_area);
+        f.render_widget(list, dialog_area);
+    }
+}
+
+pub fn render_restore_picker(f: &mut Frame, area: Rect, cert_manager: &CertManager) {
+    if let Some(picker) = &cert_manager.restore_picker {
+        let width = area.width.min(80);
+        let height = area.height.min(16);
+        let dialog_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        // Keep the selection in view
+        let visible = dialog_area.height.saturating_sub(2) as usize;
+        let offset = picker.selected.saturating_sub(visible.saturating_sub(1));
+        let items: Vec<ListItem> = picker
+            .candidates
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .map(|(i, manifest)| {
+                let item = ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<24}", manifest.host),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled(
+                        format!(" {} ", manifest.generation),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        format!("{} files", manifest.entries.len()),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ]));
+                if i == picker.selected {
+                    item.style(
+                        Style::default()
+                            .bg(Color::Cyan)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    item
+                }
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .title("Restore Backup")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .style(Style::default().bg(Color::Black)),
+        );
+
+        f.render_widget(Clear, dialog_area);
+        f.render_widget(list, dialog_area);
+    }
+}
+
 pub fn render_trust_info(f: &mut Frame, area: Rect, cert_manager: &CertManager) {
     let block = Block::default()
         .title("Trust Validation")
@@ -555,8 +713,8 @@ pub fn render_trust_info(f: &mut Frame, area: Rect, cert_manager: &CertManager)
     let inner_area = block.inner(area);
     f.render_widget(block, area);
 
-    let content = if let Some(store) = &cert_manager.trust_store {
-        let store_vec: Vec<_> = store.iter().collect();
+    let content = if cert_manager.trust_info_len() > 0 {
+        let store_vec: Vec<_> = cert_manager.trust_store.iter().flatten().collect();
         let mut lines = Vec::new();
 
         for (node, trust_info) in store_vec.iter().skip(cert_manager.trust_info_scroll) {
@@ -631,6 +789,121 @@ pub fn render_trust_info(f: &mut Frame, area: Rect, cert_manager: &CertManager)
             ]));
             lines.push(Line::from(vec![Span::raw("")])); // Add spacing between nodes
         }
+
+        // Per-host report of the last remote verification
+        let skipped = cert_manager.trust_info_scroll.saturating_sub(store_vec.len());
+        for host in cert_manager.verification.iter().skip(skipped) {
+            let verified = host.files.iter().filter(|file| file.valid()).count();
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("Host: {}", host.host),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("  {}/{} files verified", verified, host.files.len()),
+                    Style::default().fg(if host.valid { Color::Green } else { Color::Red }),
+                ),
+            ]));
+            for file in host.files.iter().filter(|file| !file.valid()) {
+                for problem in &file.problems {
+                    lines.push(Line::from(vec![
+                        Span::raw(format!("  {}: ", file.remote)),
+                        Span::styled(problem.clone(), Style::default().fg(Color::Red)),
+                    ]));
+                }
+            }
+            let expiring: Vec<String> = host
+                .files
+                .iter()
+                .filter(|file| file.days_left.is_some_and(|days| (0..30).contains(&days)))
+                .map(|file| format!("{} ({}d)", file.cert_type, file.days_left.unwrap_or(0)))
+                .collect();
+            if !expiring.is_empty() {
+                lines.push(Line::from(vec![Span::styled(
+                    format!("  Expiring: {}", expiring.join(", ")),
+                    Style::default().fg(Color::Yellow),
+                )]));
+            }
+            lines.push(Line::from(vec![
+                Span::raw("  Last Checked: "),
+                Span::styled(host.checked.clone(), Style::default().fg(Color::Gray)),
+            ]));
+            lines.push(Line::from(vec![Span::raw("")]));
+        }
+
+        // What the components served when last probed
+        let skipped = skipped.saturating_sub(cert_manager.verification.len());
+        for endpoint in cert_manager.probes.iter().skip(skipped) {
+            let status = match (endpoint.reachable, endpoint.valid()) {
+                (false, _) => ("Unreachable", Color::Red),
+                (true, true) => ("Valid", Color::Green),
+                (true, false) => ("Invalid", Color::Red),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "Endpoint: {}:{} ({})",
+                        endpoint.host,
+                        endpoint.port,
+                        endpoint.component.as_str()
+                    ),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::styled(status.0, Style::default().fg(status.1)),
+            ]));
+            if let Some(leaf) = endpoint.chain.first() {
+                lines.push(Line::from(vec![Span::raw(format!(
+                    "  Serving: {} (expires {})",
+                    leaf.subject, leaf.not_after
+                ))]));
+            }
+            for problem in &endpoint.problems {
+                lines.push(Line::from(vec![Span::styled(
+                    format!("  {}", problem),
+                    Style::default().fg(Color::Red),
+                )]));
+            }
+            lines.push(Line::from(vec![Span::raw("")]));
+        }
+
+        // Per-node report of the last preflight checks
+        let skipped = skipped.saturating_sub(cert_manager.probes.len());
+        for report in cert_manager.preflight.iter().skip(skipped) {
+            let color = |status: CheckStatus| match status {
+                CheckStatus::Pass => Color::Green,
+                CheckStatus::Warn => Color::Yellow,
+                CheckStatus::Fail => Color::Red,
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("Preflight: {}", report.host),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::styled(
+                    report.status.as_str().to_uppercase(),
+                    Style::default().fg(color(report.status)),
+                ),
+            ]));
+            for check in report.checks.iter().filter(|check| check.status != CheckStatus::Pass) {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("  {}: ", check.name)),
+                    Span::styled(check.detail.clone(), Style::default().fg(color(check.status))),
+                ]));
+            }
+            lines.push(Line::from(vec![
+                Span::raw("  Last Checked: "),
+                Span::styled(report.checked.clone(), Style::default().fg(Color::Gray)),
+            ]));
+            lines.push(Line::from(vec![Span::raw("")]));
+        }
         lines
     } else {
         vec![Line::from("Trust information not available")]
@@ -639,9 +912,9 @@ pub fn render_trust_info(f: &mut Frame, area: Rect, cert_manager: &CertManager)
     let paragraph = Paragraph::new(content);
     f.render_widget(paragraph, inner_area);
 
-    if let Some(store) = &cert_manager.trust_store {
+    if cert_manager.trust_info_len() > 0 {
         let mut scrollbar_state = ScrollbarState::default()
-            .content_length(store.len())
+            .content_length(cert_manager.trust_info_len())
             .viewport_content_length(area.height.saturating_sub(2) as usize)
             .position(cert_manager.trust_info_scroll);
 
@@ -51,6 +51,30 @@ impl Logger for FileLogger {
     }
 }
 
+// ConsoleLogger prints straight to stdout for non-interactive commands
+#[derive(Debug)]
+pub struct ConsoleLogger {
+    debug: bool,
+}
+
+impl ConsoleLogger {
+    pub fn new(debug: bool) -> Self {
+        Self { debug }
+    }
+}
+
+impl Logger for ConsoleLogger {
+    fn log(&mut self, message: &str) {
+        println!("{}", message);
+    }
+
+    fn debug_log(&mut self, message: &str) {
+        if self.debug {
+            println!("[DEBUG] {}", message);
+        }
+    }
+}
+
 // MultiLogger allows logging to multiple destinations
 pub struct MultiLogger {
     loggers: Vec<Box<dyn Logger>>,
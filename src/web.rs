@@ -1,12 +1,15 @@
 use axum::{
     debug_handler,
-    extract::State,
+    extract::{Path, Query, Request, State},
     http::{header, Method, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::{get, get_service},
+    body::Bytes,
+    routing::{get, get_service, post},
     Json, Router,
 };
-use serde::Serialize;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
@@ -14,18 +17,52 @@ use std::{
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
 use tower_http::services::ServeFile;
-use utoipa::{OpenApi, ToSchema};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
     app::{CertManager, CertStatus, ClusterInfo, ConnectivityStatus, NodeInfo},
-    discovery::{self, CertificateInfoSchema, NodeTrustInfoSchema},
+    cert::{
+        ocsp::{self, OcspError},
+        revocation::{self, RevocationError},
+        verification::{FileVerification, HostVerification},
+    },
+    discovery::{
+        self,
+        probe::{Component, EndpointProbe, PresentedCertificate},
+        CertificateInfoSchema, NodeTrustInfoSchema,
+    },
+    remote::{
+        self, BackupEntry, BackupManifest, CheckStatus, DistributionProgress, FileProgress,
+        HostKey, HostKeyState, HostKeyStatus, HostPreflight, HostProgress, HostState,
+        KnownHosts, PreflightCheck, ReloadOutcome, ReloadState, TransferState,
+    },
+    state::{
+        self, CertificateRecord, HostEventRecord, StateHistory, StateStore, VerificationRecord,
+    },
     types::{ApiServerMetrics, ControlPlaneMetrics, EtcdMetrics, SchedulerMetrics},
 };
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(cluster_handler, certificates_handler),
+    paths(
+        cluster_handler,
+        certificates_handler,
+        crl_handler,
+        ocsp_get_handler,
+        ocsp_post_handler,
+        distribution_handler,
+        tls_probe_handler,
+        preflight_handler,
+        history_handler,
+        backups_handler,
+        restore_handler,
+        host_keys_handler,
+        pin_host_key_handler
+    ),
     components(schemas(
         ClusterInfo,
         NodeInfo,
@@ -33,11 +70,52 @@ use crate::{
         ComponentInfo,
         CertificateDetail,
         WorkerNodeInfo,
-        NodeMetrics
-    ))
+        NodeMetrics,
+        DistributionProgress,
+        HostProgress,
+        FileProgress,
+        TransferState,
+        HostState,
+        ReloadOutcome,
+        ReloadState,
+        HostBackups,
+        BackupManifest,
+        BackupEntry,
+        HostKeyStatus,
+        HostKeyState,
+        HostKey,
+        PinRequest,
+        RestoreRequest,
+        HostVerification,
+        FileVerification,
+        EndpointProbe,
+        PresentedCertificate,
+        Component,
+        HostPreflight,
+        PreflightCheck,
+        CheckStatus,
+        StateHistory,
+        CertificateRecord,
+        HostEventRecord,
+        VerificationRecord
+    )),
+    modifiers(&ApiTokenScheme)
 )]
 struct ApiDoc;
 
+struct ApiTokenScheme;
+
+impl Modify for ApiTokenScheme {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "api_token",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
+
 // New structures for API responses
 #[derive(Serialize)]
 struct ControlPlaneInfo {
@@ -102,6 +180,8 @@ struct WorkerNodeInfo {
 #[derive(Serialize, ToSchema)]
 pub struct TrustValidationResponse {
     nodes: HashMap<String, NodeTrustInfoSchema>,
+    /// Per-host report of the last remote verification.
+    hosts: Vec<HostVerification>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -116,18 +196,89 @@ pub struct WebServerState {
     pub is_running: bool,
     pub port: u16,
     pub cert_manager: Option<Arc<RwLock<CertManager>>>,
+    /// Bearer token the endpoints changing nodes require.
+    pub api_token: ApiToken,
+}
+
+/// Taken from STARQUILL_API_TOKEN, or generated for each run.
+#[derive(Clone)]
+pub struct ApiToken {
+    pub value: String,
+    pub generated: bool,
+}
+
+impl Default for ApiToken {
+    fn default() -> Self {
+        Self {
+            value: uuid::Uuid::new_v4().simple().to_string(),
+            generated: true,
+        }
+    }
+}
+
+impl ApiToken {
+    pub fn load() -> Self {
+        match std::env::var("STARQUILL_API_TOKEN") {
+            Ok(value) if !value.is_empty() => Self {
+                value,
+                generated: false,
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// Where the token came from. Logged instead of the token, since the
+    /// log is shown in the TUI and written to files.
+    pub fn source(&self) -> &'static str {
+        if self.generated {
+            "generated at startup"
+        } else {
+            "STARQUILL_API_TOKEN"
+        }
+    }
 }
 
 impl WebServerState {
-    pub fn new(port: Option<u16>) -> Self {
+    pub fn new(port: Option<u16>, api_token: ApiToken) -> Self {
         Self {
             port: port.unwrap_or(3000), // Default to port 3000 if none specified
             is_running: false,
             cert_manager: None,
+            api_token,
         }
     }
 }
 
+// Endpoints that write to nodes are reachable from anywhere the server
+// listens, so they require `Authorization: Bearer <api_token>`. A custom
+// header also keeps browsers from sending them cross-site without a CORS
+// preflight, which only allows GET.
+async fn require_api_token(
+    State(state): State<Arc<RwLock<WebServerState>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let expected = state.read().unwrap().api_token.value.clone();
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    let authorized = presented.len() == expected.len()
+        && openssl::memcmp::eq(presented.as_bytes(), expected.as_bytes());
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": "Missing or invalid API token" })),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
 // Helper function to create component metrics
 fn create_component_metrics<T: std::fmt::Debug>(_component_metrics: Option<&T>) -> ComponentMetrics {
     // Use type-specific logic if needed
@@ -436,6 +587,14 @@ async fn cluster_handler(State(state): State<Arc<RwLock<WebServerState>>>) -> Re
                         .distributed
                         .or(Some(c.generated))
                         .map(|dt| dt.to_rfc3339()),
+                    sha256: c
+                        .checksums
+                        .get(&manager.config.control_plane)
+                        .and_then(|sum| sum.sha256.clone()),
+                    checksum_matches: c
+                        .checksums
+                        .get(&manager.config.control_plane)
+                        .map(|sum| sum.matches),
                 })
                 .collect(),
         },
@@ -462,6 +621,8 @@ async fn cluster_handler(State(state): State<Arc<RwLock<WebServerState>>>) -> Re
                             .distributed
                             .or(Some(c.generated))
                             .map(|dt| dt.to_rfc3339()),
+                        sha256: c.checksums.get(ip).and_then(|sum| sum.sha256.clone()),
+                        checksum_matches: c.checksums.get(ip).map(|sum| sum.matches),
                     })
                     .collect(),
             })
@@ -591,6 +752,7 @@ async fn trust_validation_handler(State(state): State<Arc<RwLock<WebServerState>
                     permissions_valid: v.permissions_valid,
                     expiring_soon: v.expiring_soon.clone(),
                     last_checked: v.last_checked.to_rfc3339(),
+                    endpoints: v.endpoints.clone(),
                 },
             )
         })
@@ -598,6 +760,7 @@ async fn trust_validation_handler(State(state): State<Arc<RwLock<WebServerState>
 
     let response = TrustValidationResponse {
         nodes: converted_store,
+        hosts: manager.verification.clone(),
     };
 
     (
@@ -610,13 +773,627 @@ async fn trust_validation_handler(State(state): State<Arc<RwLock<WebServerState>
         .into_response()
 }
 
+// Handler for /api/tls-probe
+#[utoipa::path(
+    get,
+    path = "/api/tls-probe",
+    responses(
+        (status = 200, description = "What each component served when the endpoints were last probed", body = [EndpointProbe]),
+        (status = 404, description = "No probe has been run")
+    )
+)]
+async fn tls_probe_handler(State(state): State<Arc<RwLock<WebServerState>>>) -> Response {
+    let cert_manager = {
+        let state_guard = state.read().unwrap();
+        match state_guard.cert_manager.as_ref() {
+            Some(cm) => cm.clone(),
+            None => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Json(serde_json::json!({
+                        "error": "CertManager not initialized"
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    let probes = cert_manager.read().unwrap().probes.clone();
+
+    if probes.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": "No probe has been run" })),
+        )
+            .into_response();
+    }
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        Json(serde_json::json!({ "data": probes })),
+    )
+        .into_response()
+}
+
+// Handler for /api/preflight
+#[utoipa::path(
+    get,
+    path = "/api/preflight",
+    responses(
+        (status = 200, description = "Per-node report of the last preflight checks", body = [HostPreflight]),
+        (status = 404, description = "No preflight has been run")
+    )
+)]
+async fn preflight_handler(State(state): State<Arc<RwLock<WebServerState>>>) -> Response {
+    let cert_manager = {
+        let state_guard = state.read().unwrap();
+        match state_guard.cert_manager.as_ref() {
+            Some(cm) => cm.clone(),
+            None => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Json(serde_json::json!({
+                        "error": "CertManager not initialized"
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    let preflight = cert_manager.read().unwrap().preflight.clone();
+
+    if preflight.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": "No preflight has been run" })),
+        )
+            .into_response();
+    }
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        Json(serde_json::json!({ "data": preflight })),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,
+}
+
+// Handler for /api/history
+#[utoipa::path(
+    get,
+    path = "/api/history",
+    params(
+        ("limit" = Option<usize>, Query, description = "Entries per history, newest first (default 200)")
+    ),
+    responses(
+        (status = 200, description = "Certificates issued or adopted, host events and verification results from the state database", body = StateHistory),
+        (status = 500, description = "The state database could not be read")
+    )
+)]
+async fn history_handler(Query(query): Query<HistoryQuery>) -> Response {
+    let history = StateStore::open(std::path::Path::new(state::DEFAULT_PATH))
+        .and_then(|store| store.history(query.limit.unwrap_or(200)));
+
+    match history {
+        Ok(history) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "data": history })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+// Handler for /api/distribution
+#[utoipa::path(
+    get,
+    path = "/api/distribution",
+    responses(
+        (status = 200, description = "Per-host and per-file progress of the current or last distribution", body = DistributionProgress),
+        (status = 404, description = "No distribution has been started")
+    )
+)]
+async fn distribution_handler(State(state): State<Arc<RwLock<WebServerState>>>) -> Response {
+    let cert_manager = {
+        let state_guard = state.read().unwrap();
+        match state_guard.cert_manager.as_ref() {
+            Some(cm) => cm.clone(),
+            None => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Json(serde_json::json!({
+                        "error": "CertManager not initialized"
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    let progress = cert_manager.read().unwrap().distribution_progress();
+
+    match progress {
+        Some(progress) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "data": progress })),
+        )
+            .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": "No distribution has been started" })),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+struct HostBackups {
+    host: String,
+    backups: Vec<BackupManifest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// Handler for /api/backups
+#[utoipa::path(
+    get,
+    path = "/api/backups",
+    responses(
+        (status = 200, description = "Backups kept on each host, oldest first", body = [HostBackups])
+    )
+)]
+async fn backups_handler(State(state): State<Arc<RwLock<WebServerState>>>) -> Response {
+    let cert_manager = {
+        let state_guard = state.read().unwrap();
+        match state_guard.cert_manager.as_ref() {
+            Some(cm) => cm.clone(),
+            None => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Json(serde_json::json!({
+                        "error": "CertManager not initialized"
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    let (transport, backup_dir, hosts) = {
+        let manager = cert_manager.read().unwrap();
+        (
+            manager.transport(),
+            manager.config.distribution.backup_dir.clone(),
+            manager.get_all_hosts(),
+        )
+    };
+
+    // Listing talks to every host, so keep it off the async workers
+    let listing = tokio::task::spawn_blocking(move || {
+        hosts
+            .into_iter()
+            .map(
+                |host| match remote::list_backups(transport.as_ref(), &host, &backup_dir) {
+                    Ok(backups) => HostBackups {
+                        host,
+                        backups,
+                        error: None,
+                    },
+                    Err(e) => HostBackups {
+                        host,
+                        backups: Vec::new(),
+                        error: Some(e.to_string()),
+                    },
+                },
+            )
+            .collect::<Vec<_>>()
+    })
+    .await;
+
+    match listing {
+        Ok(response) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "data": response })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct RestoreRequest {
+    /// The generation from the path again, confirming the restore.
+    generation: String,
+}
+
+// Handler for /api/backups/{host}/{generation}/restore
+#[utoipa::path(
+    post,
+    path = "/api/backups/{host}/{generation}/restore",
+    params(
+        ("host" = String, Path, description = "Host the backup was taken on"),
+        ("generation" = String, Path, description = "Generation to restore, e.g. 20240101T120000Z")
+    ),
+    request_body(content = RestoreRequest, description = "The generation to restore, confirmed"),
+    responses(
+        (status = 200, description = "Files restored to their state before the distribution", body = BackupManifest),
+        (status = 400, description = "The generation in the body differs from the path"),
+        (status = 401, description = "Missing or invalid API token"),
+        (status = 404, description = "Unknown host or no such backup on it"),
+        (status = 409, description = "A distribution is running")
+    ),
+    security(("api_token" = []))
+)]
+async fn restore_handler(
+    State(state): State<Arc<RwLock<WebServerState>>>,
+    Path((host, generation)): Path<(String, String)>,
+    Json(request): Json<RestoreRequest>,
+) -> Response {
+    if request.generation != generation {
+        return (
+            StatusCode::BAD_REQUEST,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({
+                "error": format!("Confirm the restore with {{\"generation\": \"{}\"}}", generation)
+            })),
+        )
+            .into_response();
+    }
+
+    let cert_manager = {
+        let state_guard = state.read().unwrap();
+        match state_guard.cert_manager.as_ref() {
+            Some(cm) => cm.clone(),
+            None => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Json(serde_json::json!({
+                        "error": "CertManager not initialized"
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    let (transport, backup_dir) = {
+        let manager = cert_manager.read().unwrap();
+        if manager.is_distributing() {
+            return (
+                StatusCode::CONFLICT,
+                [(header::CONTENT_TYPE, "application/json")],
+                Json(serde_json::json!({ "error": "A distribution is running" })),
+            )
+                .into_response();
+        }
+        if !manager.get_all_hosts().contains(&host) {
+            return (
+                StatusCode::NOT_FOUND,
+                [(header::CONTENT_TYPE, "application/json")],
+                Json(serde_json::json!({ "error": format!("{} is not a cluster host", host) })),
+            )
+                .into_response();
+        }
+        (
+            manager.transport(),
+            manager.config.distribution.backup_dir.clone(),
+        )
+    };
+
+    let restored = tokio::task::spawn_blocking(move || {
+        remote::restore_generation(transport.as_ref(), &host, &backup_dir, &generation)
+    })
+    .await
+    .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    match restored {
+        Ok(manifest) => {
+            cert_manager.write().unwrap().note_restored(&manifest);
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/json")],
+                Json(serde_json::json!({ "data": manifest })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            let status = match e.kind() {
+                std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                std::io::ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (
+                status,
+                [(header::CONTENT_TYPE, "application/json")],
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+// Handler for /api/host-keys
+#[utoipa::path(
+    get,
+    path = "/api/host-keys",
+    responses(
+        (status = 200, description = "Pinned and presented SSH host key of every host", body = [HostKeyStatus])
+    )
+)]
+async fn host_keys_handler(State(state): State<Arc<RwLock<WebServerState>>>) -> Response {
+    let cert_manager = {
+        let state_guard = state.read().unwrap();
+        match state_guard.cert_manager.as_ref() {
+            Some(cm) => cm.clone(),
+            None => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Json(serde_json::json!({
+                        "error": "CertManager not initialized"
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    let statuses = {
+        let manager = cert_manager.read().unwrap();
+        KnownHosts::shared(&manager.config.host_keys).statuses(&manager.get_all_hosts())
+    };
+
+    match statuses {
+        Ok(response) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "data": response })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct PinRequest {
+    /// Fingerprint the host is expected to present, checked out of band;
+    /// pinning fails if it presents another one.
+    fingerprint: String,
+}
+
+// Handler for /api/host-keys/{host}/pin
+#[utoipa::path(
+    post,
+    path = "/api/host-keys/{host}/pin",
+    params(
+        ("host" = String, Path, description = "Host whose presented key to pin")
+    ),
+    request_body(content = PinRequest, description = "The fingerprint the host must present"),
+    responses(
+        (status = 200, description = "Key pinned in the known-hosts store", body = HostKey),
+        (status = 400, description = "The host presented another fingerprint"),
+        (status = 401, description = "Missing or invalid API token"),
+        (status = 404, description = "Unknown host, or it presents its pinned key"),
+        (status = 409, description = "The key is pinned in cluster_config.json")
+    ),
+    security(("api_token" = []))
+)]
+async fn pin_host_key_handler(
+    State(state): State<Arc<RwLock<WebServerState>>>,
+    Path(host): Path<String>,
+    Json(request): Json<PinRequest>,
+) -> Response {
+    let cert_manager = {
+        let state_guard = state.read().unwrap();
+        match state_guard.cert_manager.as_ref() {
+            Some(cm) => cm.clone(),
+            None => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    Json(serde_json::json!({
+                        "error": "CertManager not initialized"
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    let (transport, known_hosts) = {
+        let manager = cert_manager.read().unwrap();
+        if !manager.get_all_hosts().contains(&host) {
+            return (
+                StatusCode::NOT_FOUND,
+                [(header::CONTENT_TYPE, "application/json")],
+                Json(serde_json::json!({ "error": format!("{} is not a cluster host", host) })),
+            )
+                .into_response();
+        }
+        (
+            manager.transport(),
+            KnownHosts::shared(&manager.config.host_keys),
+        )
+    };
+    let fingerprint = request.fingerprint;
+
+    let pinned = tokio::task::spawn_blocking(move || {
+        match known_hosts.trust(&host, &fingerprint) {
+            // Connect once to learn the key the host presents now
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let _ = transport.exec(&host, "true");
+                known_hosts.trust(&host, &fingerprint)
+            }
+            result => result,
+        }
+    })
+    .await
+    .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    match pinned {
+        Ok(key) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "data": key })),
+        )
+            .into_response(),
+        Err(e) => {
+            let status = match e.kind() {
+                std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                std::io::ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+                std::io::ErrorKind::PermissionDenied => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (
+                status,
+                [(header::CONTENT_TYPE, "application/json")],
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CrlQuery {
+    format: Option<String>,
+}
+
+// Handler for /api/crl/{ca}
+#[utoipa::path(
+    get,
+    path = "/api/crl/{ca}",
+    params(
+        ("ca" = String, Path, description = "CA directory name, e.g. kubernetes-ca"),
+        ("format" = Option<String>, Query, description = "der (default) or pem")
+    ),
+    responses(
+        (status = 200, description = "Latest signed CRL", content_type = "application/pkix-crl"),
+        (status = 404, description = "No CRL published for this CA")
+    )
+)]
+async fn crl_handler(Path(ca): Path<String>, Query(query): Query<CrlQuery>) -> Response {
+    let crl = match revocation::load_crl(&ca) {
+        Ok(crl) => crl,
+        Err(e) => {
+            let status = match e {
+                RevocationError::UnknownCertificate(_) => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return (
+                status,
+                [(header::CONTENT_TYPE, "application/json")],
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    };
+
+    let encoded = if query.format.as_deref() == Some("pem") {
+        crl.to_pem().map(|pem| ("application/x-pem-file", pem))
+    } else {
+        crl.to_der().map(|der| ("application/pkix-crl", der))
+    };
+
+    match encoded {
+        Ok((content_type, body)) => {
+            (StatusCode::OK, [(header::CONTENT_TYPE, content_type)], body).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "application/json")],
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Answers an OCSP request, turning failures into unsigned error responses.
+/// OCSP errors travel in the response body, so the HTTP status is always 200.
+fn ocsp_response(request: Result<Vec<u8>, OcspError>) -> Response {
+    let body = match request.and_then(|der| ocsp::respond(&der)) {
+        Ok(body) => body,
+        Err(e) => e.to_response(),
+    };
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/ocsp-response")],
+        body,
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/ocsp/{request}",
+    params(
+        ("request" = String, Path, description = "Base64 encoded DER OCSPRequest (RFC 6960 A.1)")
+    ),
+    responses(
+        (status = 200, description = "DER encoded OCSPResponse", content_type = "application/ocsp-response")
+    )
+)]
+async fn ocsp_get_handler(Path(request): Path<String>) -> Response {
+    // Clients differ on padding and on the standard vs URL-safe alphabet
+    let encoded = request.trim_end_matches('=');
+    let der = general_purpose::STANDARD_NO_PAD
+        .decode(encoded)
+        .or_else(|_| general_purpose::URL_SAFE_NO_PAD.decode(encoded))
+        .map_err(|e| OcspError::Malformed(e.to_string()));
+    ocsp_response(der)
+}
+
+#[utoipa::path(
+    post,
+    path = "/ocsp",
+    request_body(content = Vec<u8>, description = "DER encoded OCSPRequest", content_type = "application/ocsp-request"),
+    responses(
+        (status = 200, description = "DER encoded OCSPResponse", content_type = "application/ocsp-response")
+    )
+)]
+async fn ocsp_post_handler(body: Bytes) -> Response {
+    ocsp_response(Ok(body.to_vec()))
+}
+
 pub async fn start_web_server(
     state: Arc<RwLock<WebServerState>>,
     shutdown: tokio::sync::oneshot::Receiver<()>,
 ) {
-    let port = {
+    let (port, api_token) = {
         let state = state.read().unwrap();
-        state.port
+        (state.port, state.api_token.clone())
     };
 
     let app = Router::new()
@@ -627,6 +1404,27 @@ pub async fn start_web_server(
         .route("/api/certificates", get(certificates_handler))
         .route("/api/debug/certificates", get(debug_certificates))
         .route("/api/trust-validate", get(trust_validation_handler))
+        .route("/api/distribution", get(distribution_handler))
+        .route("/api/tls-probe", get(tls_probe_handler))
+        .route("/api/preflight", get(preflight_handler))
+        .route("/api/history", get(history_handler))
+        .route("/api/backups", get(backups_handler))
+        .route("/api/host-keys", get(host_keys_handler))
+        .merge(
+            Router::new()
+                .route(
+                    "/api/backups/:host/:generation/restore",
+                    post(restore_handler),
+                )
+                .route("/api/host-keys/:host/pin", post(pin_host_key_handler))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    require_api_token,
+                )),
+        )
+        .route("/api/crl/:ca", get(crl_handler))
+        .route("/ocsp", post(ocsp_post_handler))
+        .route("/ocsp/*request", get(ocsp_get_handler))
         .nest_service(
             "/",
             get_service(
@@ -648,6 +1446,10 @@ pub async fn start_web_server(
             if let Some(ref cm) = state.read().unwrap().cert_manager.as_ref() {
                 let mut manager = cm.write().unwrap();
                 manager.log(&format!("Web server listening on {}", addr));
+                manager.log(&format!(
+                    "Restore and pin endpoints require the header Authorization: Bearer <token>, token {}",
+                    api_token.source()
+                ));
                 // manager.log("Available endpoints:");
                 // manager.log("  - /health");
                 // manager.log("  - /api/cluster");